@@ -0,0 +1,93 @@
+use crate::i18n;
+use crate::svg::escape;
+use crate::{ics, iso_week_key, CareWeek, Config};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::{self, Write};
+
+// Ids stay the same when a week changes hands, so readers update the entry
+// instead of adding another one.
+fn feed_id(conf: &Config) -> String {
+    format!(
+        "urn:whocares:{}",
+        ics::slug(conf.name.as_deref().unwrap_or("whocares"))
+    )
+}
+
+// An Atom feed with an entry per week, for readers and portals that don't
+// do calendars.
+pub fn write_feed(
+    out: &mut impl Write,
+    conf: &Config,
+    weeks: impl Iterator<Item = CareWeek>,
+    stamp: DateTime<Utc>,
+) -> io::Result<()> {
+    let id = feed_id(conf);
+    let updated = stamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+    write!(
+        out,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="{}">
+<id>{id}</id>
+<title>{}</title>
+<updated>{updated}</updated>
+<author><name>whocares</name></author>
+<generator>whocares</generator>
+"#,
+        conf.formatting.locale.code(),
+        escape(conf.name.as_deref().unwrap_or("whocares"))
+    )?;
+    for week in weeks {
+        let caretakers = match &week.suspended {
+            Some(blackout) => i18n::format(
+                conf.formatting.locale,
+                "suspended",
+                &[("blackout", blackout)],
+            ),
+            None => format!("{}{}", week.caretakers(), week.overlap_note()),
+        };
+        writeln!(
+            out,
+            "<entry>\n<id>{id}:{}</id>\n<title>{}</title>\n<updated>{updated}</updated>\n<summary>{}</summary>\n</entry>",
+            iso_week_key(week.start_date),
+            escape(&i18n::week_line(&conf.formatting, &week)),
+            escape(&format!("{caretakers}{}", week.holiday_note()))
+        )?;
+    }
+    writeln!(out, "</feed>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_weeks;
+    use chrono::{NaiveDate, TimeZone};
+    use std::str::FromStr;
+
+    #[test]
+    fn feed_has_an_entry_per_week() {
+        let conf = Config {
+            name: Some("Cat & Dog".to_string()),
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            ..Default::default()
+        };
+        let stamp = Utc.with_ymd_and_hms(2024, 7, 1, 8, 0, 0).unwrap();
+        let mut feed = Vec::new();
+        write_feed(
+            &mut feed,
+            &conf,
+            get_weeks(&conf, conf.startdate, 2).into_iter(),
+            stamp,
+        )
+        .unwrap();
+        let feed = String::from_utf8(feed).unwrap();
+        assert!(
+            feed.contains("<title>Cat &amp; Dog</title>\n<updated>2024-07-01T08:00:00Z</updated>")
+        );
+        assert_eq!(feed.matches("<entry>").count(), 2);
+        assert!(feed.contains(
+            "<entry>\n<id>urn:whocares:cat---dog:2024-W28</id>\n<title>week #28 2024-07-08 - 2024-07-14: Jimmy</title>\n"
+        ));
+        assert!(feed.ends_with("</feed>\n"));
+    }
+}
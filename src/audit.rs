@@ -0,0 +1,133 @@
+#[cfg(feature = "sqlite")]
+use crate::store::{quote, Db};
+use serde_json::{json, Value};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+const FILE: &str = "audit.log";
+
+// Top-level settings that differ, descending one level into objects so a
+// swap shows up as the reschedule entries it touched.
+pub fn changes(before: &Value, after: &Value) -> Vec<Value> {
+    let empty = serde_json::Map::new();
+    let keys = |v: &Value| {
+        v.as_object()
+            .unwrap_or(&empty)
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let mut all = keys(before);
+    all.extend(keys(after));
+    all.sort();
+    all.dedup();
+
+    let mut changes = Vec::new();
+    for key in all {
+        let (old, new) = (&before[&key], &after[&key]);
+        if old == new {
+            continue;
+        }
+        if old.is_object() || new.is_object() {
+            for change in self::changes(old, new) {
+                let sub_key = change["key"].as_str().unwrap_or_default();
+                changes.push(json!({
+                    "key": format!("{key}.{sub_key}"),
+                    "old": change["old"],
+                    "new": change["new"],
+                }));
+            }
+        } else {
+            changes.push(json!({ "key": key, "old": old, "new": new }));
+        }
+    }
+    changes
+}
+
+fn user() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+// Appends one JSON line per mutation to audit.log next to the config file,
+// or a row to the configured database.
+pub fn append(config_path: &str, action: &str, before: &Value, after: &Value) -> io::Result<()> {
+    let changes = changes(before, after);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let (time, user) = (chrono::Local::now().to_rfc3339(), user());
+    #[cfg(feature = "sqlite")]
+    if let Some(database) = after["database"].as_str() {
+        return Db::open(config_path, database)
+            .run(&format!(
+                "INSERT INTO audit VALUES ({}, {}, {}, {});",
+                quote(&time),
+                quote(&user),
+                quote(action),
+                quote(&Value::from(changes).to_string())
+            ))
+            .map(drop);
+    }
+    let entry = json!({
+        "time": time,
+        "user": user,
+        "action": action,
+        "changes": changes,
+    });
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(Path::new(config_path).with_file_name(FILE))?;
+    writeln!(file, "{entry}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_works() {
+        let before = json!({
+            "caretakers": ["A", "B"],
+            "reschedule": { "2024-5": "A" },
+        });
+        let after = json!({
+            "caretakers": ["A", "B"],
+            "reschedule": { "2024-5": "B", "2024-6": "A" },
+        });
+        assert_eq!(
+            changes(&before, &after),
+            vec![
+                json!({ "key": "reschedule.2024-5", "old": "A", "new": "B" }),
+                json!({ "key": "reschedule.2024-6", "old": null, "new": "A" }),
+            ]
+        );
+        assert!(changes(&before, &before).is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn append_to_database_works() {
+        let dir = std::env::temp_dir().join(format!("whocares-audit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        let config = config.to_str().unwrap();
+        let before = json!({ "database": "whocares.db", "caretakers": ["A"] });
+        let after = json!({ "database": "whocares.db", "caretakers": ["A", "B"] });
+        append(config, "add", &before, &after).unwrap();
+
+        let rows = Db::open(config, "whocares.db")
+            .run("SELECT action, changes FROM audit;")
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["action"], "add");
+        let changes: Value = serde_json::from_str(rows[0]["changes"].as_str().unwrap()).unwrap();
+        assert_eq!(changes[0]["key"], "caretakers");
+        assert!(!dir.join(FILE).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use crate::logging::warning;
+use crate::{http, ics};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub use whocares_core::rotation::Unavailability;
+
+// The config is loaded several times a run and every minute by the daemon,
+// remote calendars needn't be fetched each time.
+const FETCH_TTL: Duration = Duration::from_secs(10 * 60);
+
+static FETCHED: Mutex<BTreeMap<String, (Instant, String)>> = Mutex::new(BTreeMap::new());
+
+// Failures aren't kept, the next load tries again.
+fn cached(source: &str, fetch: impl FnOnce() -> io::Result<String>) -> io::Result<String> {
+    let mut fetched = FETCHED.lock().unwrap_or_else(|e| e.into_inner());
+    match fetched.get(source) {
+        Some((at, body)) if at.elapsed() < FETCH_TTL => Ok(body.clone()),
+        _ => {
+            let body = fetch()?;
+            fetched.insert(source.to_string(), (Instant::now(), body.clone()));
+            Ok(body)
+        }
+    }
+}
+
+fn read_source(config_path: &str, source: &str) -> io::Result<String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        cached(source, || {
+            let response = http::Request {
+                method: "GET",
+                url: source,
+                ..Default::default()
+            }
+            .send()?
+            .error_for_status()?;
+            Ok(response.body)
+        })
+    } else {
+        fs::read_to_string(Path::new(config_path).with_file_name(source))
+    }
+}
+
+pub fn load(config_path: &str, sources: &HashMap<String, Vec<String>>) -> Unavailability {
+    let mut unavailable = Unavailability::new();
+    for (caretaker, calendars) in sources {
+        for source in calendars {
+            match read_source(config_path, source) {
+                Ok(calendar) => unavailable.entry(caretaker.clone()).or_default().extend(
+                    ics::parse_events(&calendar)
+                        .into_iter()
+                        .filter(|e| !e.transparent)
+                        .filter_map(|e| e.full_days()),
+                ),
+                Err(e) => warning!("failed to load availability {source}: {e}"),
+            }
+        }
+    }
+    unavailable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_calendars_are_cached() {
+        let source = "https://example.com/cached.ics";
+        assert!(cached(source, || Err(io::Error::other("offline"))).is_err());
+        assert_eq!(cached(source, || Ok("first".to_string())).unwrap(), "first");
+        assert_eq!(
+            cached(source, || Ok("second".to_string())).unwrap(),
+            "first"
+        );
+    }
+
+    #[test]
+    fn short_events_leave_the_week_available() {
+        let dir =
+            std::env::temp_dir().join(format!("whocares-availability-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        fs::write(
+            dir.join("edgar.ics"),
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\nDTSTART:20240702T090000\r\nDTEND:20240702T110000\r\nSUMMARY:Dentist\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20240710\r\nDTEND;VALUE=DATE:20240711\r\nSUMMARY:Off\r\nEND:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        )
+        .unwrap();
+        let sources = HashMap::from([("Edgar".to_string(), vec!["edgar.ics".to_string()])]);
+        let unavailable = load(config.to_str().unwrap(), &sources);
+        let monday = |s: &str| s.parse().unwrap();
+        let available = |week| whocares_core::rotation::is_available(&unavailable, "Edgar", week);
+        assert!(available(monday("2024-07-01")));
+        assert!(!available(monday("2024-07-08")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
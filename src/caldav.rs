@@ -0,0 +1,78 @@
+use crate::dry_run;
+use crate::sync::{SyncState, SyncedEvent};
+use crate::{http, ics, CareWeek, Config};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+
+const PASSWORD_ENV: &str = "WHOCARES_CALDAV_PASSWORD";
+const STATE_FILE: &str = "caldav-state.json";
+
+#[derive(Deserialize, Default)]
+pub struct CalDavConfig {
+    pub url: Option<String>,
+    #[serde(default)]
+    username: String,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+impl CalDavConfig {
+    fn password(&self) -> String {
+        env::var(PASSWORD_ENV)
+            .ok()
+            .or_else(|| self.password.clone())
+            .unwrap_or_default()
+    }
+}
+
+pub fn publish(config: &Config, config_path: &str, weeks: &[CareWeek]) -> io::Result<()> {
+    let conf = &config.caldav;
+    let Some(url) = conf.url.as_deref() else {
+        return Err(io::Error::other("no caldav url configured"));
+    };
+    let url = url.trim_end_matches('/');
+    let password = conf.password();
+    let auth = (!conf.username.is_empty()).then_some((conf.username.as_str(), password.as_str()));
+    let stamp = chrono::Utc::now();
+
+    // A resource per event, split weeks, overlaps and shifts have several.
+    let mut current = BTreeMap::new();
+    for week in weeks {
+        for (uid, body) in ics::resources(config, week, stamp) {
+            http::Request {
+                method: "PUT",
+                url: &format!("{url}/{uid}.ics"),
+                headers: vec![("Content-Type", "text/calendar; charset=utf-8".to_string())],
+                basic_auth: auth,
+                body: Some(&body),
+                ..Default::default()
+            }
+            .send()?
+            .error_for_status()?;
+            println!("{}updated {uid}", dry_run::prefix());
+            current.insert(uid, SyncedEvent::new(week.start_date, String::new()));
+        }
+    }
+
+    let mut state = SyncState::load(config_path, STATE_FILE, config.database.as_deref())?;
+    let from = weeks.first().map_or(NaiveDate::MAX, |w| w.start_date);
+    for (uid, _) in state.stale(&current, from) {
+        let response = http::Request {
+            method: "DELETE",
+            url: &format!("{url}/{uid}.ics"),
+            basic_auth: auth,
+            ..Default::default()
+        }
+        .send()?;
+        if response.status != 404 {
+            response.error_for_status()?;
+        }
+        println!("{}deleted {uid}", dry_run::prefix());
+    }
+
+    state.events = current;
+    state.save()
+}
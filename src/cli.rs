@@ -0,0 +1,369 @@
+use crate::exit;
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct Command {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub about: &'static str,
+}
+
+pub struct Flag {
+    pub flag: &'static str,
+    pub about: &'static str,
+}
+
+// The subcommands of whocares. Usage messages and man pages are built from
+// these, so new commands belong here.
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "",
+        args: "[WEEKS]",
+        about: "List the caretakers of the next WEEKS weeks, 4 by default.",
+    },
+    Command {
+        name: "serve",
+        args: "[ADDR]",
+        about: "Serve the dashboard, calendar feeds, JSON API and Slack command.",
+    },
+    Command {
+        name: "daemon",
+        args: "",
+        about: "Send handoff and reminder notifications when they are due.",
+    },
+    Command {
+        name: "notify",
+        args: "[handoff|reminder]",
+        about: "Send a notification now.",
+    },
+    Command {
+        name: "webcal",
+        args: "",
+        about: "Print calendar subscription links.",
+    },
+    Command {
+        name: "import",
+        args: "ics|roster <file>",
+        about:
+            "Turn the events of an ICS file into reschedules, or add the caretakers of a CSV file.",
+    },
+    Command {
+        name: "sync",
+        args: "ldap",
+        about: "Add the members of the LDAP group to the caretakers and flag those who left.",
+    },
+    Command {
+        name: "stats",
+        args: "[ytd | year [YYYY] | FROM TO]",
+        about: "Count the weeks served by each caretaker.",
+    },
+    Command {
+        name: "record",
+        args: "<YYYY-Www> <caretaker>",
+        about: "Record who actually covered a week.",
+    },
+    Command {
+        name: "forecast",
+        args: "[COUNT]",
+        about: "List the next COUNT duty weeks of every caretaker.",
+    },
+    Command {
+        name: "plan",
+        args: "[WEEKS]",
+        about: "Plan the next WEEKS weeks under the plan rules and write them as reschedules, review with --dry-run.",
+    },
+    Command {
+        name: "grid",
+        args: "[WEEKS]",
+        about: "Show who has which weekday in the next WEEKS weeks as a table.",
+    },
+    Command {
+        name: "next-for",
+        args: "<caretaker>|--me [COUNT]",
+        about: "List the next COUNT duty weeks of one caretaker.",
+    },
+    Command {
+        name: "until-my-turn",
+        args: "<caretaker>|--me",
+        about: "Tell how many days are left until the next turn.",
+    },
+    Command {
+        name: "on-duty",
+        args: "<caretaker>|--me [-v]",
+        about: "Exit with 0 if the caretaker is on duty this week and 1 otherwise.",
+    },
+    Command {
+        name: "diff",
+        args: "<old.json> <new.json> [WEEKS]",
+        about: "Show the weeks that change caretaker between two configs.",
+    },
+    Command {
+        name: "changes",
+        args: "",
+        about: "Show what changed in the schedule since the last run.",
+    },
+    Command {
+        name: "roster",
+        args: "add|remove <caretaker> [WEEKS]",
+        about: "Change the roster, keeping the caretakers of the next WEEKS weeks, 4 by default, and reflowing the rest.",
+    },
+    Command {
+        name: "reschedule",
+        args: "add <YYYY-Www> <caretaker>",
+        about: "Assign a week to another caretaker.",
+    },
+    Command {
+        name: "swap",
+        args: "<YYYY-Www> <YYYY-Www>",
+        about: "Trade the caretakers of two weeks.",
+    },
+    Command {
+        name: "swaps",
+        args: "propose <YYYY-Www> <YYYY-Www> | approve|decline <YYYY-Www> | list",
+        about: "Propose a swap that only happens once whoever has the second week approves it.",
+    },
+    Command {
+        name: "prune",
+        args: "",
+        about: "Remove reschedules of past weeks.",
+    },
+    Command {
+        name: "edit",
+        args: "",
+        about: "Edit the config in $EDITOR and validate it before saving.",
+    },
+    Command {
+        name: "tui",
+        args: "",
+        about: "Browse the calendar interactively.",
+    },
+    Command {
+        name: "doctor",
+        args: "",
+        about: "Check the config and the environment.",
+    },
+    Command {
+        name: "credits",
+        args: "",
+        about: "Show the credit balance of every caretaker.",
+    },
+    Command {
+        name: "fairness",
+        args: "[ytd | year [YYYY] | FROM TO]",
+        about: "Rate how evenly duty is shared and suggest swaps.",
+    },
+    Command {
+        name: "export",
+        args: "ics|csv|jsonl|svg|html|atom|pagerduty [WEEKS]",
+        about: "Print the schedule as an ICS calendar, CSV, JSON lines, an SVG calendar graphic, a page to print, an Atom feed or PagerDuty schedule.",
+    },
+    Command {
+        name: "export-all",
+        args: "ics|csv|jsonl|svg|html|atom[,...] DIR [WEEKS]",
+        about: "Write DIR/<profile>.<format> for every profile, in parallel.",
+    },
+    Command {
+        name: "publish",
+        args: "caldav|outlook|pagerduty|opsgenie|mqtt|github [WEEKS]",
+        about: "Sync the schedule to a remote calendar, paging service or GitHub issue.",
+    },
+];
+
+pub const OPTIONS: &[Flag] = &[
+    Flag {
+        flag: "--format <template>",
+        about: "Print weeks as template with {week}, {iso_week}, {start}, {end}, {caretaker}, {holidays} and {block}.",
+    },
+    Flag {
+        flag: "--weeks <n>",
+        about: "Show this many weeks, in place of a WEEKS argument.",
+    },
+    Flag {
+        flag: "--group-by month",
+        about: "Show a header and how many weeks everyone has for each month.",
+    },
+    Flag {
+        flag: "--summary",
+        about: "End with how many of the shown weeks everyone has.",
+    },
+    Flag {
+        flag: "--caretaker <caretaker>",
+        about: "Only show the weeks of this caretaker.",
+    },
+    Flag {
+        flag: "--me",
+        about: "Act for the caretaker of the invoking user, see WHOCARES_ME.",
+    },
+    Flag {
+        flag: "--profile <name>",
+        about: "Use ~/.config/whocares/profiles/<name>.json as config.",
+    },
+    Flag {
+        flag: "--dry-run",
+        about: "Show what would be written or sent without doing it.",
+    },
+    Flag {
+        flag: "--watch",
+        about: "Keep the output up to date.",
+    },
+    Flag {
+        flag: "-v, -vv, -vvv",
+        about: "Log more, on top of RUST_LOG.",
+    },
+    Flag {
+        flag: "-q",
+        about: "Only log errors.",
+    },
+    Flag {
+        flag: "-h, --help",
+        about: "Show the commands and options.",
+    },
+];
+
+const EXIT_CODES: &[(i32, &str)] = &[
+    (0, "Success."),
+    (exit::FAILURE, "Failure, or not on duty for on-duty."),
+    (exit::USAGE, "Invalid arguments."),
+    (exit::CONFIG_NOT_FOUND, "The config file was not found."),
+    (exit::CONFIG_INVALID, "The config file is invalid."),
+    (exit::NO_CARETAKER, "Nobody is assigned."),
+    (
+        exit::NOTIFICATION_FAILED,
+        "A notification could not be sent.",
+    ),
+    (
+        exit::PUBLISH_FAILED,
+        "Publishing to a remote calendar failed.",
+    ),
+];
+
+fn command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+pub fn synopsis(name: &str) -> String {
+    let line = command(name).map_or(String::new(), |c| format!("{} {}", c.name, c.args));
+    format!("whocares {}", line.trim())
+}
+
+fn roff(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('-', "\\-")
+}
+
+fn header(title: &str, date: NaiveDate) -> String {
+    format!(
+        ".TH {} 1 \"{date}\" \"whocares {}\" \"User Commands\"\n",
+        roff(&title.to_uppercase()),
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+fn options_section() -> String {
+    let mut page = String::from(".SH OPTIONS\n");
+    for option in OPTIONS {
+        page.push_str(&format!(
+            ".TP\n.B {}\n{}\n",
+            roff(option.flag),
+            roff(option.about)
+        ));
+    }
+    page
+}
+
+pub fn man_page(date: NaiveDate) -> String {
+    let mut page = header("whocares", date);
+    page.push_str(".SH NAME\nwhocares \\- who takes care this week\n");
+    page.push_str(".SH SYNOPSIS\n.B whocares\n[\\fIOPTIONS\\fR] [\\fICOMMAND\\fR]\n");
+    page.push_str(".SH COMMANDS\n");
+    for command in COMMANDS {
+        page.push_str(&format!(
+            ".TP\n.B {}\n{}\n",
+            roff(synopsis(command.name).trim_start_matches("whocares ")),
+            roff(command.about)
+        ));
+    }
+    page.push_str(&options_section());
+    page.push_str(".SH EXIT STATUS\n");
+    for (code, about) in EXIT_CODES {
+        page.push_str(&format!(".TP\n.B {code}\n{}\n", roff(about)));
+    }
+    page
+}
+
+fn command_page(command: &Command, date: NaiveDate) -> String {
+    let title = format!("whocares-{}", command.name);
+    let mut page = header(&title, date);
+    page.push_str(&format!(
+        ".SH NAME\n{} \\- {}\n",
+        roff(&title),
+        roff(command.about)
+    ));
+    page.push_str(&format!(
+        ".SH SYNOPSIS\n.B whocares {}\n{}\n",
+        roff(command.name),
+        roff(command.args)
+    ));
+    page.push_str(&options_section());
+    page.push_str(".SH SEE ALSO\n.BR whocares (1)\n");
+    page
+}
+
+// The man page in short, for --help.
+pub fn help() -> String {
+    const WIDTH: usize = 28;
+    // Longer entries get their description on the next line.
+    let entry = |name: &str, about: &str| match name.len() {
+        0..=WIDTH => format!("  {name:WIDTH$}  {about}\n"),
+        _ => format!("  {name}\n  {:WIDTH$}  {about}\n", ""),
+    };
+    let mut text = String::from("usage: whocares [OPTIONS] [COMMAND]\n\nCommands:\n");
+    for command in COMMANDS {
+        let synopsis = synopsis(command.name);
+        text.push_str(&entry(
+            synopsis.trim_start_matches("whocares "),
+            command.about,
+        ));
+    }
+    text.push_str("\nOptions:\n");
+    for option in OPTIONS {
+        text.push_str(&entry(option.flag, option.about));
+    }
+    text
+}
+
+// Writes whocares.1 and a whocares-<command>.1 page per subcommand.
+pub fn mangen(dir: &str, date: NaiveDate) -> io::Result<()> {
+    let dir = Path::new(dir);
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("whocares.1"), man_page(date))?;
+    for command in COMMANDS.iter().filter(|c| !c.name.is_empty()) {
+        fs::write(
+            dir.join(format!("whocares-{}.1", command.name)),
+            command_page(command, date),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn man_page_works() {
+        let date = NaiveDate::from_str("2025-08-04").unwrap();
+        let page = man_page(date);
+        assert!(page.starts_with(".TH WHOCARES 1 \"2025-08-04\""));
+        assert!(page
+            .contains(".B swap <YYYY\\-Www> <YYYY\\-Www>\nTrade the caretakers of two weeks.\n"));
+        assert!(page.contains(".B \\-\\-dry\\-run\n"));
+        assert!(page.contains(".B 3\nThe config file was not found.\n"));
+
+        assert_eq!(synopsis("swap"), "whocares swap <YYYY-Www> <YYYY-Www>");
+        assert_eq!(synopsis(""), "whocares [WEEKS]");
+        assert!(help().contains("\n  swap <YYYY-Www> <YYYY-Www> "));
+    }
+}
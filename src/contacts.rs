@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Per caretaker, keyed by their name in the roster.
+pub type Contacts = HashMap<String, Contact>;
+
+#[derive(Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct Contact {
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    // Shown before their name, like "🦊", for those who can't read yet.
+    #[serde(default)]
+    pub emoji: Option<String>,
+}
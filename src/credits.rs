@@ -0,0 +1,116 @@
+use crate::{regular_caretaker, stats, CareWeek, Config};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::io;
+
+pub use whocares_core::rotation::Balances;
+
+#[derive(Deserialize)]
+pub struct CreditConfig {
+    #[serde(default = "default_extra_week")]
+    pub extra_week: f64,
+    #[serde(default = "default_holiday_week")]
+    pub holiday_week: f64,
+    // Give weeks whose caretaker is away to whoever owes the most credits.
+    #[serde(default)]
+    pub prefer_owing: bool,
+}
+
+impl Default for CreditConfig {
+    fn default() -> Self {
+        CreditConfig {
+            extra_week: default_extra_week(),
+            holiday_week: default_holiday_week(),
+            prefer_owing: false,
+        }
+    }
+}
+
+fn default_extra_week() -> f64 {
+    1.0
+}
+
+fn default_holiday_week() -> f64 {
+    2.0
+}
+
+impl CreditConfig {
+    fn weight(&self, week: &CareWeek) -> f64 {
+        if week.holidays.is_empty() {
+            self.extra_week
+        } else {
+            self.holiday_week
+        }
+    }
+}
+
+// Covering somebody else's week earns its weight in credits, having your
+// week covered costs the same amount.
+pub fn balances(conf: &Config, weeks: &[CareWeek]) -> Balances {
+    let mut balances: Balances = conf.everyone().map(|c| (c.clone(), 0.0)).collect();
+    for week in weeks.iter().filter(|w| w.suspended.is_none()) {
+        let regular = regular_caretaker(conf, week.start_date);
+        if *regular != *week.caretaker {
+            let weight = conf.credits.weight(week);
+            *balances.entry(week.caretaker.to_string()).or_default() += weight;
+            *balances.entry(regular.to_string()).or_default() -= weight;
+        }
+    }
+    balances
+}
+
+pub fn balances_until(conf: &Config, config_path: &str, today: NaiveDate) -> io::Result<Balances> {
+    let weeks = stats::served_weeks(conf, config_path, conf.startdate, today)?;
+    Ok(balances(conf, &weeks))
+}
+
+pub fn report(conf: &Config, balances: &Balances) -> String {
+    let mut caretakers: Vec<&String> = balances.keys().collect();
+    caretakers.sort_by_key(|c| conf.everyone().position(|r| r == *c).unwrap_or(usize::MAX));
+    caretakers
+        .into_iter()
+        .map(|c| format!("{c}: {:+}\n", balances[c]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holidays::Holiday;
+    use std::str::FromStr;
+
+    #[test]
+    fn balances_weight_holiday_weeks() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: date("2024-01-01"),
+            ..Default::default()
+        };
+        let weeks = [
+            CareWeek {
+                caretaker: "B".into(),
+                start_date: date("2024-01-01"),
+                holidays: vec![Holiday {
+                    date: date("2024-01-01"),
+                    name: "Neujahr",
+                }],
+                ..Default::default()
+            },
+            CareWeek {
+                caretaker: "B".into(),
+                start_date: date("2024-01-08"),
+                ..Default::default()
+            },
+            CareWeek {
+                caretaker: "B".into(),
+                start_date: date("2024-01-15"),
+                ..Default::default()
+            },
+        ];
+
+        let balances = balances(&conf, &weeks);
+        assert_eq!(balances["A"], -3.0);
+        assert_eq!(balances["B"], 3.0);
+    }
+}
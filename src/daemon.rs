@@ -0,0 +1,128 @@
+use crate::exit;
+use crate::logging::{debug, error, info};
+use crate::notify::{self, Event};
+use crate::{current_week, get_config, get_next_weeks, start_of_week};
+use chrono::{Duration, NaiveDateTime, NaiveTime, Utc, Weekday};
+use std::io;
+use std::thread;
+
+const MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(60);
+
+fn next_handoff(after: NaiveDateTime) -> NaiveDateTime {
+    let monday = after.date().week(Weekday::Mon).first_day();
+    let handoff = monday.and_time(NaiveTime::MIN);
+    if handoff > after {
+        handoff
+    } else {
+        handoff + Duration::weeks(1)
+    }
+}
+
+pub fn next_event(after: NaiveDateTime, reminder_hours: u32) -> (NaiveDateTime, Event) {
+    let handoff = next_handoff(after);
+    let lead = Duration::hours(reminder_hours as i64);
+
+    let reminder = [handoff, handoff + Duration::weeks(1)]
+        .into_iter()
+        .map(|h| h - lead)
+        .find(|r| *r > after);
+
+    match reminder {
+        Some(reminder) if reminder < handoff => (reminder, Event::Reminder),
+        _ => (handoff, Event::Handoff),
+    }
+}
+
+pub fn fire(config_path: &str, event: Event) -> io::Result<()> {
+    let conf = get_config(config_path)?;
+    let week = match event {
+        Event::Handoff => current_week(&conf),
+        Event::Reminder => {
+            let next = start_of_week(conf.today()) + Duration::weeks(1);
+            get_next_weeks(&conf, 2)
+                .into_iter()
+                .find(|week| week.start_date == next)
+        }
+    };
+    match week {
+        Some(week) => notify::send_all(
+            &conf.notifications,
+            &conf.formatting,
+            &conf.contacts,
+            event,
+            &week,
+            conf.today(),
+        )
+        .map_err(|e| exit::with_code(exit::NOTIFICATION_FAILED, e)),
+        None => Err(exit::with_code(
+            exit::NO_CARETAKER,
+            io::Error::other("no caretaker scheduled"),
+        )),
+    }
+}
+
+pub fn run(config_path: &str) -> io::Result<()> {
+    let mut after = get_config(config_path)?.zone.now();
+
+    loop {
+        let conf = get_config(config_path)?;
+        let (at, event) = next_event(after, conf.notifications.reminder_hours);
+        // Events are in local time, the wait is real time, which differs
+        // across DST changes.
+        let remaining = conf.zone.to_utc(at) - Utc::now();
+
+        if let Ok(remaining) = remaining.to_std() {
+            if !remaining.is_zero() {
+                debug!("next {} notification at {at}", event.name());
+                thread::sleep(remaining.min(MAX_SLEEP));
+                continue;
+            }
+        }
+
+        info!("{at}: sending {} notifications", event.name());
+        if let Err(e) = fire(config_path, event) {
+            error!("{e}");
+        }
+        after = at;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn next_event_works() {
+        // Wednesday, handoff is on Monday 2024-07-08
+        assert_eq!(
+            next_event(at("2024-07-03", 12), 24),
+            (at("2024-07-07", 0), Event::Reminder)
+        );
+        // Right after the reminder the handoff is next
+        assert_eq!(
+            next_event(at("2024-07-07", 0), 24),
+            (at("2024-07-08", 0), Event::Handoff)
+        );
+        // After the handoff the following reminder is next
+        assert_eq!(
+            next_event(at("2024-07-08", 0), 24),
+            (at("2024-07-14", 0), Event::Reminder)
+        );
+    }
+
+    #[test]
+    fn next_event_without_reminder_lead() {
+        assert_eq!(
+            next_event(at("2024-07-03", 12), 0),
+            (at("2024-07-08", 0), Event::Handoff)
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{get_weeks, CareWeek, Config};
+use chrono::NaiveDate;
+
+pub struct Change {
+    pub week: CareWeek,
+    pub old_caretaker: String,
+}
+
+pub fn changes(old: &[CareWeek], new: Vec<CareWeek>) -> Vec<Change> {
+    new.into_iter()
+        .filter_map(|week| {
+            let previous = old.iter().find(|w| w.start_date == week.start_date)?;
+            (previous.caretaker != week.caretaker).then(|| Change {
+                old_caretaker: previous.caretaker.to_string(),
+                week,
+            })
+        })
+        .collect()
+}
+
+pub fn diff(old: &Config, new: &Config, from: NaiveDate, weeks: usize) -> Vec<Change> {
+    changes(&get_weeks(old, from, weeks), get_weeks(new, from, weeks))
+}
+
+pub fn report(changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return "no changes\n".to_string();
+    }
+    changes
+        .iter()
+        .map(|c| {
+            format!(
+                "week #{} {} - {}: {} -> {}\n",
+                c.week.week, c.week.start_date, c.week.end_date, c.old_caretaker, c.week.caretaker
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn diff_works() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let old = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            ..Default::default()
+        };
+        let new = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            reschedule: HashMap::from([("2024-2".to_string(), "A".to_string())]),
+            ..Default::default()
+        };
+
+        let changes = diff(&old, &new, start, 4);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].old_caretaker, "B");
+        assert_eq!(&*changes[0].week.caretaker, "A");
+        assert_eq!(
+            report(&changes),
+            "week #2 2024-01-08 - 2024-01-14: B -> A\n"
+        );
+        assert_eq!(report(&diff(&old, &old, start, 4)), "no changes\n");
+    }
+}
@@ -0,0 +1,343 @@
+#[cfg(feature = "notifications")]
+use crate::notify::Channel;
+use crate::{get_config, near_duplicates, parse_week, split_reschedule, start_of_week, tz, Config};
+use chrono::{Datelike, NaiveDate};
+use std::collections::HashSet;
+#[cfg(feature = "notifications")]
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(feature = "notifications")]
+use std::process::Command;
+#[cfg(feature = "notifications")]
+use std::time::Duration;
+
+#[cfg(feature = "notifications")]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+pub struct Check {
+    pub status: Status,
+    pub message: String,
+}
+
+fn check(status: Status, message: String) -> Check {
+    Check { status, message }
+}
+
+fn check_startdate(conf: &Config, today: NaiveDate) -> Check {
+    let years = (today - conf.startdate).num_days() / 365;
+    if conf.startdate > today {
+        check(
+            Status::Warn,
+            format!("start date {} is in the future", conf.startdate),
+        )
+    } else if years > 20 {
+        check(
+            Status::Warn,
+            format!("start date {} is {years} years ago", conf.startdate),
+        )
+    } else {
+        check(Status::Pass, format!("start date {}", conf.startdate))
+    }
+}
+
+fn check_roster(conf: &Config) -> Check {
+    let mut seen = HashSet::new();
+    let duplicates: Vec<&str> = conf
+        .caretakers
+        .iter()
+        .filter(|c| !seen.insert(c.as_str()))
+        .map(String::as_str)
+        .collect();
+    if conf.caretakers.is_empty() {
+        check(Status::Fail, "no caretakers configured".to_string())
+    } else if !duplicates.is_empty() {
+        check(
+            Status::Fail,
+            format!("duplicate caretakers: {}", duplicates.join(", ")),
+        )
+    } else if let Some((a, b)) = near_duplicates(&conf.caretakers).first() {
+        check(
+            Status::Warn,
+            format!("caretakers \"{a}\" and \"{b}\" only differ in case or spaces"),
+        )
+    } else if conf.caretakers.len() == 1 {
+        check(
+            Status::Pass,
+            format!("1 caretaker, {} is always on duty", conf.caretakers[0]),
+        )
+    } else {
+        check(
+            Status::Pass,
+            format!("{} caretakers", conf.caretakers.len()),
+        )
+    }
+}
+
+fn check_reschedules(conf: &Config, today: NaiveDate) -> Vec<Check> {
+    let mut keys: Vec<(&String, &String)> = conf.reschedule.iter().collect();
+    keys.sort();
+    let checks: Vec<Check> = keys
+        .into_iter()
+        .filter_map(|(key, caretaker)| {
+            let (first, second) = split_reschedule(caretaker);
+            let stranger = [Some(first), second]
+                .into_iter()
+                .flatten()
+                .find(|c| !conf.is_caretaker(c));
+            match (parse_week(key), stranger) {
+                (None, _) => Some(check(
+                    Status::Fail,
+                    format!("reschedule {key}: not a week, expected YYYY-Www like 2025-W14"),
+                )),
+                (Some(_), Some(stranger)) => Some(check(
+                    Status::Warn,
+                    format!("reschedule {key}: {stranger} is not a caretaker"),
+                )),
+                (Some(monday), None) if monday < start_of_week(today) => Some(check(
+                    Status::Warn,
+                    format!("reschedule {key}: in the past, remove it with `whocares prune`"),
+                )),
+                (Some(_), None) => None,
+            }
+        })
+        .collect();
+    if checks.is_empty() {
+        return vec![check(
+            Status::Pass,
+            format!("{} reschedules", conf.reschedule.len()),
+        )];
+    }
+    checks
+}
+
+fn check_preferences(conf: &Config) -> Vec<Check> {
+    let mut strangers: Vec<&String> = conf
+        .preferences
+        .keys()
+        .filter(|c| !conf.is_caretaker(c))
+        .collect();
+    strangers.sort();
+    strangers
+        .into_iter()
+        .map(|c| check(Status::Warn, format!("preferences: {c} is not a caretaker")))
+        .collect()
+}
+
+// host:port of a URL, with the default port of its scheme
+#[cfg(feature = "notifications")]
+fn url_addr(url: &str) -> String {
+    let authority = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?'])
+        .next()
+        .unwrap_or_default();
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    let default_port = if url.starts_with("http://") { 80 } else { 443 };
+    if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{authority}:{default_port}")
+    }
+}
+
+#[cfg(feature = "notifications")]
+fn reachable(addr: &str) -> bool {
+    addr.to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .is_some_and(|a| TcpStream::connect_timeout(&a, CONNECT_TIMEOUT).is_ok())
+}
+
+#[cfg(feature = "notifications")]
+fn check_channel(conf: &Config, channel: &Channel) -> Check {
+    match channel {
+        Channel::Webhook { url } => {
+            let addr = url_addr(url);
+            match reachable(&addr) {
+                true => check(Status::Pass, format!("webhook {addr} is reachable")),
+                false => check(Status::Fail, format!("webhook {addr} is unreachable")),
+            }
+        }
+        Channel::Signal(signal) => {
+            let addr = url_addr(&signal.url);
+            match reachable(&addr) {
+                true => check(Status::Pass, format!("signal-cli {addr} is reachable")),
+                false => check(Status::Fail, format!("signal-cli {addr} is unreachable")),
+            }
+        }
+        Channel::Mqtt(mqtt) => {
+            let addr = format!("{}:{}", mqtt.host, mqtt.port);
+            match reachable(&addr) {
+                true => check(Status::Pass, format!("mqtt broker {addr} is reachable")),
+                false => check(Status::Fail, format!("mqtt broker {addr} is unreachable")),
+            }
+        }
+        Channel::Irc(irc) => {
+            let addr = format!("{}:{}", irc.server, irc.port());
+            match reachable(&addr) {
+                true => check(Status::Pass, format!("irc server {addr} is reachable")),
+                false => check(Status::Fail, format!("irc server {addr} is unreachable")),
+            }
+        }
+        Channel::Sns(sns) => match sns.host() {
+            Ok(host) => match reachable(&format!("{host}:443")) {
+                true => check(Status::Pass, format!("sns {host} is reachable")),
+                false => check(Status::Fail, format!("sns {host} is unreachable")),
+            },
+            Err(e) => check(Status::Fail, e.to_string()),
+        },
+        Channel::Twilio(_) => {
+            let missing: Vec<&str> = conf
+                .everyone()
+                .filter(|c| {
+                    conf.contacts
+                        .get(*c)
+                        .and_then(|c| c.phone.as_ref())
+                        .is_none()
+                })
+                .map(String::as_str)
+                .collect();
+            match missing.as_slice() {
+                [] => check(
+                    Status::Pass,
+                    "twilio: every caretaker has a phone number".to_string(),
+                ),
+                missing => check(
+                    Status::Warn,
+                    format!("twilio: no phone number for {}", missing.join(", ")),
+                ),
+            }
+        }
+        Channel::Command { command } => {
+            let program = command.split_whitespace().next().unwrap_or_default();
+            let found = Command::new("sh")
+                .arg("-c")
+                .arg("command -v \"$1\" >/dev/null")
+                .arg("sh")
+                .arg(program)
+                .status()
+                .is_ok_and(|s| s.success());
+            match found {
+                true => check(Status::Pass, format!("command {program} found")),
+                false => check(Status::Fail, format!("command {program} not found")),
+            }
+        }
+    }
+}
+
+fn check_clock(conf: &Config) -> Vec<Check> {
+    let now = chrono::Local::now();
+    let mut checks = vec![if (2024..2100).contains(&now.year()) {
+        check(
+            Status::Pass,
+            format!("clock reads {}", now.format("%Y-%m-%d %H:%M %:z")),
+        )
+    } else {
+        check(
+            Status::Fail,
+            format!("clock reads {}, is it set?", now.to_rfc3339()),
+        )
+    }];
+
+    if let Some(timezone) = &conf.ics.timezone {
+        let local = now.offset().local_minus_utc();
+        checks.push(match tz::load(timezone) {
+            Err(e) => check(Status::Fail, format!("timezone {timezone}: {e}")),
+            Ok(tz)
+                if tz.std_offset_secs == local
+                    || tz.dst.as_ref().is_some_and(|d| d.offset_secs == local) =>
+            {
+                check(
+                    Status::Pass,
+                    format!("timezone {timezone} matches the system"),
+                )
+            }
+            Ok(_) => check(
+                Status::Warn,
+                format!(
+                    "timezone {timezone} differs from the system offset {}",
+                    now.format("%:z")
+                ),
+            ),
+        });
+    }
+    checks
+}
+
+pub fn checks(config_path: &str, today: NaiveDate) -> Vec<Check> {
+    let conf = match get_config(config_path) {
+        Ok(conf) => conf,
+        Err(e) => return vec![check(Status::Fail, format!("config {config_path}: {e}"))],
+    };
+    let mut checks = vec![
+        check(Status::Pass, format!("config {config_path} parses")),
+        check_startdate(&conf, today),
+        check_roster(&conf),
+    ];
+    checks.extend(check_reschedules(&conf, today));
+    checks.extend(check_preferences(&conf));
+    #[cfg(feature = "notifications")]
+    checks.extend(
+        conf.notifications
+            .channels
+            .iter()
+            .map(|channel| check_channel(&conf, channel)),
+    );
+    checks.extend(check_clock(&conf));
+    checks
+}
+
+pub fn report(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|c| {
+            let status = match c.status {
+                Status::Pass => "PASS",
+                Status::Warn => "WARN",
+                Status::Fail => "FAIL",
+            };
+            format!("{status} {}\n", c.message)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn config_checks_work() {
+        let today = NaiveDate::from_str("2025-07-30").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string(), "A".to_string()],
+            startdate: NaiveDate::from_str("2024-01-01").unwrap(),
+            reschedule: HashMap::from([
+                ("2025-20".to_string(), "A".to_string()),
+                ("2025-40".to_string(), "C".to_string()),
+                ("2025-41".to_string(), "B".to_string()),
+                ("soon".to_string(), "B".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(check_startdate(&conf, today).status, Status::Pass);
+        let roster = check_roster(&conf);
+        assert_eq!(roster.status, Status::Fail);
+        assert_eq!(roster.message, "duplicate caretakers: A");
+
+        let statuses: Vec<Status> = check_reschedules(&conf, today)
+            .iter()
+            .map(|c| c.status)
+            .collect();
+        assert_eq!(statuses, [Status::Warn, Status::Warn, Status::Fail]);
+    }
+}
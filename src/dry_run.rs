@@ -0,0 +1,22 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set by --dry-run: config writes, state files and anything sent to remote
+// services are reported instead of performed.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Marks output describing actions that were not actually carried out.
+pub fn prefix() -> &'static str {
+    if enabled() {
+        "(dry run) "
+    } else {
+        ""
+    }
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+// Exit codes of the whocares binary. They are part of its interface, so
+// existing values must not change.
+pub const FAILURE: i32 = 1; // anything else, also "not on duty" for on-duty
+pub const USAGE: i32 = 2;
+pub const CONFIG_NOT_FOUND: i32 = 3;
+pub const CONFIG_INVALID: i32 = 4;
+pub const NO_CARETAKER: i32 = 5;
+pub const NOTIFICATION_FAILED: i32 = 6;
+pub const PUBLISH_FAILED: i32 = 7;
+
+#[derive(Debug)]
+struct Failure {
+    code: i32,
+    source: io::Error,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Error for Failure {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Tags an error with the exit code it should end the process with. Errors
+// that already carry a code keep it.
+pub fn with_code(code: i32, e: io::Error) -> io::Error {
+    if e.get_ref().is_some_and(|inner| inner.is::<Failure>()) {
+        return e;
+    }
+    io::Error::new(e.kind(), Failure { code, source: e })
+}
+
+pub fn code(e: &io::Error) -> i32 {
+    match e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<Failure>())
+    {
+        Some(failure) => failure.code,
+        None if e.kind() == io::ErrorKind::InvalidInput => USAGE,
+        None => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_works() {
+        let e = with_code(CONFIG_INVALID, io::Error::other("bad"));
+        assert_eq!(code(&e), CONFIG_INVALID);
+        assert_eq!(e.to_string(), "bad");
+        assert_eq!(code(&with_code(PUBLISH_FAILED, e)), CONFIG_INVALID);
+
+        let usage = io::Error::new(io::ErrorKind::InvalidInput, "usage: whocares");
+        assert_eq!(code(&usage), USAGE);
+        assert_eq!(code(&io::Error::other("other")), FAILURE);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::logging::{error, info};
+use crate::{atom, dry_run, get_config, ics, print, svg, weeks_from, CareWeek, Config};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+pub const FORMATS: &[&str] = &["ics", "csv", "jsonl", "svg", "html", "atom"];
+
+// RFC 4180 quoting, only where it's needed.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// One row per week and two for split weeks, written as the weeks come so
+// the range can be long.
+pub fn write_csv(out: &mut impl Write, weeks: impl Iterator<Item = CareWeek>) -> io::Result<()> {
+    out.write_all(b"week,iso_week,caretaker,start_date,end_date,holidays\r\n")?;
+    for week in weeks {
+        let holidays: Vec<String> = week
+            .holidays
+            .iter()
+            .map(|h| format!("{} {}", h.date, h.name))
+            .collect();
+        // Suspended weeks have no spans, one row says so instead.
+        let suspended = week.suspended.is_some().then(|| week.caretakers());
+        let mut spans = week.spans();
+        if let Some(suspended) = &suspended {
+            let (start, end) = week.period();
+            spans.push((suspended, start, end));
+        }
+        for (caretaker, start, end) in spans {
+            let row = [
+                week.week.to_string(),
+                crate::iso_week_key(week.start_date),
+                caretaker.to_string(),
+                start.to_string(),
+                end.to_string(),
+                holidays.join("; "),
+            ];
+            let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+            write!(out, "{}\r\n", row.join(","))?;
+        }
+    }
+    Ok(())
+}
+
+// One JSON object per line, like the weeks of the HTTP API.
+pub fn write_jsonl(out: &mut impl Write, weeks: impl Iterator<Item = CareWeek>) -> io::Result<()> {
+    for week in weeks {
+        writeln!(out, "{}", week.to_json())?;
+    }
+    Ok(())
+}
+
+pub fn write(
+    out: &mut impl Write,
+    format: &str,
+    conf: &Config,
+    weeks: impl Iterator<Item = CareWeek>,
+) -> io::Result<()> {
+    match format {
+        "ics" => ics::write_calendar(out, conf, weeks, chrono::Utc::now()),
+        "csv" => write_csv(out, weeks),
+        "svg" => svg::write_svg(out, conf, weeks),
+        "html" => print::write_html(out, conf, weeks),
+        "atom" => atom::write_feed(out, conf, weeks, chrono::Utc::now()),
+        _ => write_jsonl(out, weeks),
+    }
+}
+
+// Through a temporary file, so whatever serves the directory never sees
+// half an export.
+fn write_file(path: &Path, format: &str, conf: &Config, weeks: usize) -> io::Result<()> {
+    if dry_run::enabled() {
+        println!("{}would write {}", dry_run::prefix(), path.display());
+        return Ok(());
+    }
+    let weeks = weeks_from(conf, conf.today().max(conf.startdate)).take(weeks);
+    let tmp = path.with_extension(format!("{format}.tmp"));
+    let mut out = io::BufWriter::new(fs::File::create(&tmp)?);
+    write(&mut out, format, conf, weeks)?;
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+// Writes DIR/<profile>.<format> for every profile and format. Profiles are
+// spread over a thread per CPU and their formats are rendered side by side,
+// each config is only loaded once.
+pub fn export_all(
+    profiles: &[(String, String)],
+    formats: &[&str],
+    dir: &Path,
+    weeks: usize,
+) -> io::Result<()> {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(profiles.len());
+    let export = |(name, config_path): &(String, String)| {
+        let conf = match get_config(config_path) {
+            Ok(conf) => conf,
+            Err(e) => {
+                error!("export of {name} failed: {e}");
+                failed.fetch_add(formats.len(), Ordering::Relaxed);
+                return;
+            }
+        };
+        thread::scope(|scope| {
+            for format in formats {
+                let (conf, failed) = (&conf, &failed);
+                scope.spawn(move || {
+                    let path = dir.join(format!("{name}.{format}"));
+                    match write_file(&path, format, conf, weeks) {
+                        Ok(()) => info!("wrote {}", path.display()),
+                        Err(e) => {
+                            error!("writing {} failed: {e}", path.display());
+                            failed.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+    };
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(profile) = profiles.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    export(profile);
+                }
+            });
+        }
+    });
+
+    match failed.into_inner() {
+        0 => Ok(()),
+        failed => Err(io::Error::other(format!(
+            "{failed} of {} exports failed",
+            profiles.len() * formats.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn exports_work() {
+        let week = || CareWeek {
+            week: 27,
+            caretaker: "Page, Jimmy".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            ..Default::default()
+        };
+        let mut csv = Vec::new();
+        write_csv(&mut csv, [week()].into_iter()).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "week,iso_week,caretaker,start_date,end_date,holidays\r\n27,2024-W27,\"Page, Jimmy\",2024-07-01,2024-07-07,\r\n"
+        );
+
+        let mut jsonl = Vec::new();
+        write_jsonl(&mut jsonl, [week(), week()].into_iter()).unwrap();
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["iso_week"], "2024-W27");
+    }
+
+    #[test]
+    fn export_all_writes_every_profile() {
+        let dir = std::env::temp_dir().join(format!("whocares-export-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("team.json");
+        fs::write(
+            &config,
+            r#"{"startdate": "2024-07-01", "caretakers": ["Edgar"], "reschedule": {}}"#,
+        )
+        .unwrap();
+        let config = config.to_str().unwrap().to_string();
+        let profiles = [("a".to_string(), config.clone()), ("b".to_string(), config)];
+
+        export_all(&profiles, &["ics", "csv"], &dir, 3).unwrap();
+        for file in ["a.ics", "a.csv", "b.ics", "b.csv"] {
+            assert!(dir.join(file).exists(), "{file}");
+        }
+        assert_eq!(
+            fs::read_to_string(dir.join("b.csv"))
+                .unwrap()
+                .lines()
+                .count(),
+            4
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{get_weeks, CareWeek, Config};
+use chrono::NaiveDate;
+
+const HORIZON_WEEKS: usize = 5 * 53;
+
+// The next `count` duty weeks of each caretaker starting with the week of
+// `from`. A split week is both caretakers', a week of shifts everyone's on
+// one and suspended weeks are nobody's.
+pub fn forecast(conf: &Config, from: NaiveDate, count: usize) -> Vec<(String, Vec<CareWeek>)> {
+    let mut forecast: Vec<(String, Vec<CareWeek>)> =
+        conf.everyone().map(|c| (c.clone(), Vec::new())).collect();
+
+    for week in get_weeks(conf, from, HORIZON_WEEKS) {
+        for (caretaker, weeks) in forecast.iter_mut() {
+            let on_duty = week.everyone().contains(&caretaker.as_str());
+            if on_duty && weeks.len() < count {
+                weeks.push(week.clone());
+            }
+        }
+        if forecast.iter().all(|(_, weeks)| weeks.len() >= count) {
+            break;
+        }
+    }
+    forecast
+}
+
+// Split weeks and days off make a caretaker's turns out of their spans.
+pub fn until_turn(conf: &Config, caretaker: &str, today: NaiveDate) -> String {
+    let weeks = get_weeks(conf, today, HORIZON_WEEKS);
+    let turns: Vec<(NaiveDate, NaiveDate)> = weeks
+        .iter()
+        .flat_map(|w| turns(w, caretaker))
+        .filter(|(_, end)| *end >= today)
+        .take(2)
+        .collect();
+    let days = |date: NaiveDate| (date - today).num_days();
+
+    match turns.as_slice() {
+        [(start, end), rest @ ..] if *start <= today => {
+            let mut status = format!("on duty until {end} ({} days left)", days(*end));
+            if let Some((next, _)) = rest.first() {
+                status.push_str(&format!(", next turn in {} days", days(*next)));
+            }
+            status
+        }
+        [(next, _), ..] => format!("next turn in {} days ({next})", days(*next)),
+        [] => "no upcoming turn".to_string(),
+    }
+}
+
+// With shifts everyone on one takes care all the covered days of the week.
+fn turns(week: &CareWeek, caretaker: &str) -> Vec<(NaiveDate, NaiveDate)> {
+    let mine = |c: &str| match week.shifts.is_empty() {
+        true => c == caretaker,
+        false => week.everyone().contains(&caretaker),
+    };
+    week.spans()
+        .into_iter()
+        .filter(|(c, _, _)| mine(c))
+        .map(|(_, start, end)| (start, end))
+        .collect()
+}
+
+pub fn report(forecast: &[(String, Vec<CareWeek>)]) -> String {
+    let mut report = String::new();
+    for (caretaker, weeks) in forecast {
+        report.push_str(&format!("{caretaker}:\n"));
+        if weeks.is_empty() {
+            report.push_str("  no upcoming weeks\n");
+        }
+        for week in weeks {
+            report.push_str(&format!(
+                "  week #{} {} - {}{}\n",
+                week.week,
+                week.start_date,
+                week.end_date,
+                week.holiday_note()
+            ));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn forecast_works() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            startdate: start,
+            reschedule: HashMap::from([("2024-1".to_string(), "B".to_string())]),
+            ..Default::default()
+        };
+        let forecast = forecast(&conf, start, 2);
+        let starts = |i: usize| -> Vec<String> {
+            forecast[i]
+                .1
+                .iter()
+                .map(|w| w.start_date.to_string())
+                .collect()
+        };
+        assert_eq!(forecast[0].0, "A");
+        assert_eq!(starts(0), vec!["2024-01-22", "2024-02-12"]);
+        assert_eq!(starts(1), vec!["2024-01-01", "2024-01-08"]);
+        assert_eq!(starts(2), vec!["2024-01-15", "2024-02-05"]);
+    }
+
+    #[test]
+    fn until_turn_works() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-01-03").unwrap();
+        assert_eq!(
+            until_turn(&conf, "A", today),
+            "on duty until 2024-01-07 (4 days left), next turn in 12 days"
+        );
+        assert_eq!(
+            until_turn(&conf, "B", today),
+            "next turn in 5 days (2024-01-08)"
+        );
+        assert_eq!(until_turn(&conf, "C", today), "no upcoming turn");
+    }
+
+    #[test]
+    fn suspended_weeks_are_skipped() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            blackouts: serde_json::from_str(
+                r#"[{"name": "closed", "start": "2024-01-08", "end": "2024-01-14"}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let forecast = forecast(&conf, start, 2);
+        let starts: Vec<String> = forecast[1]
+            .1
+            .iter()
+            .map(|w| w.start_date.to_string())
+            .collect();
+        assert_eq!(starts, vec!["2024-01-22", "2024-02-05"]);
+        let today = NaiveDate::from_str("2024-01-03").unwrap();
+        assert_eq!(
+            until_turn(&conf, "B", today),
+            "next turn in 19 days (2024-01-22)"
+        );
+    }
+
+    #[test]
+    fn split_weeks_are_both_caretakers() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            reschedule: HashMap::from([("2024-1".to_string(), "A/B".to_string())]),
+            ..Default::default()
+        };
+        let forecast = forecast(&conf, start, 1);
+        assert_eq!(forecast[0].1[0].start_date, start);
+        assert_eq!(forecast[1].1[0].start_date, start);
+
+        let monday = start;
+        assert_eq!(
+            until_turn(&conf, "A", monday),
+            "on duty until 2024-01-03 (2 days left), next turn in 14 days"
+        );
+        assert_eq!(
+            until_turn(&conf, "B", monday),
+            "next turn in 3 days (2024-01-04)"
+        );
+        let friday = NaiveDate::from_str("2024-01-05").unwrap();
+        assert_eq!(
+            until_turn(&conf, "B", friday),
+            "on duty until 2024-01-07 (2 days left), next turn in 3 days"
+        );
+    }
+
+    #[test]
+    fn days_are_turns_of_their_own() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            per_weekday: true,
+            ..Default::default()
+        };
+        let forecast = forecast(&conf, start, 1);
+        assert_eq!(forecast[1].1[0].start_date, start);
+        assert_eq!(
+            until_turn(&conf, "A", start),
+            "on duty until 2024-01-01 (0 days left), next turn in 2 days"
+        );
+        assert_eq!(
+            until_turn(&conf, "B", start),
+            "next turn in 1 days (2024-01-02)"
+        );
+    }
+
+    #[test]
+    fn shifts_are_turns_of_everyone_on_one() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            startdate: start,
+            shifts: serde_json::from_str(
+                r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00", "offset": 1}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let forecast = forecast(&conf, start, 2);
+        let starts = |i: usize| -> Vec<String> {
+            forecast[i]
+                .1
+                .iter()
+                .map(|w| w.start_date.to_string())
+                .collect()
+        };
+        // B has the night of the first week and the day of the second.
+        assert_eq!(starts(1), vec!["2024-01-01", "2024-01-08"]);
+        assert_eq!(starts(2), vec!["2024-01-08", "2024-01-15"]);
+        assert_eq!(
+            until_turn(&conf, "B", start),
+            "on duty until 2024-01-07 (6 days left), next turn in 7 days"
+        );
+    }
+}
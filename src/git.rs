@@ -0,0 +1,41 @@
+use crate::logging::{info, warning};
+use serde::Deserialize;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output};
+
+#[derive(Deserialize, Default)]
+pub struct GitConfig {
+    #[serde(default)]
+    pub auto_commit: bool,
+}
+
+fn git(dir: &Path, args: &[&str]) -> io::Result<Output> {
+    Command::new("git").arg("-C").arg(dir).args(args).output()
+}
+
+// Commits just the config file, if it is tracked in a git work tree.
+pub fn commit(config_path: &str, message: &str) -> io::Result<()> {
+    let path = Path::new(config_path);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let inside = git(dir, &["rev-parse", "--is-inside-work-tree"])?;
+    if !inside.status.success() {
+        warning!("git auto_commit is enabled but {config_path} is not in a git repository");
+        return Ok(());
+    }
+    git(dir, &["add", "--", &file])?;
+    let commit = git(dir, &["commit", "-m", message, "--", &file])?;
+    if !commit.status.success() {
+        return Err(io::Error::other(format!(
+            "git commit failed: {}",
+            String::from_utf8_lossy(&commit.stderr).trim()
+        )));
+    }
+    info!("committed {config_path}: {message}");
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use crate::dry_run;
+use crate::i18n::{self, Formatting};
+use crate::{http, CareWeek};
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::io;
+
+const API_URL: &str = "https://api.github.com";
+const TOKEN_ENVS: [&str; 2] = ["WHOCARES_GITHUB_TOKEN", "GITHUB_TOKEN"];
+
+#[derive(Deserialize, Default)]
+pub struct GitHubConfig {
+    // "owner/name"
+    repo: Option<String>,
+    // created and pinned on the first publish if not set
+    #[serde(default)]
+    issue: Option<u64>,
+    #[serde(default)]
+    gist: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+impl GitHubConfig {
+    fn token(&self) -> io::Result<String> {
+        TOKEN_ENVS
+            .iter()
+            .find_map(|name| env::var(name).ok())
+            .or_else(|| self.token.clone())
+            .or_else(|| dry_run::enabled().then(String::new))
+            .ok_or_else(|| io::Error::other("no github token configured"))
+    }
+}
+
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+pub fn markdown(formatting: &Formatting, weeks: &[CareWeek]) -> String {
+    let mut table = String::from("| Week | Dates | Caretaker | Holidays |\n|---|---|---|---|\n");
+    for week in weeks {
+        let holidays: Vec<&str> = week.holidays.iter().map(|h| h.name).collect();
+        let caretaker = match &week.suspended {
+            Some(blackout) => {
+                i18n::format(formatting.locale, "suspended", &[("blackout", blackout)])
+            }
+            None => formatting.caretakers(week),
+        };
+        table.push_str(&format!(
+            "| {} | {} - {} | {} | {} |\n",
+            week.week,
+            formatting.date(week.start_date),
+            formatting.date(week.end_date),
+            cell(&caretaker),
+            cell(&holidays.join(", "))
+        ));
+    }
+    table
+}
+
+fn request(method: &str, url: &str, token: &str, body: &Value) -> io::Result<Value> {
+    let response = http::Request {
+        method,
+        url,
+        headers: vec![
+            ("Authorization", format!("Bearer {token}")),
+            ("Accept", "application/vnd.github+json".to_string()),
+            ("User-Agent", "whocares".to_string()),
+        ],
+        body: Some(&body.to_string()),
+        ..Default::default()
+    }
+    .send()?
+    .error_for_status()?;
+    Ok(serde_json::from_str(&response.body).unwrap_or_default())
+}
+
+pub fn publish(
+    conf: &GitHubConfig,
+    name: Option<&str>,
+    formatting: &Formatting,
+    weeks: &[CareWeek],
+) -> io::Result<()> {
+    if conf.repo.is_none() && conf.gist.is_none() {
+        return Err(io::Error::other("no github repo or gist configured"));
+    }
+    let token = conf.token()?;
+    let body = markdown(formatting, weeks);
+
+    if let Some(repo) = &conf.repo {
+        let issues_url = format!("{API_URL}/repos/{repo}/issues");
+        let title = conf
+            .title
+            .clone()
+            .unwrap_or_else(|| name.unwrap_or("whocares").to_string());
+        let issue = serde_json::json!({ "title": title, "body": body });
+        match conf.issue {
+            Some(number) => {
+                request("PATCH", &format!("{issues_url}/{number}"), &token, &issue)?;
+                println!("{}updated {repo}#{number}", dry_run::prefix());
+            }
+            None => {
+                let created = request("POST", &issues_url, &token, &issue)?;
+                let pin = serde_json::json!({
+                    "query": "mutation($id: ID!) { pinIssue(input: {issueId: $id}) { issue { number } } }",
+                    "variables": { "id": created["node_id"] },
+                });
+                request("POST", &format!("{API_URL}/graphql"), &token, &pin)?;
+                println!(
+                    "{}created {repo}#{}, set github.issue to update it",
+                    dry_run::prefix(),
+                    created["number"]
+                );
+            }
+        }
+    }
+    if let Some(gist) = &conf.gist {
+        let files = serde_json::json!({ "files": { "whocares.md": { "content": body } } });
+        request("PATCH", &format!("{API_URL}/gists/{gist}"), &token, &files)?;
+        println!("{}updated gist {gist}", dry_run::prefix());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn markdown_works() {
+        let week = CareWeek {
+            week: 27,
+            caretaker: "Edgar | Jimmy".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            ..Default::default()
+        };
+        let suspended = CareWeek {
+            week: 28,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-08").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-14").unwrap(),
+            suspended: Some("Renovation".into()),
+            ..Default::default()
+        };
+        let split = CareWeek {
+            week: 29,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-15").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-21").unwrap(),
+            split: Some(whocares_core::rotation::Split {
+                from: NaiveDate::from_str("2024-07-18").unwrap(),
+                caretaker: "Jimmy".into(),
+            }),
+            ..Default::default()
+        };
+        let days = CareWeek {
+            week: 30,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-22").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-28").unwrap(),
+            days: [
+                "Edgar", "Jimmy", "Edgar", "Jimmy", "Edgar", "Jimmy", "Edgar",
+            ]
+            .map(Into::into)
+            .to_vec(),
+            coverage: serde_json::from_str(r#"["Mon", "Tue"]"#).unwrap(),
+            ..Default::default()
+        };
+        let table = markdown(&Formatting::default(), &[week, suspended, split, days]);
+        assert_eq!(
+            table.lines().nth(2),
+            Some("| 27 | 2024-07-01 - 2024-07-07 | Edgar \\| Jimmy |  |")
+        );
+        assert_eq!(
+            table.lines().nth(3),
+            Some("| 28 | 2024-07-08 - 2024-07-14 | suspended (Renovation) |  |")
+        );
+        assert_eq!(
+            table.lines().nth(4),
+            Some("| 29 | 2024-07-15 - 2024-07-21 | Edgar/Jimmy from Thu |  |")
+        );
+        assert_eq!(
+            table.lines().nth(5),
+            Some("| 30 | 2024-07-22 - 2024-07-28 | Mon Edgar, Tue Jimmy |  |")
+        );
+    }
+}
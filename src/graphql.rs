@@ -0,0 +1,513 @@
+use crate::{get_weeks, iso_week_key, parse_week, weeks_from, CareWeek, Config};
+use serde_json::{Map, Value};
+
+const MAX_WEEKS: i64 = 520;
+
+// The subset of GraphQL the schema needs: one query operation with fields,
+// aliases, arguments and variables, no fragments or directives.
+pub const SCHEMA: &str = "\
+type Query {
+  schedule: Schedule
+  current: Week
+  weeks(from: String, count: Int = 4): [Week]
+  caretaker(name: String!): Caretaker
+}
+type Schedule { name: String, startDate: String, caretakers: [String] }
+type Week { week: Int, isoWeek: String, caretaker: String, caretakers: [String], spans: [Span], shifts: [OnShift], suspended: String, startDate: String, endDate: String, holidays: [Holiday] }
+type Span { caretaker: String, startDate: String, endDate: String }
+type OnShift { shift: String, caretaker: String }
+type Holiday { date: String, name: String }
+type Caretaker { name: String, nextWeeks(count: Int = 4): [Week] }
+";
+
+struct Field {
+    alias: String,
+    name: String,
+    args: Vec<(String, Value)>,
+    selection: Vec<Field>,
+}
+
+impl Field {
+    fn arg(&self, name: &str) -> Option<&Value> {
+        self.args.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    variables: &'a Value,
+}
+
+fn is_name(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl Parser<'_> {
+    fn skip(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '#' {
+                while self.chars.next().is_some_and(|c| c != '\n') {}
+            } else if c.is_whitespace() || c == ',' {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip();
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            Some(c) => Err(format!("expected {expected:?}, found {c:?}")),
+            None => Err(format!("expected {expected:?}, found the end")),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        self.skip();
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek().filter(|c| is_name(**c)) {
+            name.push(c);
+            self.chars.next();
+        }
+        if name.is_empty() {
+            return Err("expected a name".to_string());
+        }
+        Ok(name)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('$') => {
+                self.chars.next();
+                let name = self.name()?;
+                Ok(self.variables.get(&name).cloned().unwrap_or(Value::Null))
+            }
+            Some('"') => {
+                self.chars.next();
+                let mut s = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('"') => return Ok(Value::String(s)),
+                        Some('\\') => match self.chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some(c) => s.push(c),
+                            None => break,
+                        },
+                        Some(c) => s.push(c),
+                        None => break,
+                    }
+                }
+                Err("unterminated string".to_string())
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(&c) = self
+                    .chars
+                    .peek()
+                    .filter(|c| **c == '-' || c.is_ascii_digit())
+                {
+                    number.push(c);
+                    self.chars.next();
+                }
+                number
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .map_err(|_| format!("invalid number {number}"))
+            }
+            Some(_) => match self.name()?.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                other => Ok(Value::String(other.to_string())),
+            },
+            None => Err("expected a value".to_string()),
+        }
+    }
+
+    fn selection(&mut self) -> Result<Vec<Field>, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        while self.peek() != Some('}') {
+            let alias = self.name()?;
+            let name = if self.peek() == Some(':') {
+                self.chars.next();
+                self.name()?
+            } else {
+                alias.clone()
+            };
+            let mut args = Vec::new();
+            if self.peek() == Some('(') {
+                self.chars.next();
+                while self.peek() != Some(')') {
+                    let arg = self.name()?;
+                    self.expect(':')?;
+                    args.push((arg, self.value()?));
+                }
+                self.chars.next();
+            }
+            let selection = if self.peek() == Some('{') {
+                self.selection()?
+            } else {
+                Vec::new()
+            };
+            fields.push(Field {
+                alias,
+                name,
+                args,
+                selection,
+            });
+        }
+        self.chars.next();
+        Ok(fields)
+    }
+
+    fn document(&mut self) -> Result<Vec<Field>, String> {
+        if self.peek() != Some('{') {
+            let keyword = self.name()?;
+            if keyword != "query" {
+                return Err(format!("only queries are supported, not {keyword}"));
+            }
+            if self.peek().is_some_and(is_name) {
+                self.name()?;
+            }
+            // Variable definitions only matter for validation, the values
+            // come from the variables object.
+            if self.peek() == Some('(') {
+                while self.chars.next().is_some_and(|c| c != ')') {}
+            }
+        }
+        let fields = self.selection()?;
+        match self.peek() {
+            None => Ok(fields),
+            Some(c) => Err(format!("unexpected {c:?} after the query")),
+        }
+    }
+}
+
+fn select(
+    value: Value,
+    field: &Field,
+    resolve: &dyn Fn(&Field, &Value) -> Option<Value>,
+) -> Result<Value, String> {
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| select(item, field, resolve))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Value::Object(object) => {
+            if field.selection.is_empty() {
+                return Err(format!("{} needs a selection of fields", field.name));
+            }
+            let object = Value::Object(object);
+            let mut selected = Map::new();
+            for sub in &field.selection {
+                let value = match resolve(sub, &object) {
+                    Some(value) => value,
+                    None => match object.get(&sub.name) {
+                        Some(value) => value.clone(),
+                        None => {
+                            return Err(format!("unknown field {} on {}", sub.name, field.name))
+                        }
+                    },
+                };
+                selected.insert(sub.alias.clone(), select(value, sub, resolve)?);
+            }
+            Ok(Value::Object(selected))
+        }
+        scalar if field.selection.is_empty() => Ok(scalar),
+        _ => Err(format!("{} has no fields to select", field.name)),
+    }
+}
+
+// A suspended week has the blackout instead of a caretaker. Split weeks
+// and days off make several spans, shifts several caretakers.
+fn week_json(week: &CareWeek) -> Value {
+    serde_json::json!({
+        "week": week.week,
+        "isoWeek": iso_week_key(week.start_date),
+        "caretaker": week.suspended.is_none().then_some(&week.caretaker),
+        "caretakers": week.everyone(),
+        "spans": week
+            .spans()
+            .iter()
+            .map(|(caretaker, start, end)| serde_json::json!({
+                "caretaker": caretaker,
+                "startDate": start.to_string(),
+                "endDate": end.to_string(),
+            }))
+            .collect::<Vec<_>>(),
+        "shifts": week
+            .shifts
+            .iter()
+            .map(|s| serde_json::json!({ "shift": s.shift, "caretaker": s.caretaker }))
+            .collect::<Vec<_>>(),
+        "suspended": week.suspended,
+        "startDate": week.start_date.to_string(),
+        "endDate": week.end_date.to_string(),
+        "holidays": week.holidays_json(),
+    })
+}
+
+fn count(field: &Field) -> Result<usize, String> {
+    match field.arg("count") {
+        None | Some(Value::Null) => Ok(4),
+        Some(count) => count
+            .as_i64()
+            .filter(|c| (0..=MAX_WEEKS).contains(c))
+            .map(|c| c as usize)
+            .ok_or_else(|| format!("count has to be between 0 and {MAX_WEEKS}")),
+    }
+}
+
+fn resolve_root(conf: &Config, field: &Field, today: chrono::NaiveDate) -> Result<Value, String> {
+    Ok(match field.name.as_str() {
+        "schedule" => serde_json::json!({
+            "name": conf.name,
+            "startDate": conf.startdate.to_string(),
+            "caretakers": conf.everyone().collect::<Vec<_>>(),
+        }),
+        "current" => week_json(&get_weeks(conf, today, 1)[0]),
+        "weeks" => {
+            let from = match field.arg("from").and_then(Value::as_str) {
+                Some(from) => parse_week(from)
+                    .or_else(|| from.parse().ok())
+                    .ok_or_else(|| format!("invalid week {from}"))?,
+                None => today,
+            };
+            get_weeks(conf, from, count(field)?)
+                .iter()
+                .map(week_json)
+                .collect()
+        }
+        "caretaker" => {
+            let name = field
+                .arg("name")
+                .and_then(Value::as_str)
+                .ok_or("caretaker needs a name")?;
+            if !conf.is_caretaker(name) {
+                Value::Null
+            } else {
+                serde_json::json!({ "name": name })
+            }
+        }
+        "__schema" | "__type" => {
+            return Err("introspection is not supported, GET /graphql for the schema".to_string())
+        }
+        other => return Err(format!("unknown field {other} on Query")),
+    })
+}
+
+pub fn execute(conf: &Config, query: &str, variables: &Value, today: chrono::NaiveDate) -> Value {
+    let mut parser = Parser {
+        chars: query.chars().peekable(),
+        variables,
+    };
+    let result = parser.document().and_then(|fields| {
+        let errors = std::cell::RefCell::new(Vec::new());
+        // nextWeeks takes arguments, so it can't come from the object.
+        let resolve = |field: &Field, parent: &Value| -> Option<Value> {
+            if field.name != "nextWeeks" {
+                return None;
+            }
+            let name = parent.get("name")?.as_str()?;
+            let count = match count(field) {
+                Ok(count) => count,
+                Err(e) => {
+                    errors.borrow_mut().push(e);
+                    return Some(Value::Null);
+                }
+            };
+            let weeks: Vec<Value> = weeks_from(conf, today)
+                .take(MAX_WEEKS as usize)
+                .filter(|w| w.everyone().contains(&name))
+                .take(count)
+                .map(|w| week_json(&w))
+                .collect();
+            Some(weeks.into())
+        };
+        let mut data = Map::new();
+        for field in &fields {
+            let value = resolve_root(conf, field, today)?;
+            data.insert(field.alias.clone(), select(value, field, &resolve)?);
+        }
+        match errors.into_inner().first() {
+            Some(e) => Err(e.clone()),
+            None => Ok(Value::Object(data)),
+        }
+    });
+    match result {
+        Ok(data) => serde_json::json!({ "data": data }),
+        Err(message) => serde_json::json!({ "errors": [{ "message": message }] }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn execute_works() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let query = r#"
+            query Rotation($who: String!) {
+              schedule { caretakers }
+              weeks(from: "2024-W28", count: 2) { isoWeek caretaker }
+              jimmy: caretaker(name: $who) { name nextWeeks(count: 1) { startDate } }
+              nobody: caretaker(name: "Robert") { name }
+            }"#;
+        let result = execute(&conf, query, &serde_json::json!({ "who": "Jimmy" }), today);
+        assert_eq!(
+            result,
+            serde_json::json!({ "data": {
+                "schedule": { "caretakers": ["Edgar", "Jimmy"] },
+                "weeks": [
+                    { "isoWeek": "2024-W28", "caretaker": "Jimmy" },
+                    { "isoWeek": "2024-W29", "caretaker": "Edgar" },
+                ],
+                "jimmy": { "name": "Jimmy", "nextWeeks": [{ "startDate": "2024-07-08" }] },
+                "nobody": null,
+            }})
+        );
+
+        let result = execute(&conf, "{ current { owner } }", &Value::Null, today);
+        assert_eq!(
+            result["errors"][0]["message"],
+            "unknown field owner on current"
+        );
+        let result = execute(&conf, "mutation { swap }", &Value::Null, today);
+        assert!(result["errors"][0]["message"].is_string());
+    }
+
+    #[test]
+    fn split_weeks_have_spans() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            reschedule: HashMap::from([("2024-W29".to_string(), "Edgar/Jimmy".to_string())]),
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let query = r#"{
+              weeks(from: "2024-W29", count: 1) { caretakers spans { caretaker startDate } }
+              caretaker(name: "Jimmy") { nextWeeks(count: 2) { isoWeek } }
+            }"#;
+        let result = execute(&conf, query, &Value::Null, today);
+        assert_eq!(
+            result,
+            serde_json::json!({ "data": {
+                "weeks": [{
+                    "caretakers": ["Edgar", "Jimmy"],
+                    "spans": [
+                        { "caretaker": "Edgar", "startDate": "2024-07-15" },
+                        { "caretaker": "Jimmy", "startDate": "2024-07-18" },
+                    ],
+                }],
+                "caretaker": { "nextWeeks": [{ "isoWeek": "2024-W28" }, { "isoWeek": "2024-W29" }] },
+            }})
+        );
+    }
+
+    #[test]
+    fn suspended_weeks_have_no_caretaker() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            blackouts: serde_json::from_str(
+                r#"[{"name": "closed", "start": "2024-07-08", "end": "2024-07-14"}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let query = r#"{
+              weeks(from: "2024-W28", count: 1) { caretaker suspended }
+              caretaker(name: "Jimmy") { nextWeeks(count: 1) { isoWeek } }
+            }"#;
+        let result = execute(&conf, query, &Value::Null, today);
+        assert_eq!(
+            result,
+            serde_json::json!({ "data": {
+                "weeks": [{ "caretaker": null, "suspended": "closed" }],
+                "caretaker": { "nextWeeks": [{ "isoWeek": "2024-W30" }] },
+            }})
+        );
+    }
+
+    #[test]
+    fn days_make_spans() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            per_weekday: true,
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-07-01").unwrap();
+        let query = r#"{
+              current { caretakers spans { caretaker endDate } }
+              caretaker(name: "Jimmy") { nextWeeks(count: 1) { isoWeek } }
+            }"#;
+        let result = execute(&conf, query, &Value::Null, today);
+        let current = &result["data"]["current"];
+        assert_eq!(current["caretakers"], serde_json::json!(["Edgar", "Jimmy"]));
+        assert_eq!(current["spans"].as_array().unwrap().len(), 7);
+        assert_eq!(
+            current["spans"][1],
+            serde_json::json!({ "caretaker": "Jimmy", "endDate": "2024-07-02" })
+        );
+        assert_eq!(
+            result["data"]["caretaker"]["nextWeeks"],
+            serde_json::json!([{ "isoWeek": "2024-W27" }])
+        );
+    }
+
+    #[test]
+    fn shifts_have_their_caretakers() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string(), "Robert".to_string()],
+            shifts: serde_json::from_str(
+                r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00", "offset": 1}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-07-01").unwrap();
+        let query = r#"{
+              current { caretakers shifts { shift caretaker } }
+              caretaker(name: "Jimmy") { nextWeeks(count: 2) { isoWeek } }
+            }"#;
+        let result = execute(&conf, query, &Value::Null, today);
+        assert_eq!(
+            result,
+            serde_json::json!({ "data": {
+                "current": {
+                    "caretakers": ["Edgar", "Jimmy"],
+                    "shifts": [
+                        { "shift": "day", "caretaker": "Edgar" },
+                        { "shift": "night", "caretaker": "Jimmy" },
+                    ],
+                },
+                "caretaker": { "nextWeeks": [{ "isoWeek": "2024-W27" }, { "isoWeek": "2024-W28" }] },
+            }})
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{iso_week_key, CareWeek};
+use chrono::NaiveDate;
+
+// Whoever has the day, split and per-weekday weeks included. Empty if it
+// isn't covered.
+fn on_day(week: &CareWeek, day: NaiveDate) -> &str {
+    week.spans()
+        .into_iter()
+        .find(|(_, start, end)| *start <= day && day <= *end)
+        .map_or("", |(caretaker, _, _)| caretaker)
+}
+
+// A row per week and a column per covered weekday.
+pub fn grid(weeks: &[CareWeek]) -> String {
+    let Some(first) = weeks.first() else {
+        return String::new();
+    };
+    let columns: Vec<usize> = (0..7)
+        .filter(|i| {
+            weeks
+                .iter()
+                .any(|w| w.coverage.contains(w.start_date + chrono::Days::new(*i)))
+        })
+        .map(|i| i as usize)
+        .collect();
+    let header: Vec<String> = columns
+        .iter()
+        .map(|i| {
+            (first.start_date + chrono::Days::new(*i as u64))
+                .format("%a")
+                .to_string()
+        })
+        .collect();
+    let rows: Vec<(String, Vec<&str>)> = weeks
+        .iter()
+        .map(|w| {
+            let days = w.start_date.iter_days().take(7).collect::<Vec<_>>();
+            let names = columns.iter().map(|i| on_day(w, days[*i])).collect();
+            (iso_week_key(w.start_date), names)
+        })
+        .collect();
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(i, day)| {
+            rows.iter()
+                .map(|(_, names)| names[i].chars().count())
+                .chain([day.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let line = |label: &str, cells: Vec<&str>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        format!("{label:8}  {}", cells.join("  "))
+            .trim_end()
+            .to_string()
+            + "\n"
+    };
+    let mut grid = line("week", header.iter().map(String::as_str).collect());
+    for (week, names) in &rows {
+        grid.push_str(&line(week, names.clone()));
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::str::FromStr;
+
+    #[test]
+    fn grid_works() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            per_weekday: true,
+            days: serde_json::from_str(r#"["mon", "tue", "sat"]"#).unwrap(),
+            ..Default::default()
+        };
+        let weeks = crate::get_weeks(&conf, NaiveDate::from_str("2024-07-01").unwrap(), 2);
+        assert_eq!(
+            grid(&weeks),
+            "week      Mon    Tue    Sat\n\
+             2024-W27  Edgar  Jimmy  Jimmy\n\
+             2024-W28  Jimmy  Edgar  Edgar\n"
+        );
+    }
+}
@@ -0,0 +1,414 @@
+// The service of proto/whocares.proto as gRPC-Web, which works over the
+// HTTP/1.1 of `whocares serve`. Plain gRPC needs HTTP/2, so those clients
+// go through a proxy like Envoy's grpc_web filter.
+use crate::http::url_encode;
+use crate::{get_weeks, parse_week, weeks_from, CareWeek, Config, MAX_WEEKS};
+use chrono::NaiveDate;
+use whocares_core::Week;
+
+pub const PREFIX: &str = "/whocares.v1.Rotation/";
+pub const WATCH_CHANGES: &str = "/whocares.v1.Rotation/WatchChanges";
+pub const CONTENT_TYPE: &str = "application/grpc-web+proto";
+const DEFAULT_WEEKS: usize = 8;
+const TRAILERS: u8 = 0x80;
+const COMPRESSED: u8 = 0x01;
+
+// The status codes of gRPC used here.
+const OK: u32 = 0;
+const INVALID_ARGUMENT: u32 = 3;
+const NOT_FOUND: u32 = 5;
+const UNIMPLEMENTED: u32 = 12;
+
+struct Status {
+    code: u32,
+    message: String,
+}
+
+impl Status {
+    fn new(code: u32, message: impl Into<String>) -> Self {
+        Status {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+// application/grpc-web is proto too.
+pub fn accepts(content_type: Option<&str>) -> bool {
+    let content_type = content_type.unwrap_or_default();
+    let content_type = content_type.split(';').next().unwrap_or_default().trim();
+    content_type == "application/grpc-web" || content_type == CONTENT_TYPE
+}
+
+// The protobuf encoding of a message, leaving out the fields that have
+// their default value like proto3 does.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.0.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.0.push(n as u8);
+    }
+
+    fn uint(mut self, field: u32, n: u64) -> Self {
+        if n != 0 {
+            self.varint(u64::from(field) << 3);
+            self.varint(n);
+        }
+        self
+    }
+
+    fn bytes(mut self, field: u32, bytes: &[u8]) -> Self {
+        self.varint(u64::from(field) << 3 | 2);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn string(self, field: u32, s: &str) -> Self {
+        if s.is_empty() {
+            return self;
+        }
+        self.bytes(field, s.as_bytes())
+    }
+
+    fn message(self, field: u32, message: Message) -> Self {
+        self.bytes(field, &message.0)
+    }
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, Status> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| Status::new(INVALID_ARGUMENT, "truncated message"))?;
+        *bytes = rest;
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(n);
+        }
+    }
+    Err(Status::new(INVALID_ARGUMENT, "varint too long"))
+}
+
+// The fields of a message, skipping the fixed size ones no request has.
+fn decode(mut bytes: &[u8]) -> Result<Vec<(u64, Field<'_>)>, Status> {
+    let mut fields = Vec::new();
+    while !bytes.is_empty() {
+        let key = read_varint(&mut bytes)?;
+        let field = match key & 7 {
+            0 => Field::Varint(read_varint(&mut bytes)?),
+            1 | 5 => {
+                let len = if key & 7 == 1 { 8 } else { 4 };
+                bytes = bytes
+                    .get(len..)
+                    .ok_or_else(|| Status::new(INVALID_ARGUMENT, "truncated message"))?;
+                continue;
+            }
+            2 => {
+                let len = read_varint(&mut bytes)? as usize;
+                if len > bytes.len() {
+                    return Err(Status::new(INVALID_ARGUMENT, "truncated message"));
+                }
+                let (value, rest) = bytes.split_at(len);
+                bytes = rest;
+                Field::Bytes(value)
+            }
+            wire => {
+                return Err(Status::new(
+                    INVALID_ARGUMENT,
+                    format!("unsupported wire type {wire}"),
+                ))
+            }
+        };
+        fields.push((key >> 3, field));
+    }
+    Ok(fields)
+}
+
+fn frame(flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![flags];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// The message of a request, which has exactly one.
+fn unframe(body: &[u8]) -> Result<&[u8], Status> {
+    let (header, payload) = body
+        .split_at_checked(5)
+        .ok_or_else(|| Status::new(INVALID_ARGUMENT, "expected a gRPC-Web frame"))?;
+    if header[0] & COMPRESSED != 0 {
+        return Err(Status::new(
+            UNIMPLEMENTED,
+            "compressed messages are not supported",
+        ));
+    }
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    payload
+        .get(..len)
+        .ok_or_else(|| Status::new(INVALID_ARGUMENT, "truncated frame"))
+}
+
+fn trailers(status: &Status) -> Vec<u8> {
+    let mut trailers = format!("grpc-status: {}\r\n", status.code);
+    if !status.message.is_empty() {
+        trailers.push_str(&format!(
+            "grpc-message: {}\r\n",
+            url_encode(&status.message)
+        ));
+    }
+    frame(TRAILERS, trailers.as_bytes())
+}
+
+fn week(week: &CareWeek) -> Message {
+    let holidays = week.holidays.iter().map(|h| {
+        Message::default()
+            .string(1, &h.date.to_string())
+            .string(2, h.name)
+    });
+    holidays.fold(
+        Message::default()
+            .uint(1, u64::from(week.week))
+            .string(2, &Week::containing(week.start_date).to_string())
+            .string(3, &week.caretaker)
+            .string(4, &week.start_date.to_string())
+            .string(5, &week.end_date.to_string()),
+        |message, holiday| message.message(6, holiday),
+    )
+}
+
+fn current(conf: &Config, today: NaiveDate) -> Option<CareWeek> {
+    get_weeks(conf, today.max(conf.startdate), 1)
+        .pop()
+        .filter(|week| week.start_date <= today)
+}
+
+fn list_weeks(conf: &Config, request: &[u8], today: NaiveDate) -> Result<Message, Status> {
+    let (mut from, mut count, mut caretaker) = (None, DEFAULT_WEEKS, None);
+    for (number, field) in decode(request)? {
+        match (number, field) {
+            (1, Field::Bytes(s)) => from = Some(String::from_utf8_lossy(s).into_owned()),
+            (2, Field::Varint(n)) if n > 0 => count = (n as usize).min(MAX_WEEKS as usize),
+            (3, Field::Bytes(s)) => caretaker = Some(String::from_utf8_lossy(s).into_owned()),
+            _ => {}
+        }
+    }
+    let from = match from.filter(|from| !from.is_empty()) {
+        Some(from) => parse_week(&from)
+            .ok_or_else(|| Status::new(INVALID_ARGUMENT, format!("invalid week {from}")))?,
+        None => today.max(conf.startdate),
+    };
+    let weeks = match caretaker.filter(|name| !name.is_empty()) {
+        Some(name) if !conf.is_caretaker(&name) => {
+            return Err(Status::new(NOT_FOUND, format!("{name} is no caretaker")))
+        }
+        Some(name) => weeks_from(conf, from)
+            .take(MAX_WEEKS as usize)
+            .filter(|w| w.everyone().contains(&name.as_str()))
+            .take(count)
+            .collect(),
+        None => get_weeks(conf, from, count),
+    };
+    Ok(weeks
+        .iter()
+        .fold(Message::default(), |message, w| message.message(1, week(w))))
+}
+
+fn unary(conf: &Config, method: &str, request: &[u8], today: NaiveDate) -> Result<Message, Status> {
+    match method {
+        "GetCurrent" => current(conf, today)
+            .map(|w| week(&w))
+            .ok_or_else(|| Status::new(NOT_FOUND, "the rotation hasn't started")),
+        "ListWeeks" => list_weeks(conf, request, today),
+        _ => Err(Status::new(
+            UNIMPLEMENTED,
+            format!("unknown method {method}"),
+        )),
+    }
+}
+
+// The body of the response to a unary call, errors are in its trailers.
+pub fn call(conf: &Config, path: &str, body: &[u8], today: NaiveDate) -> Vec<u8> {
+    let method = path.strip_prefix(PREFIX).unwrap_or(path);
+    match unframe(body).and_then(|request| unary(conf, method, request, today)) {
+        Ok(message) => {
+            let mut body = frame(0, &message.0);
+            body.extend(trailers(&Status::new(OK, "")));
+            body
+        }
+        Err(status) => trailers(&status),
+    }
+}
+
+// The weeks WatchChanges looks at.
+pub fn watched(conf: &Config, today: NaiveDate) -> Vec<CareWeek> {
+    get_weeks(conf, today.max(conf.startdate), DEFAULT_WEEKS)
+}
+
+// A Change frame with the weeks whose caretakers differ from before.
+pub fn change(conf: &Config, before: &[CareWeek], after: &[CareWeek], today: NaiveDate) -> Vec<u8> {
+    let changed = after.iter().filter(|w| {
+        before
+            .iter()
+            .find(|b| b.start_date == w.start_date)
+            .is_none_or(|b| b.caretakers() != w.caretakers())
+    });
+    let mut message = Message::default();
+    if let Some(current) = current(conf, today) {
+        message = message.message(1, week(&current));
+    }
+    let message = changed.fold(message, |message, w| message.message(2, week(w)));
+    frame(0, &message.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn config() -> Config {
+        Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            ..Default::default()
+        }
+    }
+
+    // The messages and the status of a response.
+    fn parse(mut body: &[u8]) -> (Vec<Vec<u8>>, String) {
+        let mut messages = Vec::new();
+        while !body.is_empty() {
+            let payload = unframe(body).unwrap_or_default().to_vec();
+            body = &body[5 + payload.len()..];
+            if body.is_empty() {
+                return (messages, String::from_utf8(payload).unwrap());
+            }
+            messages.push(payload);
+        }
+        (messages, String::new())
+    }
+
+    fn fields(message: &[u8], number: u64) -> Vec<Vec<u8>> {
+        decode(message)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(n, field)| match field {
+                Field::Bytes(bytes) if n == number => Some(bytes.to_vec()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn strings(message: &[u8], number: u64) -> Vec<String> {
+        let fields = fields(message, number).into_iter();
+        fields.map(|s| String::from_utf8(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn list_weeks_works() {
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let request = Message::default()
+            .string(1, "2024-W28")
+            .uint(2, 2)
+            .string(3, "Edgar");
+        let body = call(
+            &config(),
+            "/whocares.v1.Rotation/ListWeeks",
+            &frame(0, &request.0),
+            today,
+        );
+        let (messages, status) = parse(&body);
+        assert_eq!(status, "grpc-status: 0\r\n");
+        let weeks = fields(&messages[0], 1);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(strings(&weeks[0], 2), ["2024-W29"]);
+        assert_eq!(strings(&weeks[0], 3), ["Edgar"]);
+        assert_eq!(strings(&weeks[1], 4), ["2024-07-29"]);
+
+        let body = call(
+            &config(),
+            "/whocares.v1.Rotation/ListWeeks",
+            &frame(0, &[]),
+            today,
+        );
+        let (messages, _) = parse(&body);
+        assert_eq!(fields(&messages[0], 1).len(), DEFAULT_WEEKS);
+    }
+
+    #[test]
+    fn errors_are_in_the_trailers() {
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let status = |path: &str, request: Message| {
+            let body = call(&config(), path, &frame(0, &request.0), today);
+            let (messages, status) = parse(&body);
+            assert!(messages.is_empty());
+            status
+        };
+        assert_eq!(
+            status(
+                "/whocares.v1.Rotation/ListWeeks",
+                Message::default().string(1, "nope")
+            ),
+            "grpc-status: 3\r\ngrpc-message: invalid%20week%20nope\r\n"
+        );
+        assert!(status(
+            "/whocares.v1.Rotation/ListWeeks",
+            Message::default().string(3, "Robert")
+        )
+        .starts_with("grpc-status: 5\r\n"));
+        assert!(status("/whocares.v1.Rotation/Nope", Message::default())
+            .starts_with("grpc-status: 12\r\n"));
+        let early = NaiveDate::from_str("2024-06-01").unwrap();
+        let body = call(
+            &config(),
+            "/whocares.v1.Rotation/GetCurrent",
+            &frame(0, &[]),
+            early,
+        );
+        assert!(parse(&body).1.starts_with("grpc-status: 5\r\n"));
+        let body = call(
+            &config(),
+            "/whocares.v1.Rotation/GetCurrent",
+            &[0, 0],
+            today,
+        );
+        assert!(parse(&body).1.starts_with("grpc-status: 3\r\n"));
+    }
+
+    #[test]
+    fn change_has_the_changed_weeks() {
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let before = watched(&config(), today);
+        let conf = Config {
+            reschedule: [("2024-W29".to_string(), "Jimmy".to_string())].into(),
+            ..config()
+        };
+        let after = watched(&conf, today);
+        let frame = change(&conf, &before, &after, today);
+        let (messages, _) = parse(&[frame, trailers(&Status::new(OK, ""))].concat());
+        let current = fields(&messages[0], 1);
+        assert_eq!(strings(&current[0], 3), ["Edgar"]);
+        let changed = fields(&messages[0], 2);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(strings(&changed[0], 2), ["2024-W29"]);
+    }
+
+    #[test]
+    fn accepts_works() {
+        assert!(accepts(Some("application/grpc-web+proto")));
+        assert!(accepts(Some("application/grpc-web; charset=utf-8")));
+        assert!(!accepts(Some("application/grpc-web-text")));
+        assert!(!accepts(None));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::dry_run;
+#[cfg(feature = "sqlite")]
+use crate::store::{quote, Db};
+use crate::{iso_week_key, CareWeek, Config};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_FILE: &str = "history.json";
+
+// Who actually covered a week, keyed like "2025-W31". Unlike reschedules
+// these are records of the past, not plans.
+pub struct History {
+    path: PathBuf,
+    #[cfg(feature = "sqlite")]
+    db: Option<Db>,
+    pub covered: BTreeMap<String, String>,
+}
+
+fn read(path: &Path) -> io::Result<BTreeMap<String, String>> {
+    match fs::read_to_string(path) {
+        Ok(s) => Ok(serde_json::from_str(&s)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+impl History {
+    pub fn load(config_path: &str, conf: &Config) -> io::Result<Self> {
+        Self::load_weeks(config_path, conf, None)
+    }
+
+    // Just the weeks from `from` to `to`, which a database is asked for.
+    // Only records are added, so saving keeps the other weeks.
+    pub fn load_between(
+        config_path: &str,
+        conf: &Config,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> io::Result<Self> {
+        let weeks = (iso_week_key(from), iso_week_key(to));
+        Self::load_weeks(config_path, conf, Some(weeks))
+    }
+
+    fn load_weeks(
+        config_path: &str,
+        conf: &Config,
+        weeks: Option<(String, String)>,
+    ) -> io::Result<Self> {
+        let file_name = conf.history_file.as_deref().unwrap_or(DEFAULT_FILE);
+        let path = Path::new(config_path).with_file_name(file_name);
+        match conf.database.as_deref() {
+            #[cfg(feature = "sqlite")]
+            Some(database) => {
+                let db = Db::open(config_path, database);
+                let filter = match &weeks {
+                    Some((from, to)) => {
+                        format!(" WHERE week BETWEEN {} AND {}", quote(from), quote(to))
+                    }
+                    None => String::new(),
+                };
+                let rows = db.run(&format!("SELECT week, caretaker FROM history{filter};"))?;
+                let covered = rows
+                    .iter()
+                    .filter_map(|row| {
+                        let week = row["week"].as_str()?;
+                        Some((week.to_string(), row["caretaker"].as_str()?.to_string()))
+                    })
+                    .collect();
+                Ok(History {
+                    path,
+                    db: Some(db),
+                    covered,
+                })
+            }
+            _ => {
+                let mut covered = read(&path)?;
+                if let Some((from, to)) = &weeks {
+                    covered.retain(|week, _| from <= week && week <= to);
+                }
+                Ok(History {
+                    path,
+                    #[cfg(feature = "sqlite")]
+                    db: None,
+                    covered,
+                })
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        #[cfg(feature = "sqlite")]
+        if let Some(db) = &self.db {
+            if dry_run::enabled() {
+                println!("{}would write {}", dry_run::prefix(), db.path().display());
+                return Ok(());
+            }
+            let rows: Vec<String> = self
+                .covered
+                .iter()
+                .map(|(week, caretaker)| format!("({}, {})", quote(week), quote(caretaker)))
+                .collect();
+            if !rows.is_empty() {
+                db.run(&format!(
+                    "INSERT OR REPLACE INTO history VALUES {};",
+                    rows.join(", ")
+                ))?;
+            }
+            return Ok(());
+        }
+        if dry_run::enabled() {
+            println!("{}would write {}", dry_run::prefix(), self.path.display());
+            return Ok(());
+        }
+        let mut covered = read(&self.path)?;
+        covered.extend(self.covered.clone());
+        fs::write(&self.path, serde_json::to_string_pretty(&covered)?)
+    }
+
+    pub fn record(&mut self, start_of_week: NaiveDate, caretaker: &str) {
+        self.covered
+            .insert(iso_week_key(start_of_week), caretaker.to_string());
+    }
+
+    pub fn apply(&self, weeks: &mut [CareWeek]) {
+        for week in weeks {
+            if let Some(caretaker) = self.covered.get(&iso_week_key(week.start_date)) {
+                week.caretaker = caretaker.as_str().into();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn apply_overrides_scheduled_caretaker() {
+        let start = NaiveDate::from_str("2025-07-28").unwrap();
+        let mut history = History {
+            path: PathBuf::new(),
+            #[cfg(feature = "sqlite")]
+            db: None,
+            covered: BTreeMap::new(),
+        };
+        history.record(start, "Bob");
+        assert_eq!(history.covered["2025-W31"], "Bob");
+
+        let mut weeks = vec![CareWeek {
+            caretaker: "Alice".into(),
+            start_date: start,
+            ..Default::default()
+        }];
+        history.apply(&mut weeks);
+        assert_eq!(&*weeks[0].caretaker, "Bob");
+    }
+
+    #[test]
+    fn load_between_keeps_the_other_weeks() {
+        let dir = std::env::temp_dir().join(format!("whocares-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        let config = config.to_str().unwrap();
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let dbs = [
+            None,
+            #[cfg(feature = "sqlite")]
+            Some("whocares.db".to_string()),
+        ];
+        for database in dbs {
+            let conf = Config {
+                database,
+                ..Config::default()
+            };
+            let mut history = History::load(config, &conf).unwrap();
+            history.record(date("2025-01-06"), "Alice");
+            history.record(date("2025-07-28"), "Bob");
+            history.save().unwrap();
+
+            let mut history =
+                History::load_between(config, &conf, date("2025-07-01"), date("2025-07-31"))
+                    .unwrap();
+            assert_eq!(history.covered.keys().collect::<Vec<_>>(), ["2025-W31"]);
+            history.record(date("2025-07-21"), "Carol");
+            history.save().unwrap();
+            assert_eq!(History::load(config, &conf).unwrap().covered.len(), 3);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+use crate::dry_run;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn error_for_status(self) -> io::Result<Response> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(io::Error::other(format!(
+                "HTTP {}: {}",
+                self.status,
+                self.body.trim()
+            )))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    pub headers: Vec<(&'a str, String)>,
+    pub basic_auth: Option<(&'a str, &'a str)>,
+    pub body: Option<&'a str>,
+    // For the whole request, TIMEOUT if None.
+    pub timeout: Option<Duration>,
+}
+
+// A double-quoted string in a curl config file.
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+// Header or form field names whose values are credentials.
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["auth", "token", "secret", "key", "password", "signature"]
+        .iter()
+        .any(|secret| name.contains(secret))
+}
+
+// The values of credentials in "a=1&b=2", as in query strings and forms.
+fn redact_fields(fields: &str) -> String {
+    fields
+        .split('&')
+        .map(|field| match field.split_once('=') {
+            Some((name, _)) if is_secret(name) => format!("{name}=***"),
+            _ => field.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+impl Request<'_> {
+    // What a dry run shows instead of sending, without the credentials.
+    fn describe(&self) -> String {
+        let url = match self.url.split_once('?') {
+            Some((path, query)) => format!("{path}?{}", redact_fields(query)),
+            None => self.url.to_string(),
+        };
+        let mut description = format!("{} {url}", self.method);
+        let mut form = false;
+        for (name, value) in &self.headers {
+            let value = if is_secret(name) { "***" } else { value };
+            form |= name.eq_ignore_ascii_case("Content-Type")
+                && value.starts_with("application/x-www-form-urlencoded");
+            description.push_str(&format!("\n  {name}: {value}"));
+        }
+        if let Some((user, _)) = self.basic_auth {
+            description.push_str(&format!("\n  user {user}, password ***"));
+        }
+        if let Some(body) = self.body {
+            let body = if form {
+                redact_fields(body)
+            } else {
+                body.to_string()
+            };
+            description.push_str(&format!("\n  {}", body.trim_end().replace('\n', "\n  ")));
+        }
+        description
+    }
+
+    // Only what's fine to show up in ps. Everything else, credentials
+    // included, goes through config() on stdin.
+    fn args(&self) -> Vec<String> {
+        [
+            "-sS",
+            "-X",
+            self.method,
+            "-w",
+            "\n%{http_code}",
+            "--connect-timeout",
+            &CONNECT_TIMEOUT.as_secs().to_string(),
+            "--max-time",
+            &self.timeout.unwrap_or(TIMEOUT).as_secs_f64().to_string(),
+            "--config",
+            "-",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn config(&self) -> String {
+        let mut config = String::new();
+        for (name, value) in &self.headers {
+            config.push_str(&format!(
+                "header = {}\n",
+                quote(&format!("{name}: {value}"))
+            ));
+        }
+        if let Some((user, password)) = self.basic_auth {
+            config.push_str(&format!(
+                "user = {}\n",
+                quote(&format!("{user}:{password}"))
+            ));
+        }
+        if let Some(body) = self.body {
+            config.push_str(&format!("data-raw = {}\n", quote(body)));
+        }
+        config.push_str(&format!("url = {}\n", quote(self.url)));
+        config
+    }
+
+    pub fn send(&self) -> io::Result<Response> {
+        if dry_run::enabled() && self.method != "GET" {
+            println!("{}would {}", dry_run::prefix(), self.describe());
+            return Ok(Response {
+                status: 200,
+                body: String::new(),
+            });
+        }
+        let mut cmd = Command::new("curl");
+        cmd.args(self.args())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(self.config().as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{} {} failed: {}",
+                self.method,
+                self.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        parse_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(any(feature = "sync", feature = "notifications", feature = "server"))]
+pub fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn parse_output(output: &str) -> io::Result<Response> {
+    let (body, status) = output.rsplit_once('\n').unwrap_or(("", output));
+    let status = status
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::other(format!("invalid HTTP status: {status}")))?;
+    Ok(Response {
+        status,
+        body: body.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_works() {
+        let response = parse_output("{\"ok\":true}\n201").unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body, "{\"ok\":true}");
+        assert!(response.is_success());
+
+        let response = parse_output("\n404").unwrap();
+        assert!(response.error_for_status().is_err());
+    }
+
+    #[test]
+    fn credentials_stay_off_the_command_line() {
+        let request = Request {
+            method: "PUT",
+            url: "https://dav.example/cal/?token=t0k",
+            headers: vec![("Authorization", "Bearer s3cret".to_string())],
+            basic_auth: Some(("edgar", "hunter2")),
+            body: Some("BEGIN:VCALENDAR\r\nSUMMARY:\"@home\" \\o/\r\n"),
+            ..Default::default()
+        };
+        let args = request.args().join(" ");
+        for secret in ["s3cret", "hunter2", "t0k", "VCALENDAR"] {
+            assert!(!args.contains(secret), "{secret} in {args}");
+        }
+        assert_eq!(
+            request.config(),
+            "header = \"Authorization: Bearer s3cret\"\n\
+             user = \"edgar:hunter2\"\n\
+             data-raw = \"BEGIN:VCALENDAR\\r\\nSUMMARY:\\\"@home\\\" \\\\o/\\r\\n\"\n\
+             url = \"https://dav.example/cal/?token=t0k\"\n"
+        );
+    }
+
+    #[test]
+    fn describe_redacts_credentials() {
+        let request = Request {
+            method: "POST",
+            url: "https://login.example/token?api_key=k3y&tenant=acme",
+            headers: vec![
+                ("Authorization", "Bearer s3cret".to_string()),
+                (
+                    "Content-Type",
+                    "application/x-www-form-urlencoded".to_string(),
+                ),
+            ],
+            basic_auth: Some(("edgar", "hunter2")),
+            body: Some("client_id=whocares&client_secret=t0p"),
+            ..Default::default()
+        };
+        assert_eq!(
+            request.describe(),
+            "POST https://login.example/token?api_key=***&tenant=acme\n  \
+             Authorization: ***\n  \
+             Content-Type: application/x-www-form-urlencoded\n  \
+             user edgar, password ***\n  \
+             client_id=whocares&client_secret=***"
+        );
+        let request = Request {
+            method: "PUT",
+            url: "https://dav.example/cal/1.ics",
+            body: Some("BEGIN:VCALENDAR\r\nSUMMARY:Edgar\r\n"),
+            ..Default::default()
+        };
+        assert_eq!(
+            request.describe(),
+            "PUT https://dav.example/cal/1.ics\n  BEGIN:VCALENDAR\r\n  SUMMARY:Edgar"
+        );
+    }
+
+    #[cfg(any(feature = "sync", feature = "notifications", feature = "server"))]
+    #[test]
+    fn url_encode_works() {
+        assert_eq!(url_encode("a b/c~"), "a%20b%2Fc~");
+    }
+}
@@ -0,0 +1,314 @@
+use crate::CareWeek;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const ISO_DATE: &str = "%Y-%m-%d";
+const DE_WEEKDAYS: [&str; 7] = [
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+    "Sonntag",
+];
+const DE_MONTHS: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl Locale {
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+        }
+    }
+}
+
+// How dates and messages are shown to people. Machine-readable outputs
+// (JSON, ICS) always use ISO dates.
+#[derive(Deserialize, Default)]
+pub struct Formatting {
+    #[serde(default)]
+    pub locale: Locale,
+    // strftime pattern like "%a %d.%m.", names follow the locale.
+    #[serde(default)]
+    pub date_format: Option<String>,
+    // The caretakers' emoji from their contacts, set when loading the config.
+    #[serde(skip)]
+    pub emoji: HashMap<String, String>,
+}
+
+impl Formatting {
+    pub fn date(&self, date: NaiveDate) -> String {
+        let mut pattern = self.date_format.as_deref().unwrap_or(ISO_DATE).to_string();
+        if self.locale == Locale::De {
+            let weekday = DE_WEEKDAYS[date.weekday().num_days_from_monday() as usize];
+            let month = DE_MONTHS[date.month0() as usize];
+            pattern = pattern
+                .replace("%A", weekday)
+                .replace("%a", &weekday[..2])
+                .replace("%B", month)
+                .replace("%b", &month.chars().take(3).collect::<String>());
+        }
+        date.format(&pattern).to_string()
+    }
+
+    // A caretaker's name after their emoji, if they have one.
+    pub fn name(&self, caretaker: &str) -> String {
+        match self.emoji.get(caretaker) {
+            Some(emoji) => format!("{emoji} {caretaker}"),
+            None => caretaker.to_string(),
+        }
+    }
+
+    // `CareWeek::caretakers` after the emoji of everyone in it, like
+    // "🦊🐻 Alice/Bob from Thu".
+    pub fn caretakers(&self, week: &CareWeek) -> String {
+        let mut emoji = String::new();
+        let mut seen = Vec::new();
+        for (caretaker, _, _) in week.spans() {
+            if let Some(e) = self
+                .emoji
+                .get(caretaker)
+                .filter(|_| !seen.contains(&caretaker))
+            {
+                emoji.push_str(e);
+            }
+            seen.push(caretaker);
+        }
+        match emoji.is_empty() {
+            true => week.caretakers(),
+            false => format!("{emoji} {}", week.caretakers()),
+        }
+    }
+}
+
+// User-facing messages by key. Placeholders in braces are filled by `format`.
+fn template(locale: Locale, key: &str) -> &'static str {
+    match (locale, key) {
+        (Locale::De, "week") => "Woche",
+        (Locale::De, "this-week") => "Diese Woche",
+        (Locale::De, "next") => "Danach",
+        (Locale::De, "nobody") => "niemand",
+        (Locale::De, "nobody-scheduled") => "Niemand ist eingeplant.",
+        (Locale::De, "handoff") => "{caretaker} kümmert sich diese Woche",
+        (Locale::De, "reminder") => "Erinnerung: {caretaker} kümmert sich nächste Woche",
+        (Locale::De, "suspended") => "ausgesetzt ({blackout})",
+        (Locale::De, "suspended-handoff") => "Diese Woche ist ausgesetzt: {blackout}",
+        (Locale::De, "suspended-reminder") => {
+            "Erinnerung: nächste Woche ist ausgesetzt: {blackout}"
+        }
+        (Locale::De, "total") => "Insgesamt",
+        (Locale::De, "subtotal") => "{caretaker}: {weeks} Woche(n)",
+        (Locale::De, "starts") => {
+            "Die Rotation beginnt am {date}, {weeks} Woche(n) bis dahin ist niemand eingeplant"
+        }
+        (_, "week") => "week",
+        (_, "this-week") => "This week",
+        (_, "next") => "Next",
+        (_, "nobody") => "nobody",
+        (_, "nobody-scheduled") => "Nobody is scheduled.",
+        (_, "handoff") => "{caretaker} is taking care this week",
+        (_, "reminder") => "Reminder: {caretaker} is taking care next week",
+        (_, "suspended") => "suspended ({blackout})",
+        (_, "suspended-handoff") => "Nobody takes care this week: {blackout}",
+        (_, "suspended-reminder") => "Reminder: nobody takes care next week: {blackout}",
+        (_, "total") => "Total",
+        (_, "subtotal") => "{caretaker}: {weeks} week(s)",
+        (_, "starts") => {
+            "The rotation starts on {date}, nobody is scheduled for the {weeks} week(s) until then"
+        }
+        _ => "",
+    }
+}
+
+pub fn tr(locale: Locale, key: &str) -> &'static str {
+    template(locale, key)
+}
+
+pub fn format(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template(locale, key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+pub fn week_line(formatting: &Formatting, week: &CareWeek) -> String {
+    let block = week
+        .block
+        .as_ref()
+        .map(|b| format!(" [{b}]"))
+        .unwrap_or_default();
+    let caretakers = match &week.suspended {
+        Some(blackout) => format(formatting.locale, "suspended", &[("blackout", blackout)]),
+        None => formatting.caretakers(week),
+    };
+    let (first, last) = week.period();
+    format!(
+        "{} #{} {} - {}: {caretakers}{}{}{block}{}",
+        tr(formatting.locale, "week"),
+        week.week,
+        formatting.date(first),
+        formatting.date(last),
+        week.overlap_note(),
+        week.holiday_note(),
+        week.pending_note()
+    )
+}
+
+pub fn month_line(formatting: &Formatting, date: NaiveDate) -> String {
+    Formatting {
+        locale: formatting.locale,
+        date_format: Some("%B %Y".to_string()),
+        ..Default::default()
+    }
+    .date(date)
+}
+
+// "  Alice: 2 week(s), Bob: 1 week(s)", in order of their first week
+// after `everyone`, who are listed even without a week.
+pub fn subtotal_line(formatting: &Formatting, everyone: &[&str], weeks: &[CareWeek]) -> String {
+    let mut counts: Vec<(&str, usize)> = everyone.iter().map(|c| (*c, 0)).collect();
+    for week in weeks.iter().filter(|w| w.suspended.is_none()) {
+        match counts.iter_mut().find(|(c, _)| *c == &*week.caretaker) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((&week.caretaker, 1)),
+        }
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(caretaker, n)| {
+            format(
+                formatting.locale,
+                "subtotal",
+                &[
+                    ("caretaker", &formatting.name(caretaker)),
+                    ("weeks", &n.to_string()),
+                ],
+            )
+        })
+        .collect();
+    format!("  {}", counts.join(", "))
+}
+
+pub fn starts_line(formatting: &Formatting, startdate: NaiveDate, weeks: i64) -> String {
+    format(
+        formatting.locale,
+        "starts",
+        &[
+            ("date", &formatting.date(startdate)),
+            ("weeks", &weeks.to_string()),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_works() {
+        let args = [("caretaker", "Edgar")];
+        assert_eq!(
+            format(Locale::En, "handoff", &args),
+            "Edgar is taking care this week"
+        );
+        assert_eq!(
+            format(Locale::De, "reminder", &args),
+            "Erinnerung: Edgar kümmert sich nächste Woche"
+        );
+        assert_eq!(tr(Locale::De, "week"), "Woche");
+    }
+
+    #[test]
+    fn date_works() {
+        let date = NaiveDate::from_ymd_opt(2025, 8, 4).unwrap();
+        assert_eq!(Formatting::default().date(date), "2025-08-04");
+        let formatting = Formatting {
+            locale: Locale::De,
+            date_format: Some("%a %d.%m.".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(formatting.date(date), "Mo 04.08.");
+        let formatting = Formatting {
+            locale: Locale::En,
+            date_format: Some("%a %e %b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(formatting.date(date), "Mon  4 Aug");
+    }
+
+    #[test]
+    fn month_lines_work() {
+        let week = |caretaker: &str, suspended: Option<&str>| CareWeek {
+            caretaker: caretaker.into(),
+            suspended: suspended.map(Into::into),
+            ..Default::default()
+        };
+        let weeks = [
+            week("Jimmy", None),
+            week("Edgar", None),
+            week("Jimmy", None),
+            week("", Some("Summer")),
+        ];
+        let formatting = Formatting {
+            locale: Locale::De,
+            ..Default::default()
+        };
+        assert_eq!(
+            subtotal_line(&formatting, &[], &weeks),
+            "  Jimmy: 2 Woche(n), Edgar: 1 Woche(n)"
+        );
+        assert_eq!(
+            subtotal_line(&formatting, &["Edgar", "Robert"], &weeks),
+            "  Edgar: 1 Woche(n), Robert: 0 Woche(n), Jimmy: 2 Woche(n)"
+        );
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        assert_eq!(month_line(&formatting, date), "März 2025");
+    }
+
+    #[test]
+    fn emoji_comes_first() {
+        let formatting = Formatting {
+            emoji: HashMap::from([("Jimmy".to_string(), "🦊".to_string())]),
+            ..Default::default()
+        };
+        let week = CareWeek {
+            week: 27,
+            caretaker: "Jimmy".into(),
+            start_date: NaiveDate::from_ymd_opt(2024, 7, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2024, 7, 7).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            week_line(&formatting, &week),
+            "week #27 2024-07-01 - 2024-07-07: 🦊 Jimmy"
+        );
+        assert_eq!(formatting.name("Edgar"), "Edgar");
+        assert_eq!(
+            subtotal_line(&formatting, &["Edgar"], &[week]),
+            "  Edgar: 0 week(s), 🦊 Jimmy: 1 week(s)"
+        );
+    }
+}
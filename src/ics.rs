@@ -0,0 +1,640 @@
+use crate::logging::warning;
+use crate::{crypto, tz, CareWeek, Config};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::Deserialize;
+use std::borrow::Borrow;
+use std::io::{self, Write};
+use whocares_core::rotation::shift_end;
+
+const DATE_FORMAT: &str = "%Y%m%d";
+const DATETIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+#[derive(Deserialize, Default)]
+pub struct IcsConfig {
+    pub timezone: Option<String>,
+    alarm_hours: Option<u32>,
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+pub fn slug(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn week_uid(week: &CareWeek) -> String {
+    let iso = week.start_date.iso_week();
+    format!("whocares-{}-W{:02}", iso.year(), iso.week())
+}
+
+// The slug keeps the UID readable, the hash tells "Anna B" and "Anna-B"
+// apart.
+fn span_uid(week: &CareWeek, caretaker: &str) -> String {
+    let hash = crypto::sha256(caretaker.as_bytes());
+    format!(
+        "{}-{}-{}",
+        week_uid(week),
+        slug(caretaker),
+        crypto::hex(&hash[..4])
+    )
+}
+
+// An event of a week, with the lines saying when it is.
+struct Part<'a> {
+    uid: String,
+    caretaker: &'a str,
+    summary: String,
+    times: Vec<String>,
+}
+
+fn all_day(start: NaiveDate, end: NaiveDate) -> Vec<String> {
+    let end = end.succ_opt().unwrap_or(end);
+    vec![
+        format!("DTSTART;VALUE=DATE:{}", start.format(DATE_FORMAT)),
+        format!("DTEND;VALUE=DATE:{}", end.format(DATE_FORMAT)),
+    ]
+}
+
+// A shift repeats on every covered day of the week, from its start to the
+// start of the next shift.
+fn shift_times(week: &CareWeek, conf: &Config, i: usize) -> Vec<String> {
+    let start = conf.shifts[i].start;
+    let next = shift_end(&conf.shifts, i);
+    let days: Vec<NaiveDate> = week
+        .start_date
+        .iter_days()
+        .take(7)
+        .filter(|d| week.coverage.contains(*d))
+        .collect();
+    let Some(first) = days.first() else {
+        return Vec::new();
+    };
+    let end = match next > start {
+        true => first.and_time(next),
+        false => first.succ_opt().unwrap_or(*first).and_time(next),
+    };
+    let tzid = match conf.ics.timezone.as_ref().or(conf.timezone.as_ref()) {
+        Some(timezone) => format!(";TZID={timezone}"),
+        None => String::new(),
+    };
+    let mut rrule = format!("RRULE:FREQ=DAILY;COUNT={}", days.len());
+    if days.len() < 7 {
+        let byday: Vec<String> = days
+            .iter()
+            .map(|d| d.format("%a").to_string()[..2].to_uppercase())
+            .collect();
+        rrule.push_str(&format!(";BYDAY={}", byday.join(",")));
+    }
+    vec![
+        format!(
+            "DTSTART{tzid}:{}",
+            first.and_time(start).format(DATETIME_FORMAT)
+        ),
+        format!("DTEND{tzid}:{}", end.format(DATETIME_FORMAT)),
+        rrule,
+    ]
+}
+
+// The all-day spans of a week with the UIDs of their events, just one of
+// nobody if it's suspended.
+pub fn spans(week: &CareWeek) -> Vec<(String, &str, NaiveDate, NaiveDate)> {
+    if week.suspended.is_some() {
+        let (start, end) = week.period();
+        return vec![(format!("{}-suspended", week_uid(week)), "", start, end)];
+    }
+    let mut spans: Vec<(String, &str, NaiveDate, NaiveDate)> = Vec::new();
+    for (caretaker, start, end) in week.spans() {
+        // Days off can cut a caretaker's days in several events.
+        let mut uid = span_uid(week, caretaker);
+        if spans.iter().any(|s| s.1 == caretaker) {
+            uid = format!("{uid}-{}", start.format(DATE_FORMAT));
+        }
+        spans.push((uid, caretaker, start, end));
+    }
+    spans
+}
+
+// One part for each shift, or for each caretaker of a split week, and one
+// for the outgoing caretaker's overlap. Just one of nobody if suspended.
+fn parts<'a>(week: &'a CareWeek, conf: &Config) -> Vec<Part<'a>> {
+    let mut parts: Vec<Part> = Vec::new();
+    if let Some(blackout) = &week.suspended {
+        let (uid, _, start, end) = spans(week).remove(0);
+        parts.push(Part {
+            uid,
+            caretaker: "",
+            summary: format!("Suspended: {blackout}"),
+            times: all_day(start, end),
+        });
+        return parts;
+    }
+    if !week.shifts.is_empty() {
+        for (i, on_shift) in week.shifts.iter().enumerate() {
+            // Weeks made before a config change can have other shifts.
+            if i >= conf.shifts.len() {
+                continue;
+            }
+            let times = shift_times(week, conf, i);
+            if times.is_empty() {
+                continue;
+            }
+            parts.push(Part {
+                uid: format!(
+                    "{}-{}",
+                    span_uid(week, &on_shift.caretaker),
+                    slug(&on_shift.shift)
+                ),
+                caretaker: &on_shift.caretaker,
+                summary: format!("{} ({})", on_shift.caretaker, on_shift.shift),
+                times,
+            });
+        }
+        return parts;
+    }
+    for (uid, caretaker, start, end) in spans(week) {
+        parts.push(Part {
+            uid,
+            caretaker,
+            summary: caretaker.to_string(),
+            times: all_day(start, end),
+        });
+    }
+    if let Some(overlap) = &week.overlap {
+        let runs = week.coverage.runs(week.start_date, overlap.until);
+        if let (Some((start, _)), Some((_, end))) = (runs.first(), runs.last()) {
+            parts.push(Part {
+                uid: format!("{}-overlap", span_uid(week, &overlap.caretaker)),
+                caretaker: &overlap.caretaker,
+                summary: format!("{} (handover to {})", overlap.caretaker, week.caretaker),
+                times: all_day(*start, *end),
+            });
+        }
+    }
+    parts
+}
+
+fn event(part: Part, week: &CareWeek, stamp: DateTime<Utc>, conf: &Config) -> Vec<String> {
+    let nobody = part.caretaker.is_empty();
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", part.uid),
+        format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+    ];
+    lines.extend(part.times);
+    lines.extend([
+        format!("SUMMARY:{}", escape(&part.summary)),
+        format!(
+            "DESCRIPTION:{}",
+            escape(&format!("week #{}{}", week.week, week.holiday_note()))
+        ),
+    ]);
+    if !nobody {
+        lines.push(format!("CATEGORIES:{}", escape(part.caretaker)));
+    }
+    lines.push("TRANSP:TRANSPARENT".to_string());
+    if let Some(hours) = conf.ics.alarm_hours.filter(|_| !nobody) {
+        lines.extend([
+            "BEGIN:VALARM".to_string(),
+            "ACTION:DISPLAY".to_string(),
+            format!("TRIGGER:-PT{hours}H"),
+            format!(
+                "DESCRIPTION:{}",
+                escape(&format!("{} is taking care soon", part.caretaker))
+            ),
+            "END:VALARM".to_string(),
+        ]);
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+// The events of a week, only those of one caretaker if given. Everyone
+// sees that a week is suspended.
+fn events(week: &CareWeek, stamp: DateTime<Utc>, conf: &Config, only: Option<&str>) -> String {
+    let mut lines = Vec::new();
+    for part in parts(week, conf) {
+        let nobody = part.caretaker.is_empty();
+        if !nobody && only.is_some_and(|only| only != part.caretaker) {
+            continue;
+        }
+        lines.extend(event(part, week, stamp, conf));
+    }
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+// A calendar per event of the week with its UID, as CalDAV wants one UID
+// per resource.
+#[cfg(feature = "sync")]
+pub fn resources(conf: &Config, week: &CareWeek, stamp: DateTime<Utc>) -> Vec<(String, String)> {
+    parts(week, conf)
+        .into_iter()
+        .map(|part| {
+            let uid = part.uid.clone();
+            let mut lines = header(conf);
+            lines.extend(event(part, week, stamp, conf));
+            lines.push("END:VCALENDAR".to_string());
+            (uid, lines.iter().map(|line| fold(line)).collect())
+        })
+        .collect()
+}
+
+// Writes one event at a time, so long ranges don't have to be collected.
+pub fn write_calendar<W: Write, I>(
+    out: &mut W,
+    conf: &Config,
+    weeks: I,
+    stamp: DateTime<Utc>,
+) -> io::Result<()>
+where
+    I: IntoIterator,
+    I::Item: Borrow<CareWeek>,
+{
+    write_events(out, conf, weeks, stamp, None)
+}
+
+// VCALENDAR up to the events.
+fn header(conf: &Config) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//whocares//whocares//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    if let Some(name) = &conf.name {
+        lines.push(format!("NAME:{}", escape(name)));
+        lines.push(format!("X-WR-CALNAME:{}", escape(name)));
+    }
+    if let Some(timezone) = conf.ics.timezone.as_ref().or(conf.timezone.as_ref()) {
+        lines.push(format!("X-WR-TIMEZONE:{timezone}"));
+        match tz::load(timezone) {
+            Ok(rules) => lines.extend(rules.vtimezone(timezone)),
+            Err(e) => warning!("no VTIMEZONE for {timezone}: {e}"),
+        }
+    }
+    lines
+}
+
+fn write_events<W: Write, I>(
+    out: &mut W,
+    conf: &Config,
+    weeks: I,
+    stamp: DateTime<Utc>,
+    only: Option<&str>,
+) -> io::Result<()>
+where
+    I: IntoIterator,
+    I::Item: Borrow<CareWeek>,
+{
+    let lines = header(conf);
+    for line in &lines {
+        out.write_all(fold(line).as_bytes())?;
+    }
+    for week in weeks {
+        out.write_all(events(week.borrow(), stamp, conf, only).as_bytes())?;
+    }
+    out.write_all(fold("END:VCALENDAR").as_bytes())
+}
+
+#[cfg(feature = "server")]
+pub fn calendar(conf: &Config, weeks: &[CareWeek], stamp: DateTime<Utc>) -> String {
+    let mut ics = Vec::new();
+    write_calendar(&mut ics, conf, weeks, stamp).expect("writing to a Vec can't fail");
+    String::from_utf8(ics).expect("the calendar is UTF-8")
+}
+
+// The events of one caretaker.
+#[cfg(feature = "server")]
+pub fn person_calendar(
+    conf: &Config,
+    weeks: &[CareWeek],
+    caretaker: &str,
+    stamp: DateTime<Utc>,
+) -> String {
+    let mut ics = Vec::new();
+    write_events(&mut ics, conf, weeks, stamp, Some(caretaker))
+        .expect("writing to a Vec can't fail");
+    String::from_utf8(ics).expect("the calendar is UTF-8")
+}
+
+pub struct ImportedEvent {
+    pub start: NaiveDate,
+    // exclusive, like DTEND
+    pub end: NaiveDate,
+    pub summary: String,
+    pub transparent: bool,
+    // Start and end of events that aren't all-day.
+    pub times: Option<(NaiveDateTime, NaiveDateTime)>,
+}
+
+impl ImportedEvent {
+    // The days the event takes up completely, as [start, end). None for
+    // events of a few hours, which leave the rest of the day.
+    pub fn full_days(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let Some((start, end)) = self.times else {
+            return Some((self.start, self.end));
+        };
+        let first = match start.time() {
+            NaiveTime::MIN => start.date(),
+            _ => start.date().succ_opt()?,
+        };
+        (first < end.date()).then_some((first, end.date()))
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, DATE_FORMAT).ok()
+}
+
+// Midnight for dates.
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    match value.get(..15) {
+        Some(datetime) => NaiveDateTime::parse_from_str(datetime, DATETIME_FORMAT).ok(),
+        None => Some(parse_date(value)?.and_time(NaiveTime::MIN)),
+    }
+}
+
+pub fn parse_events(ics: &str) -> Vec<ImportedEvent> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let (mut start, mut end, mut summary, mut transparent) = (None, None, String::new(), false);
+    let (mut start_time, mut end_time) = (None, None);
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.split(';').next().unwrap_or_default();
+        match name.to_ascii_uppercase().as_str() {
+            "BEGIN" if value == "VEVENT" => {
+                (start, end, summary, transparent) = (None, None, String::new(), false);
+                (start_time, end_time) = (None, None);
+            }
+            "DTSTART" => {
+                start = parse_date(value);
+                start_time = value.contains('T').then(|| parse_datetime(value)).flatten();
+            }
+            "DTEND" => {
+                end = parse_date(value);
+                end_time = parse_datetime(value);
+            }
+            "SUMMARY" => summary = unescape(value).trim().to_string(),
+            "TRANSP" => transparent = value.eq_ignore_ascii_case("TRANSPARENT"),
+            "END" if value == "VEVENT" => {
+                if let Some(start) = start {
+                    let end = end
+                        .filter(|e| *e > start)
+                        .unwrap_or(start + chrono::Days::new(1));
+                    // Without an end a timed event is just a moment.
+                    let times = start_time.map(|from| (from, end_time.unwrap_or(from).max(from)));
+                    events.push(ImportedEvent {
+                        start,
+                        end,
+                        summary: summary.clone(),
+                        transparent,
+                        times,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    fn week(caretaker: &str) -> CareWeek {
+        CareWeek {
+            week: 1,
+            caretaker: caretaker.into(),
+            start_date: NaiveDate::from_str("2024-12-30").unwrap(),
+            end_date: NaiveDate::from_str("2025-01-05").unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn uid_uses_iso_week() {
+        let uid = |caretaker| spans(&week(caretaker)).remove(0).0;
+        assert_eq!(uid("Jimmy Page"), "whocares-2025-W01-jimmy-page-87449eaa");
+        assert_ne!(uid("Anna B"), uid("Anna-B"));
+    }
+
+    #[test]
+    fn event_works() {
+        let stamp = DateTime::from_timestamp(0, 0).unwrap();
+        let ics = events(&week("Smith, Jimmy"), stamp, &Config::default(), None);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20241230\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20250106\r\n"));
+        assert!(ics.contains("SUMMARY:Smith\\, Jimmy\r\n"));
+        assert!(ics.contains("DTSTAMP:19700101T000000Z\r\n"));
+        assert!(ics.contains("CATEGORIES:Smith\\, Jimmy\r\n"));
+        assert!(!ics.contains("BEGIN:VALARM"));
+    }
+
+    #[test]
+    fn split_and_overlapping_weeks() {
+        let stamp = DateTime::from_timestamp(0, 0).unwrap();
+        let mut week = week("Edgar");
+        week.split = Some(whocares_core::rotation::Split {
+            from: NaiveDate::from_str("2025-01-02").unwrap(),
+            caretaker: "Jimmy".into(),
+        });
+        let ics = events(&week, stamp, &Config::default(), None);
+        assert!(ics.contains("DTEND;VALUE=DATE:20250102\r\nSUMMARY:Edgar\r\n"));
+        assert!(ics.contains(
+            "DTSTART;VALUE=DATE:20250102\r\nDTEND;VALUE=DATE:20250106\r\nSUMMARY:Jimmy\r\n"
+        ));
+        assert!(ics.contains("UID:whocares-2025-W01-jimmy-79af7871\r\n"));
+        // CalDAV gets a resource for each of them.
+        #[cfg(feature = "sync")]
+        {
+            let resources = resources(&Config::default(), &week, stamp);
+            let uids: Vec<&str> = resources.iter().map(|(uid, _)| uid.as_str()).collect();
+            assert_eq!(
+                uids,
+                [
+                    "whocares-2025-W01-edgar-877f4b9f",
+                    "whocares-2025-W01-jimmy-79af7871"
+                ]
+            );
+            for (uid, body) in &resources {
+                assert_eq!(body.matches("BEGIN:VEVENT").count(), 1);
+                assert!(body.contains(&format!("UID:{uid}\r\n")));
+                assert!(body.starts_with("BEGIN:VCALENDAR\r\n"));
+                assert!(body.ends_with("END:VCALENDAR\r\n"));
+            }
+        }
+
+        week.split = None;
+        week.overlap = Some(whocares_core::rotation::Overlap {
+            caretaker: "Jimmy".into(),
+            until: NaiveDate::from_str("2024-12-30").unwrap(),
+        });
+        let mut ics = Vec::new();
+        write_events(&mut ics, &Config::default(), &[week], stamp, Some("Jimmy")).unwrap();
+        let ics = String::from_utf8(ics).unwrap();
+        assert!(ics.contains("UID:whocares-2025-W01-jimmy-79af7871-overlap\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20241231\r\nSUMMARY:Jimmy (handover to Edgar)\r\n"));
+        assert!(!ics.contains("SUMMARY:Edgar"));
+    }
+
+    #[test]
+    fn shifts_repeat_daily() {
+        let stamp = DateTime::from_timestamp(0, 0).unwrap();
+        let conf = Config {
+            shifts: serde_json::from_str(
+                r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00"}]"#,
+            )
+            .unwrap(),
+            timezone: Some("Europe/Berlin".to_string()),
+            days: serde_json::from_str(r#"["sat", "sun"]"#).unwrap(),
+            ..Default::default()
+        };
+        let on_shift = |shift: &str, caretaker: &str| whocares_core::rotation::OnShift {
+            shift: shift.into(),
+            caretaker: caretaker.into(),
+        };
+        let mut week = week("Edgar");
+        week.coverage = conf.days;
+        week.shifts = vec![on_shift("day", "Edgar"), on_shift("night", "Jimmy")];
+        let ics = events(&week, stamp, &conf, Some("Jimmy"));
+        assert!(ics.contains(
+            "DTSTART;TZID=Europe/Berlin:20250104T200000\r\nDTEND;TZID=Europe/Berlin:20250105T080000\r\nRRULE:FREQ=DAILY;COUNT=2;BYDAY=SA,SU\r\n"
+        ));
+        assert!(ics.contains("SUMMARY:Jimmy (night)\r\n"));
+        assert!(!ics.contains("Edgar (day)"));
+    }
+
+    #[test]
+    fn event_with_alarm() {
+        let stamp = DateTime::from_timestamp(0, 0).unwrap();
+        let conf = Config {
+            ics: IcsConfig {
+                alarm_hours: Some(24),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let ics = events(&week("Edgar"), stamp, &conf, None);
+        assert!(ics.contains("BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT24H\r\n"));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn calendar_is_named() {
+        let conf = Config {
+            name: Some("Trash".to_string()),
+            ..Default::default()
+        };
+        let ics = calendar(&conf, &[], DateTime::from_timestamp(0, 0).unwrap());
+        assert!(ics.contains("X-WR-CALNAME:Trash\r\n"));
+    }
+
+    #[test]
+    fn parse_events_round_trips() {
+        let stamp = DateTime::from_timestamp(0, 0).unwrap();
+        let mut ics = Vec::new();
+        write_calendar(&mut ics, &Config::default(), &[week("Smith, Jimmy")], stamp).unwrap();
+        let events = parse_events(&String::from_utf8(ics).unwrap());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Smith, Jimmy");
+        assert_eq!(events[0].start, NaiveDate::from_str("2024-12-30").unwrap());
+        assert_eq!(events[0].end, NaiveDate::from_str("2025-01-06").unwrap());
+    }
+
+    #[test]
+    fn parse_events_with_folded_datetimes() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;TZID=Europe/Berlin:20240701T080000\r\nSUMMARY:Ed\r\n gar\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let events = parse_events(ics);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Edgar");
+        assert_eq!(events[0].start, NaiveDate::from_str("2024-07-01").unwrap());
+        assert_eq!(events[0].end, NaiveDate::from_str("2024-07-02").unwrap());
+        assert_eq!(events[0].full_days(), None);
+    }
+
+    #[test]
+    fn full_days_of_timed_events() {
+        let event = |start: &str, end: &str| {
+            let ics = format!("BEGIN:VEVENT\r\nDTSTART:{start}\r\nDTEND:{end}\r\nEND:VEVENT\r\n");
+            parse_events(&ics).remove(0).full_days()
+        };
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        assert_eq!(event("20240702T090000Z", "20240702T110000Z"), None);
+        assert_eq!(
+            event("20240705T180000", "20240708T080000"),
+            Some((date("2024-07-06"), date("2024-07-08")))
+        );
+        assert_eq!(
+            event("20240701T000000", "20240703T000000"),
+            Some((date("2024-07-01"), date("2024-07-03")))
+        );
+        assert_eq!(
+            event("20240701", "20240702"),
+            Some((date("2024-07-01"), date("2024-07-02")))
+        );
+    }
+
+    #[test]
+    fn fold_long_lines() {
+        let folded = fold(&"x".repeat(100));
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(lines[0].len(), 75);
+        assert_eq!(lines[1], format!(" {}", "x".repeat(25)));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::contacts::Contact;
+use crate::ics::ImportedEvent;
+use crate::{regular_caretaker, start_of_week, Config};
+use std::collections::BTreeMap;
+use whocares_core::Week;
+
+// Converts a calendar of who actually was on duty into reschedule entries
+// for every week that deviates from the regular rotation.
+pub fn reschedules_from_events(conf: &Config, events: &[ImportedEvent]) -> BTreeMap<Week, String> {
+    let mut reschedules = BTreeMap::new();
+    // Events of a few hours don't say who had the week.
+    let days = events
+        .iter()
+        .filter(|e| !e.summary.is_empty())
+        .filter_map(|e| Some((e, e.full_days()?)));
+    for (event, (start, end)) in days {
+        for monday in start_of_week(start)
+            .iter_weeks()
+            .take_while(|monday| *monday < end)
+        {
+            if regular_caretaker(conf, monday) != event.summary {
+                reschedules.insert(Week::containing(monday), event.summary.clone());
+            }
+        }
+    }
+    reschedules
+}
+
+// RFC 4180, as Google Sheets exports it: quoted fields may contain commas,
+// newlines and doubled quotes.
+pub fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut quoted, mut chars) = (false, csv.chars().peekable());
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+pub struct RosterEntry {
+    pub name: String,
+    pub contact: Contact,
+}
+
+// Columns are found by a "name", "email" and "phone" header, or are taken
+// in that order if the first row isn't one.
+pub fn roster_from_csv(csv: &str) -> Vec<RosterEntry> {
+    let mut rows = parse_csv(csv).into_iter().peekable();
+    let header: Vec<String> = rows
+        .peek()
+        .map(|r| r.iter().map(|f| f.trim().to_lowercase()).collect())
+        .unwrap_or_default();
+    let find = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let columns = match find(&["name", "caretaker"]) {
+        Some(name) => {
+            rows.next();
+            (
+                name,
+                find(&["email", "e-mail", "mail"]),
+                find(&["phone", "mobile", "phone number"]),
+            )
+        }
+        None => (0, Some(1), Some(2)),
+    };
+
+    let field = |row: &[String], i: Option<usize>| {
+        i.and_then(|i| row.get(i))
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty())
+    };
+    rows.filter_map(|row| {
+        Some(RosterEntry {
+            name: field(&row, Some(columns.0))?,
+            contact: Contact {
+                email: field(&row, columns.1),
+                phone: field(&row, columns.2),
+                emoji: None,
+            },
+        })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    fn event(start: &str, end: &str, summary: &str) -> ImportedEvent {
+        ImportedEvent {
+            start: NaiveDate::from_str(start).unwrap(),
+            end: NaiveDate::from_str(end).unwrap(),
+            summary: summary.to_string(),
+            transparent: false,
+            times: None,
+        }
+    }
+
+    #[test]
+    fn reschedules_from_events_works() {
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: NaiveDate::from_str("2024-01-01").unwrap(),
+            ..Default::default()
+        };
+        let events = [
+            event("2024-01-01", "2024-01-08", "A"),
+            event("2024-01-08", "2024-01-22", "A"),
+            event("2024-01-24", "2024-01-25", "C"),
+            ImportedEvent {
+                times: Some((
+                    NaiveDate::from_str("2024-01-30")
+                        .unwrap()
+                        .and_hms_opt(9, 0, 0)
+                        .unwrap(),
+                    NaiveDate::from_str("2024-01-30")
+                        .unwrap()
+                        .and_hms_opt(10, 0, 0)
+                        .unwrap(),
+                )),
+                ..event("2024-01-30", "2024-01-31", "D")
+            },
+        ];
+
+        let reschedules = reschedules_from_events(&conf, &events);
+        assert_eq!(
+            reschedules,
+            BTreeMap::from([
+                (Week::new(2024, 2).unwrap(), "A".to_string()),
+                (Week::new(2024, 4).unwrap(), "C".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn roster_from_csv_works() {
+        let csv = "Phone,Name,Email\r\n\"+49 151 1\",\"Page, Jimmy\",jimmy@example.com\r\n,Edgar,\r\n,,\r\n";
+        let roster = roster_from_csv(csv);
+        assert_eq!(roster.len(), 2);
+        assert_eq!(roster[0].name, "Page, Jimmy");
+        assert_eq!(roster[0].contact.phone.as_deref(), Some("+49 151 1"));
+        assert_eq!(
+            roster[0].contact.email.as_deref(),
+            Some("jimmy@example.com")
+        );
+        assert_eq!(roster[1].name, "Edgar");
+        assert_eq!(roster[1].contact, Contact::default());
+
+        let roster = roster_from_csv("Robert\n\"John \"\"Bonzo\"\"\",bonzo@example.com");
+        assert_eq!(roster[1].name, "John \"Bonzo\"");
+        assert_eq!(
+            roster[1].contact.email.as_deref(),
+            Some("bonzo@example.com")
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::crypto::base64;
+use serde::Deserialize;
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const PASSWORD_ENV: &str = "WHOCARES_IRC_PASSWORD";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+pub struct IrcConfig {
+    pub server: String,
+    #[serde(default)]
+    port: Option<u16>,
+    // TLS goes through openssl s_client, like HTTP goes through curl.
+    #[serde(default)]
+    tls: bool,
+    pub channel: String,
+    #[serde(default = "default_nick")]
+    nick: String,
+    // SASL PLAIN is used when a username is set.
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+fn default_nick() -> String {
+    "whocares".to_string()
+}
+
+impl IrcConfig {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(if self.tls { 6697 } else { 6667 })
+    }
+
+    fn sasl(&self) -> Option<(&str, String)> {
+        let password = env::var(PASSWORD_ENV)
+            .ok()
+            .or_else(|| self.password.clone())?;
+        Some((self.username.as_deref()?, password))
+    }
+
+    pub fn send(&self, text: &str) -> io::Result<()> {
+        if !self.tls {
+            let stream = TcpStream::connect((self.server.as_str(), self.port()))?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            return self.announce(BufReader::new(stream.try_clone()?), stream, text);
+        }
+        let mut child = Command::new("openssl")
+            .args(["s_client", "-quiet", "-verify_return_error"])
+            .arg("-connect")
+            .arg(format!("{}:{}", self.server, self.port()))
+            .arg("-servername")
+            .arg(&self.server)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("openssl s_client without pipes"));
+        };
+        let result = self.announce(BufReader::new(stdout), stdin, text);
+        let _ = child.kill();
+        child.wait()?;
+        result
+    }
+
+    // Registers, joins the channel, sends the text and quits, answering
+    // pings and the SASL exchange along the way.
+    pub fn announce(
+        &self,
+        reader: impl BufRead,
+        mut writer: impl Write,
+        text: &str,
+    ) -> io::Result<()> {
+        let sasl = self.sasl();
+        let mut send = |line: &str| writer.write_all(format!("{line}\r\n").as_bytes());
+        if sasl.is_some() {
+            send("CAP REQ :sasl")?;
+        }
+        send(&format!("NICK {}", self.nick))?;
+        send(&format!("USER {} 0 * :whocares", self.nick))?;
+
+        for line in reader.lines() {
+            let line = line?;
+            if let Some(token) = line.strip_prefix("PING ") {
+                send(&format!("PONG {token}"))?;
+                continue;
+            }
+            let mut words = line.split(' ');
+            let first = words.next().unwrap_or_default();
+            let command = if first.starts_with(':') {
+                words.next().unwrap_or_default()
+            } else {
+                first
+            };
+            match command {
+                "CAP" if line.contains(" ACK ") => send("AUTHENTICATE PLAIN")?,
+                "CAP" if line.contains(" NAK ") => {
+                    return Err(io::Error::other("irc server does not support SASL"));
+                }
+                "AUTHENTICATE" => {
+                    if let Some((user, password)) = &sasl {
+                        let credentials = format!("{user}\0{user}\0{password}");
+                        send(&format!("AUTHENTICATE {}", base64(credentials.as_bytes())))?;
+                    }
+                }
+                "903" => send("CAP END")?,
+                "904" | "905" => return Err(io::Error::other("irc SASL authentication failed")),
+                "433" => {
+                    return Err(io::Error::other(format!(
+                        "irc nick {} is already in use",
+                        self.nick
+                    )))
+                }
+                "001" => {
+                    send(&format!("JOIN {}", self.channel))?;
+                    for text_line in text.lines() {
+                        send(&format!("PRIVMSG {} :{text_line}", self.channel))?;
+                    }
+                    send("QUIT :bye")?;
+                }
+                "ERROR" => return Ok(()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announce_with_sasl() {
+        let conf: IrcConfig = serde_json::from_str(
+            r##"{"server": "irc.example.com", "channel": "#trash", "username": "bot", "password": "secret"}"##,
+        )
+        .unwrap();
+        let server = ":irc CAP * ACK :sasl\r\nAUTHENTICATE +\r\n:irc 903 whocares :SASL ok\r\nPING :irc\r\n:irc 001 whocares :Welcome\r\nERROR :Closing link\r\n";
+        let mut sent = Vec::new();
+        conf.announce(
+            server.as_bytes(),
+            &mut sent,
+            "Edgar is taking care this week",
+        )
+        .unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        let lines: Vec<&str> = sent.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "CAP REQ :sasl",
+                "NICK whocares",
+                "USER whocares 0 * :whocares",
+                "AUTHENTICATE PLAIN",
+                "AUTHENTICATE Ym90AGJvdABzZWNyZXQ=",
+                "CAP END",
+                "PONG :irc",
+                "JOIN #trash",
+                "PRIVMSG #trash :Edgar is taking care this week",
+                "QUIT :bye",
+            ]
+        );
+    }
+}
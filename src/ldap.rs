@@ -0,0 +1,147 @@
+use crate::contacts::Contact;
+use crate::crypto::base64_decode;
+use crate::import::RosterEntry;
+use serde::Deserialize;
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+const PASSWORD_ENV: &str = "WHOCARES_LDAP_PASSWORD";
+
+// Searched with ldapsearch from the OpenLDAP client tools.
+#[derive(Deserialize)]
+pub struct LdapConfig {
+    pub url: String,
+    base_dn: String,
+    // e.g. "(memberOf=cn=trash,ou=groups,dc=example,dc=com)"
+    filter: String,
+    #[serde(default)]
+    bind_dn: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default = "default_name_attribute")]
+    name_attribute: String,
+    #[serde(default = "default_email_attribute")]
+    email_attribute: String,
+}
+
+fn default_name_attribute() -> String {
+    "cn".to_string()
+}
+
+fn default_email_attribute() -> String {
+    "mail".to_string()
+}
+
+// A value is "attr: text" or "attr:: base64", continued on lines starting
+// with a space.
+fn attributes(entry: &[String]) -> Vec<(String, String)> {
+    entry
+        .iter()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let value = match value.strip_prefix(':') {
+                Some(encoded) => String::from_utf8(base64_decode(encoded.trim())?).ok()?,
+                None => value.trim_start().to_string(),
+            };
+            Some((name.to_lowercase(), value))
+        })
+        .collect()
+}
+
+pub fn parse_ldif(ldif: &str, name_attribute: &str, email_attribute: &str) -> Vec<RosterEntry> {
+    let mut entries: Vec<Vec<String>> = vec![Vec::new()];
+    for line in ldif.lines() {
+        let current = entries.last_mut().unwrap();
+        match (line.strip_prefix(' '), current.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if line.trim().is_empty() => entries.push(Vec::new()),
+            _ if line.starts_with('#') => {}
+            _ => current.push(line.to_string()),
+        }
+    }
+
+    let mut roster: Vec<RosterEntry> = entries
+        .iter()
+        .map(|entry| attributes(entry))
+        .filter_map(|attributes| {
+            let get = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v.clone())
+            };
+            Some(RosterEntry {
+                name: get(name_attribute)?,
+                contact: Contact {
+                    email: get(email_attribute),
+                    phone: None,
+                    emoji: None,
+                },
+            })
+        })
+        .collect();
+    roster.sort_by(|a, b| a.name.cmp(&b.name));
+    roster
+}
+
+impl LdapConfig {
+    pub fn members(&self) -> io::Result<Vec<RosterEntry>> {
+        let mut cmd = Command::new("ldapsearch");
+        cmd.args(["-x", "-LLL", "-o", "ldif-wrap=no", "-H", &self.url]);
+        let password = env::var(PASSWORD_ENV)
+            .ok()
+            .or_else(|| self.password.clone());
+        if let Some(bind_dn) = &self.bind_dn {
+            cmd.arg("-D").arg(bind_dn);
+        }
+        // Read from stdin, so the password doesn't show up in ps.
+        if password.is_some() {
+            cmd.args(["-y", "/dev/stdin"]);
+        }
+        cmd.arg("-b")
+            .arg(&self.base_dn)
+            .arg(&self.filter)
+            .arg(&self.name_attribute)
+            .arg(&self.email_attribute)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(password.unwrap_or_default().as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "ldapsearch failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(parse_ldif(
+            &String::from_utf8_lossy(&output.stdout),
+            &self.name_attribute,
+            &self.email_attribute,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ldif_works() {
+        let ldif = "dn: uid=jimmy,ou=people,dc=example,dc=com\ncn: Jimmy\nmail: jimmy@exa\n mple.com\n\n# refldap://example.com\n\ndn: uid=juergen,ou=people,dc=example,dc=com\ncn:: SsO8cmdlbg==\n\ndn: uid=nameless,ou=people,dc=example,dc=com\n";
+        let roster = parse_ldif(ldif, "cn", "mail");
+        assert_eq!(roster.len(), 2);
+        assert_eq!(roster[0].name, "Jimmy");
+        assert_eq!(
+            roster[0].contact.email.as_deref(),
+            Some("jimmy@example.com")
+        );
+        assert_eq!(roster[1].name, "Jürgen");
+        assert_eq!(roster[1].contact.email, None);
+    }
+}
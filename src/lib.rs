@@ -1,9 +1,470 @@
+//! Serde helpers for the dates, weeks and durations in whocares configs,
+//! for reuse in other config structs. They are behind the `serde-helpers`
+//! feature, enabled by default, and available under `serde_helpers`:
+//!
+//! - `date`: `NaiveDate` as "2025-08-04", also reading "04.08.2025"
+//! - `option_date`: `Option<NaiveDate>` with null as None
+//! - `datetime`: RFC 3339 `DateTime<Tz>`, `datetime::naive` for `NaiveDateTime`
+//! - `epoch`: Unix timestamps, `epoch::date` for dates
+//! - `week`: the `Week` type for "2025-W14", `iso_week` for `chrono::IsoWeek`
+//! - `duration`: `chrono::Duration` from "2w", "36h" or "PT72H"
+//!
+//! Use them with `#[serde(with = "whocares::serde_helpers::date")]`.
+
+// The stable paths of the helpers, the modules below are kept for
+// compatibility.
+#[cfg(feature = "serde-helpers")]
+pub mod serde_helpers {
+    pub use crate::date_serializer as date;
+    pub use crate::datetime_serializer as datetime;
+    pub use crate::duration_serializer as duration;
+    pub use crate::epoch_serializer as epoch;
+    pub use crate::iso_week_serializer as iso_week;
+    pub use crate::option_date_serializer as option_date;
+    pub use crate::week::{self, Week};
+}
+
+#[cfg(feature = "serde-helpers")]
 pub mod date_serializer {
     use chrono::NaiveDate;
-    use serde::{de::Error, Deserialize, Deserializer};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use whocares_core::schedule;
+
+    const FORMAT: &str = "%Y-%m-%d";
+
+    // Accepted when reading, dates are always written as FORMAT. The same
+    // rules as the dates whocares-core reads.
+    pub const FORMATS: &[&str] = schedule::DATE_FORMATS;
+
+    pub fn parse_with(s: &str, formats: &[&str]) -> Result<NaiveDate, String> {
+        schedule::parse_date_with(s, formats)
+    }
+
+    pub fn parse(s: &str) -> Result<NaiveDate, String> {
+        schedule::parse_date(s)
+    }
+
+    pub fn serialize<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&date.format(FORMAT))
+    }
 
     pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
         let s: String = Deserialize::deserialize(deserializer)?;
-        NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(D::Error::custom)
+        parse(&s).map_err(D::Error::custom)
+    }
+}
+
+// For Option<NaiveDate> fields, null means None. Use together with
+// #[serde(default)] to also allow the field to be absent.
+#[cfg(feature = "serde-helpers")]
+pub mod option_date_serializer {
+    use chrono::NaiveDate;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        date: &Option<NaiveDate>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => crate::date_serializer::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDate>, D::Error> {
+        let s: Option<String> = Deserialize::deserialize(deserializer)?;
+        s.map(|s| crate::date_serializer::parse(&s).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+// RFC 3339 timestamps like "2025-08-04T08:00:00+02:00" for DateTime fields
+// in any time zone that can be converted from a fixed offset.
+#[cfg(feature = "serde-helpers")]
+pub mod datetime_serializer {
+    use chrono::{DateTime, FixedOffset, SecondsFormat, TimeZone};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+
+    pub fn serialize<S, Tz>(datetime: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        serializer.serialize_str(&datetime.to_rfc3339_opts(SecondsFormat::AutoSi, false))
+    }
+
+    pub fn deserialize<'de, D, Tz>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+    where
+        D: Deserializer<'de>,
+        Tz: TimeZone,
+        DateTime<Tz>: From<DateTime<FixedOffset>>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&s)
+            .map(DateTime::from)
+            .map_err(|e| D::Error::custom(format!("invalid timestamp \"{s}\": {e}")))
+    }
+
+    // Local times without offset, "2025-08-04T08:00[:00]".
+    pub mod naive {
+        use chrono::NaiveDateTime;
+        use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+        const FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+        pub fn serialize<S: Serializer>(
+            datetime: &NaiveDateTime,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(&datetime.format(FORMAT))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<NaiveDateTime, D::Error> {
+            let s: String = Deserialize::deserialize(deserializer)?;
+            NaiveDateTime::parse_from_str(&s, FORMAT)
+                .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M"))
+                .map_err(|e| D::Error::custom(format!("invalid date and time \"{s}\": {e}")))
+        }
+    }
+}
+
+// Unix timestamps in seconds, as numbers or numeric strings. Written back as
+// integers.
+#[cfg(feature = "serde-helpers")]
+pub mod epoch_serializer {
+    use chrono::{DateTime, Utc};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Int(i64),
+        Float(f64),
+        Text(String),
+    }
+
+    pub(crate) fn deserialize_secs<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let secs = match Timestamp::deserialize(deserializer)? {
+            Timestamp::Int(secs) => secs,
+            Timestamp::Float(secs) => secs.floor() as i64,
+            Timestamp::Text(s) => s
+                .trim()
+                .parse()
+                .map_err(|_| D::Error::custom(format!("invalid timestamp \"{s}\"")))?,
+        };
+        DateTime::from_timestamp(secs, 0)
+            .ok_or_else(|| D::Error::custom(format!("timestamp {secs} is out of range")))
+    }
+
+    pub fn serialize<S: Serializer>(
+        datetime: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(datetime.timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        deserialize_secs(deserializer)
+    }
+
+    // Dates as the timestamp of their midnight in UTC. Timestamps during the
+    // day give their UTC date.
+    pub mod date {
+        use chrono::NaiveDate;
+        use serde::{Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            date: &NaiveDate,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.serialize_i64(date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<NaiveDate, D::Error> {
+            super::deserialize_secs(deserializer).map(|datetime| datetime.date_naive())
+        }
+    }
+}
+
+#[cfg(feature = "serde-helpers")]
+pub use whocares_core::week;
+
+// For chrono::IsoWeek fields, in the format of week::Week.
+#[cfg(feature = "serde-helpers")]
+pub mod iso_week_serializer {
+    use crate::week::Week;
+    use chrono::IsoWeek;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(week: &IsoWeek, serializer: S) -> Result<S::Ok, S::Error> {
+        Week::from(*week).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<IsoWeek, D::Error> {
+        Week::deserialize(deserializer).map(Week::iso_week)
+    }
+}
+
+// Durations like "2w", "10d", "1d12h" or ISO 8601 "PT72H" and "P2W",
+// negative ones with a leading "-".
+#[cfg(feature = "serde-helpers")]
+pub mod duration_serializer {
+    use chrono::Duration;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const UNITS: [(char, i64); 5] = [
+        ('w', 7 * 24 * 3600),
+        ('d', 24 * 3600),
+        ('h', 3600),
+        ('m', 60),
+        ('s', 1),
+    ];
+
+    fn unit_secs(unit: char, in_time_part: bool) -> Option<i64> {
+        // In ISO 8601 "M" means months before the T and minutes after it.
+        if unit == 'm' && !in_time_part {
+            return None;
+        }
+        UNITS
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, secs)| *secs)
+    }
+
+    pub fn parse(s: &str) -> Result<Duration, String> {
+        let invalid = || format!("invalid duration \"{s}\", expected e.g. 2w, 10d, 36h or PT72H");
+        let lower = s.trim().to_ascii_lowercase();
+        let (negative, lower) = match lower.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, lower.as_str()),
+        };
+        let (iso, rest) = match lower.strip_prefix('p') {
+            Some(rest) => (true, rest),
+            None => (false, lower),
+        };
+
+        let mut secs = 0i64;
+        let mut number = String::new();
+        let mut in_time_part = !iso;
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => number.push(c),
+                't' if iso && number.is_empty() && !in_time_part => in_time_part = true,
+                _ => {
+                    let value: i64 = number.parse().map_err(|_| invalid())?;
+                    let unit = unit_secs(c, in_time_part).ok_or_else(invalid)?;
+                    secs = value
+                        .checked_mul(unit)
+                        .and_then(|v| secs.checked_add(v))
+                        .ok_or_else(invalid)?;
+                    number.clear();
+                }
+            }
+        }
+        // A T needs a time after it, "PT" and "P1DT" mean nothing.
+        if !number.is_empty() || rest.is_empty() || rest.ends_with('t') {
+            return Err(invalid());
+        }
+        Duration::try_seconds(if negative { -secs } else { secs }).ok_or_else(invalid)
+    }
+
+    // The largest unit that represents the duration exactly, e.g. "36h".
+    pub fn format(duration: &Duration) -> String {
+        let secs = duration.num_seconds();
+        let (unit, size) = UNITS
+            .iter()
+            .find(|(_, size)| secs % size == 0)
+            .copied()
+            .unwrap_or(('s', 1));
+        format!("{}{unit}", secs / size)
+    }
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        parse(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "serde-helpers"))]
+mod tests {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Dated {
+        #[serde(with = "crate::date_serializer")]
+        date: NaiveDate,
+    }
+
+    #[test]
+    fn date_serializer_round_trips() {
+        let dated = Dated {
+            date: NaiveDate::from_ymd_opt(2024, 5, 27).unwrap(),
+        };
+        let json = serde_json::to_string(&dated).unwrap();
+        assert_eq!(json, r#"{"date":"2024-05-27"}"#);
+        assert_eq!(serde_json::from_str::<Dated>(&json).unwrap(), dated);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct MaybeDated {
+        #[serde(default, with = "crate::option_date_serializer")]
+        date: Option<NaiveDate>,
+    }
+
+    #[test]
+    fn option_date_serializer_works() {
+        let parse = |json| serde_json::from_str::<MaybeDated>(json).unwrap().date;
+        assert_eq!(
+            parse(r#"{"date":"27.05.2024"}"#),
+            NaiveDate::from_ymd_opt(2024, 5, 27)
+        );
+        assert_eq!(parse(r#"{"date":null}"#), None);
+        assert_eq!(parse("{}"), None);
+        assert!(serde_json::from_str::<MaybeDated>(r#"{"date":"soon"}"#).is_err());
+
+        let none = serde_json::to_string(&MaybeDated { date: None }).unwrap();
+        assert_eq!(none, r#"{"date":null}"#);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Stamped {
+        #[serde(with = "crate::datetime_serializer")]
+        at: chrono::DateTime<chrono::Utc>,
+        #[serde(with = "crate::datetime_serializer::naive")]
+        local: chrono::NaiveDateTime,
+    }
+
+    #[test]
+    fn datetime_serializer_works() {
+        let stamped: Stamped = serde_json::from_str(
+            r#"{"at":"2025-08-04T08:00:00+02:00","local":"2025-08-04T08:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(stamped.at.to_rfc3339(), "2025-08-04T06:00:00+00:00");
+        assert_eq!(stamped.local.to_string(), "2025-08-04 08:00:00");
+        assert_eq!(
+            serde_json::to_string(&stamped).unwrap(),
+            r#"{"at":"2025-08-04T06:00:00+00:00","local":"2025-08-04T08:00:00"}"#
+        );
+        assert!(serde_json::from_str::<Stamped>(r#"{"at":"08:00","local":"x"}"#).is_err());
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Epoch {
+        #[serde(with = "crate::epoch_serializer")]
+        at: chrono::DateTime<chrono::Utc>,
+        #[serde(with = "crate::epoch_serializer::date")]
+        day: NaiveDate,
+    }
+
+    #[test]
+    fn epoch_serializer_works() {
+        let epoch: Epoch =
+            serde_json::from_str(r#"{"at":"1754294400","day":1754330000.5}"#).unwrap();
+        assert_eq!(epoch.at.to_rfc3339(), "2025-08-04T08:00:00+00:00");
+        assert_eq!(epoch.day, NaiveDate::from_ymd_opt(2025, 8, 4).unwrap());
+        assert_eq!(
+            serde_json::to_string(&epoch).unwrap(),
+            r#"{"at":1754294400,"day":1754265600}"#
+        );
+        assert!(serde_json::from_str::<Epoch>(r#"{"at":"noon","day":0}"#).is_err());
+    }
+
+    #[test]
+    fn week_works() {
+        use crate::week::Week;
+        use std::collections::BTreeMap;
+
+        let weeks: BTreeMap<Week, String> =
+            serde_json::from_str(r#"{"2025-W14":"Edgar","2024-W01":"dawe"}"#).unwrap();
+        let first = *weeks.keys().next().unwrap();
+        assert_eq!(first.monday(), NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!(
+            Week::containing(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()).to_string(),
+            "2025-W01"
+        );
+        assert_eq!(
+            serde_json::to_string(&weeks).unwrap(),
+            r#"{"2024-W01":"dawe","2025-W14":"Edgar"}"#
+        );
+        assert!(serde_json::from_str::<Week>(r#""2025-W54""#).is_err());
+        assert!(serde_json::from_str::<Week>(r#""2025-14""#).is_err());
+
+        #[derive(Serialize, Deserialize)]
+        struct Weekly {
+            #[serde(with = "crate::iso_week_serializer")]
+            week: chrono::IsoWeek,
+        }
+        let weekly: Weekly = serde_json::from_str(r#"{"week":"2020-W53"}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&weekly).unwrap(),
+            r#"{"week":"2020-W53"}"#
+        );
+    }
+
+    #[test]
+    fn duration_serializer_works() {
+        use crate::duration_serializer::{format, parse};
+        use chrono::Duration;
+
+        assert_eq!(parse("2w"), Ok(Duration::weeks(2)));
+        assert_eq!(parse("10d"), Ok(Duration::days(10)));
+        assert_eq!(parse("1d12h"), Ok(Duration::hours(36)));
+        assert_eq!(parse("PT72H"), Ok(Duration::hours(72)));
+        assert_eq!(parse("P2W"), Ok(Duration::weeks(2)));
+        assert_eq!(parse("P1DT30M"), Ok(Duration::minutes(24 * 60 + 30)));
+        assert!(parse("P1M").is_err());
+        for s in ["P", "PT", "P1DT"] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+        assert!(parse("2").is_err());
+        assert!(parse("").is_err());
+        assert!(parse("fortnight").is_err());
+
+        assert_eq!(format(&Duration::weeks(2)), "2w");
+        assert_eq!(format(&Duration::hours(36)), "36h");
+        assert_eq!(format(&Duration::zero()), "0w");
+        assert_eq!(format(&Duration::hours(-36)), "-36h");
+        assert_eq!(parse("-PT36H"), Ok(Duration::hours(-36)));
+        assert!(parse("--2w").is_err());
+        for duration in [
+            Duration::weeks(2),
+            Duration::seconds(90),
+            Duration::hours(-36),
+            Duration::days(-3),
+        ] {
+            assert_eq!(parse(&format(&duration)), Ok(duration));
+        }
+    }
+
+    #[test]
+    fn date_serializer_accepts_formats() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 27);
+        let parse = |s| crate::date_serializer::parse(s).ok();
+        assert_eq!(parse("2024-05-27"), date);
+        assert_eq!(parse("27.05.2024"), date);
+        assert_eq!(parse("2024-05-27T10:00:00+02:00"), date);
+
+        let error = serde_json::from_str::<Dated>(r#"{"date":"05/27/2024"}"#).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("invalid date \"05/27/2024\", expected one of %Y-%m-%d, %d.%m.%Y"));
     }
 }
@@ -0,0 +1,84 @@
+use std::env;
+use std::io;
+
+// Bounds on what a config may contain, so a hostile one can't exhaust
+// memory or CPU. Overridable with the WHOCARES_MAX_* variables.
+pub struct Limits {
+    pub file_bytes: u64,
+    pub caretakers: usize,
+    pub reschedules: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            file_bytes: 1024 * 1024,
+            caretakers: 1000,
+            reschedules: 10_000,
+        }
+    }
+}
+
+fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+impl Limits {
+    pub fn from_env() -> Limits {
+        let default = Limits::default();
+        Limits {
+            file_bytes: var("WHOCARES_MAX_CONFIG_BYTES", default.file_bytes),
+            caretakers: var("WHOCARES_MAX_CARETAKERS", default.caretakers),
+            reschedules: var("WHOCARES_MAX_RESCHEDULES", default.reschedules),
+        }
+    }
+
+    pub fn check(&self, path: &str, caretakers: usize, reschedules: usize) -> io::Result<()> {
+        let exceeded = |what: &str, count: usize, limit: usize, var: &str| {
+            io::Error::other(format!(
+                "{path} has {count} {what}, more than the limit of {limit} (set {var} to raise it)"
+            ))
+        };
+        if caretakers > self.caretakers {
+            return Err(exceeded(
+                "caretakers",
+                caretakers,
+                self.caretakers,
+                "WHOCARES_MAX_CARETAKERS",
+            ));
+        }
+        if reschedules > self.reschedules {
+            return Err(exceeded(
+                "reschedules",
+                reschedules,
+                self.reschedules,
+                "WHOCARES_MAX_RESCHEDULES",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_works() {
+        let limits = Limits {
+            caretakers: 2,
+            reschedules: 1,
+            ..Default::default()
+        };
+        assert!(limits.check("config.json", 2, 1).is_ok());
+        let e = limits.check("config.json", 3, 0).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "config.json has 3 caretakers, more than the limit of 2 (set WHOCARES_MAX_CARETAKERS to raise it)"
+        );
+        assert!(limits.check("config.json", 1, 2).is_err());
+    }
+}
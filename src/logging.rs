@@ -0,0 +1,123 @@
+use std::env;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    fn from_u8(level: u8) -> Option<Level> {
+        [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .into_iter()
+        .find(|l| *l as u8 == level)
+    }
+}
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+
+// Parses RUST_LOG style filters: "debug", or "whocares=debug" among
+// directives for other crates. Unknown values are ignored.
+fn parse_filter(filter: &str) -> Option<Level> {
+    filter.split(',').find_map(|directive| {
+        let level = match directive.split_once('=') {
+            Some(("whocares", level)) => level,
+            Some(_) => return None,
+            None => directive,
+        };
+        match level.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(Level::Error),
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    })
+}
+
+// RUST_LOG sets the base level, each -v raises it by one and -q drops to
+// errors only.
+pub fn init(verbosity: i8) {
+    let base = env::var("RUST_LOG")
+        .ok()
+        .and_then(|f| parse_filter(&f))
+        .unwrap_or(Level::Warn);
+    let level = (base as i8 + verbosity).clamp(Level::Error as i8, Level::Trace as i8);
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    Level::from_u8(MAX_LEVEL.load(Ordering::Relaxed)).is_some_and(|max| level <= max)
+}
+
+pub fn log(level: Level, module: &str, message: std::fmt::Arguments) {
+    if enabled(level) {
+        let module = module.trim_start_matches("whocares::");
+        eprintln!(
+            "{} {:5} {module}: {message}",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            level.name()
+        );
+    }
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, module_path!(), format_args!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warn, module_path!(), format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, module_path!(), format_args!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, module_path!(), format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, error, info, warning};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_filter_works() {
+        assert_eq!(parse_filter("debug"), Some(Level::Debug));
+        assert_eq!(parse_filter("hyper=trace,whocares=info"), Some(Level::Info));
+        assert_eq!(parse_filter("hyper=trace"), None);
+        assert_eq!(parse_filter("loud"), None);
+    }
+}
@@ -1,120 +1,1604 @@
 use chrono::prelude::*;
+use logging::{debug, warning};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::{self};
-use whocares::date_serializer;
+use std::fs;
+use std::io::{self, Read, Write};
+use whocares_core::rotation::{
+    self, near_duplicates, split_reschedule, validate_blackouts, validate_blocks, validate_shifts,
+    Anchor, Blackout, Block, Coverage, Direction, Shift, SplitRule,
+};
+use whocares_core::week::{
+    is_valid_reschedule_key, iso_week_key, legacy_reschedule_key, parse_week, probable_week,
+    reschedule_key, start_of_week,
+};
+use whocares_core::Week;
+use whocares_core::{holidays, CareWeek, Rotation};
+
+mod atom;
+mod audit;
+mod availability;
+#[cfg(feature = "sync")]
+mod caldav;
+mod cli;
+mod contacts;
+mod credits;
+mod crypto;
+#[cfg(feature = "notifications")]
+mod daemon;
+mod diff;
+mod doctor;
+mod dry_run;
+mod exit;
+mod export;
+mod forecast;
+mod git;
+#[cfg(feature = "sync")]
+mod github;
+#[cfg(feature = "server")]
+mod graphql;
+mod grid;
+#[cfg(feature = "server")]
+mod grpc;
+mod history;
+mod http;
+mod i18n;
+mod ics;
+mod import;
+#[cfg(feature = "notifications")]
+mod irc;
+mod ldap;
+mod limits;
+mod logging;
+#[cfg(feature = "notifications")]
+mod mqtt;
+mod mutate;
+#[cfg(feature = "notifications")]
+mod notify;
+#[cfg(feature = "sync")]
+mod opsgenie;
+#[cfg(feature = "sync")]
+mod outlook;
+#[cfg(feature = "sync")]
+mod pagerduty;
+mod plan;
+mod preferences;
+mod print;
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "notifications")]
+mod signal;
+#[cfg(feature = "server")]
+mod slack;
+mod snapshot;
+#[cfg(feature = "notifications")]
+mod sns;
+mod stats;
+#[cfg(feature = "sqlite")]
+mod store;
+mod svg;
+mod swaps;
+#[cfg(feature = "sync")]
+mod sync;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "notifications")]
+mod twilio;
+mod tz;
+mod watch;
 
 const PATH: &str = "./config.json";
+const DEFAULT_EXPORT_WEEKS: u32 = 52;
+const DEFAULT_FORECAST_WEEKS: u32 = 3;
+const MAX_WEEKS: u32 = whocares_core::MAX_WEEKS as u32;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct Config {
-    #[serde(with = "date_serializer")]
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(deserialize_with = "whocares_core::schedule::date")]
     startdate: chrono::NaiveDate,
     caretakers: Vec<String>,
+    // Take every other week, like grandparents on odd weeks and parents
+    // on even ones, counted from the start date.
+    #[serde(default)]
+    odd_week_caretakers: Vec<String>,
+    // Terms, breaks and the like with their own rosters.
+    #[serde(default)]
+    blocks: Vec<Block>,
+    // Closures like a renovation, different from anyone's vacation: the
+    // weeks are suspended for everyone.
+    #[serde(default)]
+    blackouts: Vec<Blackout>,
+    // Floaters outside the rotation who stand in for the away, their
+    // weeks are counted apart in stats.
+    #[serde(default)]
+    substitutes: Vec<String>,
+    // Like {"day": "thu"} to hand over to the next one in the roster
+    // every Thursday. Reschedules like "Alice/Bob" split single weeks.
+    #[serde(default)]
+    split: Option<SplitRule>,
+    // Days at the start of their week the new caretaker shares with the
+    // outgoing one, like 1 to hand over on Monday together.
+    #[serde(default)]
+    overlap_days: u32,
+    // Only these days are covered, like ["sat", "sun"] for a weekend
+    // rotation that still moves on weekly.
+    #[serde(default)]
+    days: Coverage,
+    // Rotate each weekday on its own, for chores split by day.
+    #[serde(default)]
+    per_weekday: bool,
+    // true to go through the roster backwards, or a date to turn around
+    // from that week on, like an informal rotation that went the other way.
+    #[serde(default)]
+    reverse: Direction,
+    // Turns the rotation on by this many places, or pins someone to a
+    // week with an anchor like "Bob on 2025-03-03", to line it up with
+    // reality without moving the start date.
+    #[serde(default)]
+    offset: i64,
+    #[serde(default)]
+    anchor: Option<Anchor>,
+    // Like day and night shifts, each with its own offset in the roster.
+    #[serde(default)]
+    shifts: Vec<Shift>,
     reschedule: HashMap<String, String>,
+    #[cfg(feature = "server")]
+    #[serde(default)]
+    slack: slack::SlackConfig,
+    #[cfg(feature = "server")]
+    #[serde(default)]
+    server: server::ServerConfig,
+    #[cfg(feature = "server")]
+    #[serde(default)]
+    cors: server::CorsConfig,
+    #[cfg(feature = "notifications")]
+    #[serde(default)]
+    notifications: notify::NotificationConfig,
+    #[cfg(feature = "sync")]
+    #[serde(default)]
+    caldav: caldav::CalDavConfig,
+    #[cfg(feature = "sync")]
+    #[serde(default)]
+    outlook: outlook::OutlookConfig,
+    #[cfg(feature = "sync")]
+    #[serde(default)]
+    pagerduty: pagerduty::PagerDutyConfig,
+    #[cfg(feature = "sync")]
+    #[serde(default)]
+    opsgenie: opsgenie::OpsgenieConfig,
+    #[cfg(feature = "sync")]
+    #[serde(default)]
+    github: github::GitHubConfig,
+    #[serde(default)]
+    availability: HashMap<String, Vec<String>>,
+    #[serde(skip)]
+    unavailable: availability::Unavailability,
+    #[serde(default)]
+    holidays: holidays::HolidayConfig,
+    #[serde(default)]
+    ics: ics::IcsConfig,
+    #[serde(default)]
+    history_file: Option<String>,
+    // A SQLite database next to the config for history, sync state and the
+    // audit log, instead of their own files.
+    #[serde(default)]
+    database: Option<String>,
+    #[serde(default)]
+    credits: credits::CreditConfig,
+    #[serde(default)]
+    plan: plan::PlanConfig,
+    // Proposed swaps, reschedules only once the other side approves.
+    #[serde(default)]
+    swaps: Vec<swaps::Proposal>,
+    // Weeks each caretaker prefers or avoids, for plan and fairness.
+    #[serde(default)]
+    preferences: preferences::PreferenceConfig,
+    #[serde(skip)]
+    balances: credits::Balances,
+    // OS user names of caretakers whose names differ, for --me.
+    #[serde(default)]
+    users: HashMap<String, String>,
+    #[serde(default)]
+    contacts: contacts::Contacts,
+    #[serde(default)]
+    ldap: Option<ldap::LdapConfig>,
+    // IANA name like "Europe/Berlin" for what today is and when handoffs
+    // happen, instead of the system's timezone.
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(skip)]
+    zone: tz::Zone,
+    #[serde(flatten)]
+    formatting: i18n::Formatting,
 }
 
-struct CareWeek {
-    week: u32,
-    caretaker: String,
-    start_date: chrono::NaiveDate,
-    end_date: chrono::NaiveDate,
+impl Config {
+    fn today(&self) -> NaiveDate {
+        self.zone.today()
+    }
+
+    // All rosters including the blocks' and the substitutes, someone on
+    // several only once.
+    fn everyone(&self) -> impl Iterator<Item = &String> {
+        let blocks = self
+            .blocks
+            .iter()
+            .flat_map(|b| b.caretakers.iter().chain(&b.odd_week_caretakers));
+        let mut seen = std::collections::HashSet::new();
+        self.caretakers
+            .iter()
+            .chain(&self.odd_week_caretakers)
+            .chain(blocks)
+            .chain(&self.substitutes)
+            .filter(move |c| seen.insert(c.as_str()))
+    }
+
+    // The caretakers in the order they take turns from the start date, for
+    // services that only rotate forward. A shift's turns are its offset on.
+    #[cfg(feature = "sync")]
+    fn turn_order(&self, shift: Option<&Shift>) -> io::Result<Vec<&String>> {
+        let step = match self.reverse {
+            Direction::Forward => 1,
+            Direction::Reverse => -1,
+            Direction::ReverseFrom(_) => {
+                return Err(io::Error::other(
+                    "the rotation turns around on a date, only \"reverse\": true or false can be exported",
+                ))
+            }
+        };
+        let n = self.caretakers.len() as i64;
+        let offset = self.offset + shift.map_or(0, |s| (s.offset % n.max(1) as usize) as i64);
+        Ok((0..n)
+            .map(|turn| &self.caretakers[(step * turn + offset).rem_euclid(n) as usize])
+            .collect())
+    }
+
+    fn is_caretaker(&self, name: &str) -> bool {
+        self.everyone().any(|c| c == name)
+    }
+
+    fn rotation(&self) -> Rotation<'_> {
+        Rotation {
+            startdate: self.startdate,
+            caretakers: &self.caretakers,
+            odd_caretakers: &self.odd_week_caretakers,
+            blocks: &self.blocks,
+            blackouts: &self.blackouts,
+            substitutes: &self.substitutes,
+            split: self.split,
+            overlap_days: self.overlap_days,
+            coverage: self.days,
+            per_weekday: self.per_weekday,
+            direction: self.reverse,
+            offset: self.offset,
+            shifts: &self.shifts,
+            reschedule: &self.reschedule,
+            holidays: &self.holidays,
+            unavailable: &self.unavailable,
+            balances: self.credits.prefer_owing.then_some(&self.balances),
+        }
+    }
+}
+
+// Points at the line and column serde stopped at, and names the key on
+// that line, for people who don't read JSON every day.
+fn config_error(path: &str, json: &str, e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    let message = message
+        .rfind(" at line ")
+        .map_or(message.as_str(), |i| &message[..i]);
+    let mut report = format!(
+        "invalid config {path}:{}:{}: {message}",
+        e.line(),
+        e.column()
+    );
+    let Some(line) = json.lines().nth(e.line().saturating_sub(1)) else {
+        return report;
+    };
+    let key = line
+        .split_once(':')
+        .map(|(key, _)| key.trim().trim_matches('"'))
+        .filter(|key| !key.is_empty() && !key.contains(['{', '[', ',']));
+    if let Some(key) = key {
+        report.push_str(&format!(" (in \"{key}\")"));
+    }
+    let number = e.line().to_string();
+    let gutter = " ".repeat(number.len());
+    let caret = " ".repeat(e.column().saturating_sub(1));
+    report.push_str(&format!(
+        "\n{gutter} |\n{number} | {line}\n{gutter} | {caret}^"
+    ));
+    report
+}
+
+// Reads at most the size limit, so a huge file isn't loaded to be refused.
+fn read_config(file: fs::File, path: &str, limits: &limits::Limits) -> io::Result<String> {
+    let mut json = String::new();
+    file.take(limits.file_bytes + 1).read_to_string(&mut json)?;
+    if json.len() as u64 > limits.file_bytes {
+        return Err(exit::with_code(
+            exit::CONFIG_INVALID,
+            io::Error::other(format!(
+                "{path} is larger than the limit of {} bytes (set WHOCARES_MAX_CONFIG_BYTES to raise it)",
+                limits.file_bytes
+            )),
+        ));
+    }
+    Ok(json)
 }
 
 fn get_config(path: &str) -> io::Result<Config> {
-    if let Ok(file) = File::open(path) {
-        let reader = io::BufReader::new(file);
-        let schedule: Config = serde_json::from_reader(reader).unwrap();
+    let limits = limits::Limits::from_env();
+    if let Ok(file) = fs::File::open(path) {
+        let json = read_config(file, path, &limits)?;
+        debug!("loading {path}");
+        let mut schedule: Config = serde_json::from_str(&json).map_err(|e| {
+            exit::with_code(
+                exit::CONFIG_INVALID,
+                io::Error::other(config_error(path, &json, &e)),
+            )
+        })?;
+        limits
+            .check(path, schedule.everyone().count(), schedule.reschedule.len())
+            .map_err(|e| exit::with_code(exit::CONFIG_INVALID, e))?;
+        schedule
+            .holidays
+            .validate()
+            .map_err(|e| exit::with_code(exit::CONFIG_INVALID, e))?;
+        validate_caretakers(&schedule.caretakers, path)?;
+        if !schedule.odd_week_caretakers.is_empty() {
+            validate_caretakers(&schedule.odd_week_caretakers, path)?;
+        }
+        if !schedule.substitutes.is_empty() {
+            validate_caretakers(&schedule.substitutes, path)?;
+        }
+        for block in &schedule.blocks {
+            validate_caretakers(&block.caretakers, path)?;
+            if !block.odd_week_caretakers.is_empty() {
+                validate_caretakers(&block.odd_week_caretakers, path)?;
+            }
+        }
+        validate_blocks(&schedule.blocks)
+            .and_then(|_| validate_blackouts(&schedule.blackouts))
+            .and_then(|_| validate_shifts(&schedule.shifts))
+            .map_err(|e| {
+                exit::with_code(
+                    exit::CONFIG_INVALID,
+                    io::Error::other(format!("{e} in {path}")),
+                )
+            })?;
+        validate_reschedules(&schedule.reschedule, path)?;
+        if let Some(anchor) = &schedule.anchor {
+            let invalid = |e: String| {
+                exit::with_code(
+                    exit::CONFIG_INVALID,
+                    io::Error::other(format!("{e} in {path}")),
+                )
+            };
+            if schedule.offset != 0 {
+                return Err(invalid("offset and anchor both set".to_string()));
+            }
+            schedule.offset = schedule.rotation().anchor_offset(anchor).map_err(invalid)?;
+        }
+        if schedule.database.is_some() && !cfg!(feature = "sqlite") {
+            return Err(exit::with_code(
+                exit::CONFIG_INVALID,
+                io::Error::other(format!(
+                    "database in {path} needs whocares built with the sqlite feature"
+                )),
+            ));
+        }
+        if schedule.overlap_days > 6 {
+            return Err(exit::with_code(
+                exit::CONFIG_INVALID,
+                io::Error::other(format!(
+                    "overlap_days in {path} is {}, at most 6 days of a week can overlap",
+                    schedule.overlap_days
+                )),
+            ));
+        }
+        #[cfg(feature = "notifications")]
+        if schedule.notifications.reminder_hours > notify::MAX_REMINDER_HOURS {
+            return Err(exit::with_code(
+                exit::CONFIG_INVALID,
+                io::Error::other(format!(
+                    "reminder_hours in {path} is {}, reminders can be at most {} hours early",
+                    schedule.notifications.reminder_hours,
+                    notify::MAX_REMINDER_HOURS
+                )),
+            ));
+        }
+        if let Some(timezone) = &schedule.timezone {
+            schedule.zone = tz::Zone::load(Some(timezone)).map_err(|e| {
+                exit::with_code(
+                    exit::CONFIG_INVALID,
+                    io::Error::other(format!("timezone {timezone} in {path}: {e}")),
+                )
+            })?;
+        }
+        schedule.formatting.emoji = schedule
+            .contacts
+            .iter()
+            .filter_map(|(name, c)| Some((name.clone(), c.emoji.clone()?)))
+            .collect();
+        schedule.unavailable = availability::load(path, &schedule.availability);
+        if schedule.credits.prefer_owing {
+            let today = schedule.today();
+            schedule.balances = credits::balances_until(&schedule, path, today)?;
+        }
         Ok(schedule)
     } else {
-        Err(io::Error::new(io::ErrorKind::Other, "Failed to open file"))
+        Err(exit::with_code(
+            exit::CONFIG_NOT_FOUND,
+            io::Error::other(format!("Failed to open file {path}")),
+        ))
     }
 }
 
-fn get_current_caretaker_idx(conf: &Config) -> usize {
-    let start = conf.startdate;
-    let current_date: chrono::NaiveDate = chrono::Local::now().date_naive();
+// Today in the configured timezone, or the system's if the config doesn't
+// load.
+fn today(config_path: &str) -> NaiveDate {
+    get_config(config_path).map_or_else(|_| chrono::Local::now().date_naive(), |conf| conf.today())
+}
 
-    let diff = start
-        .iter_weeks()
-        .take_while(|w| w <= &current_date)
-        .count()
-        - 1;
+// The rotation is indexed modulo the roster length, so it can't be empty.
+fn validate_caretakers(caretakers: &[String], path: &str) -> io::Result<()> {
+    if caretakers.is_empty() {
+        return Err(exit::with_code(
+            exit::NO_CARETAKER,
+            io::Error::other(format!("no caretakers configured in {path}")),
+        ));
+    }
+    if caretakers.iter().any(|c| c.trim().is_empty()) {
+        return Err(exit::with_code(
+            exit::CONFIG_INVALID,
+            io::Error::other(format!("empty caretaker name in {path}")),
+        ));
+    }
+    if let Some(name) = caretakers.iter().find(|c| c.contains('/')) {
+        return Err(exit::with_code(
+            exit::CONFIG_INVALID,
+            io::Error::other(format!(
+                "caretaker \"{name}\" in {path} contains a /, which splits weeks in reschedules"
+            )),
+        ));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = caretakers.iter().find(|c| !seen.insert(c.as_str())) {
+        return Err(exit::with_code(
+            exit::CONFIG_INVALID,
+            io::Error::other(format!(
+                "{duplicate} is listed twice in {path}, they'd take twice as many weeks"
+            )),
+        ));
+    }
+    for (a, b) in near_duplicates(caretakers) {
+        warning!("\"{a}\" and \"{b}\" only differ in case or spaces, is it the same person?");
+    }
+    Ok(())
+}
 
-    diff % conf.caretakers.len()
+// A key that doesn't parse back to itself would never match a week.
+fn validate_reschedules(reschedule: &HashMap<String, String>, path: &str) -> io::Result<()> {
+    let mut keys: Vec<&String> = reschedule.keys().collect();
+    keys.sort();
+    for key in keys {
+        if !is_valid_reschedule_key(key) {
+            let hint = match probable_week(key) {
+                Some(week) => format!(", did you mean {week}?"),
+                None => String::new(),
+            };
+            return Err(exit::with_code(
+                exit::CONFIG_INVALID,
+                io::Error::other(format!(
+                    "invalid reschedule key \"{key}\" in {path}, expected an ISO week like 2025-W07{hint}"
+                )),
+            ));
+        }
+    }
+    rotation::validate_reschedules(reschedule).map_err(|e| {
+        exit::with_code(
+            exit::CONFIG_INVALID,
+            io::Error::other(format!("{e} in {path}")),
+        )
+    })
 }
 
+// Replaces an old style key of the same week, so there's only one entry.
+fn set_reschedule(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    week: Week,
+    caretaker: &str,
+) {
+    let legacy = legacy_reschedule_key(week.monday());
+    if parse_week(&legacy) == Some(week.monday()) {
+        map.remove(&legacy);
+    }
+    map.insert(reschedule_key(week), caretaker.into());
+}
+
+#[cfg(test)]
 fn get_current_caretaker(conf: &Config) -> String {
-    let caretaker_idx = get_current_caretaker_idx(conf);
-    conf.caretakers.get(caretaker_idx).unwrap().to_string()
+    regular_caretaker(conf, start_of_week(conf.today())).to_string()
+}
+
+fn regular_caretaker(conf: &Config, start_of_week: NaiveDate) -> &str {
+    conf.rotation().regular_caretaker(start_of_week)
+}
+
+// The core doesn't log, so substitutes are reported here.
+fn log_substitute(rotation: &Rotation, week: &CareWeek) {
+    let start_of_week = week.start_date;
+    if rotation
+        .rescheduled(Week::containing(start_of_week))
+        .is_some()
+    {
+        return;
+    }
+    let regular = rotation.regular_caretaker(start_of_week);
+    if *week.caretaker != *regular {
+        debug!(
+            "{regular} is away in week of {start_of_week}, {} substitutes",
+            week.caretaker
+        );
+    } else if !rotation.is_available(regular, start_of_week) {
+        warning!("nobody is available in week of {start_of_week}, keeping {regular}");
+    }
+}
+
+fn get_week(conf: &Config, start_of_week: NaiveDate) -> CareWeek {
+    let rotation = conf.rotation();
+    let mut week = rotation.week(start_of_week);
+    log_substitute(&rotation, &week);
+    swaps::mark(conf, &mut week);
+    week
 }
 
+// Before the rotation starts the weeks begin at the start date, see
+// weeks_until_start for the gap.
 fn get_next_weeks(conf: &Config, weeks: u32) -> Vec<CareWeek> {
-    let caretaker_idx = get_current_caretaker_idx(conf);
-    let num_caretakers = conf.caretakers.len();
-    let start_of_current_week = chrono::Local::now()
-        .date_naive()
-        .week(Weekday::Mon)
-        .first_day();
-
-    start_of_current_week
+    let today = conf.today();
+    get_weeks(conf, today.max(conf.startdate), weeks as usize)
+}
+
+// None once the rotation has started.
+fn weeks_until_start(conf: &Config, today: NaiveDate) -> Option<i64> {
+    let weeks = (start_of_week(conf.startdate) - start_of_week(today)).num_weeks();
+    (weeks > 0).then_some(weeks)
+}
+
+// Who has today, nobody before the rotation starts or while it's suspended.
+fn on_duty(conf: &Config, today: NaiveDate) -> Vec<String> {
+    if weeks_until_start(conf, today).is_some() {
+        return Vec::new();
+    }
+    let week = get_week(conf, start_of_week(today));
+    week.caretakers_on(today)
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+// The week containing today, nobody's on duty before the rotation starts.
+#[cfg(any(feature = "notifications", feature = "server"))]
+fn current_week(conf: &Config) -> Option<CareWeek> {
+    let today = conf.today();
+    get_next_weeks(conf, 1)
+        .pop()
+        .filter(|week| week.start_date <= today)
+}
+
+fn weeks_from(conf: &Config, from: NaiveDate) -> impl Iterator<Item = CareWeek> + '_ {
+    let rotation = conf.rotation();
+    rotation.weeks_from(from).map(move |mut week| {
+        log_substitute(&rotation, &week);
+        swaps::mark(conf, &mut week);
+        week
+    })
+}
+
+fn get_weeks(conf: &Config, from: NaiveDate, weeks: usize) -> Vec<CareWeek> {
+    weeks_from(conf, from).take(weeks).collect()
+}
+
+#[cfg(not(all(
+    feature = "server",
+    feature = "sync",
+    feature = "notifications",
+    feature = "tui"
+)))]
+fn disabled(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("whocares was built without the {feature} feature"),
+    )
+}
+
+fn usage(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("usage: {message}"))
+}
+
+fn parse_weeks(arg: Option<&String>, default: u32) -> io::Result<u32> {
+    let Some(arg) = arg else {
+        return Ok(default);
+    };
+    match arg.parse::<u32>() {
+        Ok(weeks @ 1..=MAX_WEEKS) => Ok(weeks),
+        Ok(0) => Err(usage("WEEKS has to be at least 1")),
+        Ok(_) => Err(usage(&format!(
+            "{arg} weeks is too many, at most {MAX_WEEKS} (a century)"
+        ))),
+        Err(_) => Err(usage(&format!(
+            "\"{arg}\" is not a number of weeks or a command, see whocares --help"
+        ))),
+    }
+}
+
+fn import_ics(config_path: &str, file: &str) -> io::Result<()> {
+    let conf = get_config(config_path)?;
+    let ics = std::fs::read_to_string(file)?;
+    let reschedules = import::reschedules_from_events(&conf, &ics::parse_events(&ics));
+    for (week, caretaker) in &reschedules {
+        if !conf.is_caretaker(caretaker) {
+            warning!("{caretaker} is not in the list of caretakers");
+        }
+        println!("{week}: {caretaker}");
+    }
+    mutate::update_config(config_path, &format!("import ics {file}"), |config| {
+        let map = mutate::reschedule_map(config)?;
+        for (week, caretaker) in &reschedules {
+            set_reschedule(map, *week, caretaker);
+        }
+        Ok(())
+    })
+}
+
+// Adds or removes a caretaker without reshuffling the weeks people know
+// about: the next `keep` weeks keep their caretakers, if need be as
+// reschedules, and an anchor lets the rest go on with whoever was next.
+fn change_roster(config_path: &str, add: bool, name: &str, keep: usize) -> io::Result<()> {
+    let conf = get_config(config_path)?;
+    if !conf.odd_week_caretakers.is_empty() || !conf.blocks.is_empty() {
+        return Err(io::Error::other(
+            "the roster can only be re-anchored without odd_week_caretakers and blocks",
+        ));
+    }
+    let listed = conf.caretakers.iter().any(|c| c == name);
+    let mut caretakers = conf.caretakers.clone();
+    match (add, listed) {
+        (true, false) => caretakers.push(name.to_string()),
+        (false, true) if caretakers.len() > 1 => caretakers.retain(|c| c != name),
+        (true, true) => return Err(io::Error::other(format!("{name} is already a caretaker"))),
+        (false, true) => return Err(io::Error::other(format!("{name} is the only caretaker"))),
+        (false, false) => return Err(io::Error::other(format!("{name} is not a caretaker"))),
+    }
+
+    let old = conf.rotation();
+    let from = start_of_week(conf.today().max(conf.startdate));
+    let reflow = from + chrono::Days::new(7 * keep as u64);
+    // Whoever was next after the kept weeks, or after them if they leave.
+    let next = reflow
         .iter_weeks()
-        .zip(caretaker_idx..(caretaker_idx + weeks as usize))
-        .map(|(d, i)| {
-            let week_number: u32 = d.iso_week().week();
-            let start_of_week = d;
-            let end_of_week = start_of_week
-                .checked_add_days(chrono::Days::new(6))
-                .unwrap();
-
-            let caretaker =
-                match &conf
-                    .reschedule
-                    .get(&format!("{}-{}", d.year_ce().1, week_number))
-                {
-                    Some(rescheduled_caretaker) => rescheduled_caretaker,
-                    None => {
-                        let idx = i % num_caretakers;
-                        let regular_caretaker = conf.caretakers.get(idx).unwrap();
-                        regular_caretaker
-                    }
-                };
+        .take(conf.caretakers.len())
+        .map(|d| old.regular_caretaker(d))
+        .find(|c| caretakers.iter().any(|n| n == c))
+        .unwrap_or(&caretakers[0]);
+    let anchor = Anchor {
+        caretaker: next.to_string(),
+        week: Week::containing(reflow),
+    };
+    let new = Rotation {
+        caretakers: &caretakers,
+        ..old
+    };
+    let new = Rotation {
+        offset: new.anchor_offset(&anchor).map_err(io::Error::other)?,
+        ..new
+    };
+    let pinned: Vec<(Week, &str)> = from
+        .iter_weeks()
+        .take(keep)
+        .map(|d| (d, old.caretaker(d)))
+        .filter(|(d, caretaker)| new.caretaker(*d) != *caretaker)
+        .map(|(d, caretaker)| (Week::containing(d), caretaker))
+        .collect();
 
-            CareWeek {
-                week: week_number,
-                caretaker: caretaker.clone(),
-                start_date: start_of_week,
-                end_date: end_of_week,
+    println!("{} {name}", if add { '+' } else { '-' });
+    for (week, caretaker) in &pinned {
+        println!("{week}: {caretaker} stays");
+    }
+    let anchor = format!("{} on {}", anchor.caretaker, anchor.week);
+    println!("anchor: {anchor}");
+    let action = format!("roster {} {name}", if add { "add" } else { "remove" });
+    mutate::update_config(config_path, &action, |config| {
+        let Some(object) = config.as_object_mut() else {
+            return Err(io::Error::other("config is not a JSON object"));
+        };
+        object.insert("caretakers".to_string(), caretakers.clone().into());
+        object.insert("anchor".to_string(), anchor.clone().into());
+        object.remove("offset");
+        let map = mutate::reschedule_map(config)?;
+        for (week, caretaker) in &pinned {
+            set_reschedule(map, *week, caretaker);
+        }
+        Ok(())
+    })
+}
+
+// Writes the weeks the plan gives to someone else than now as reschedules.
+fn plan_weeks(config_path: &str, weeks: usize) -> io::Result<()> {
+    let conf = get_config(config_path)?;
+    let from = conf.today();
+    let changes: Vec<(Week, String)> = plan::plan(&conf, from, weeks)?
+        .into_iter()
+        .filter(|(week, caretaker)| *get_week(&conf, week.monday()).caretaker != **caretaker)
+        .collect();
+    if changes.is_empty() {
+        println!("the schedule already follows the plan");
+        return Ok(());
+    }
+    for (week, caretaker) in &changes {
+        println!("{week}: {caretaker}");
+    }
+    mutate::update_config(config_path, &format!("plan {weeks}"), |config| {
+        let map = mutate::reschedule_map(config)?;
+        for (week, caretaker) in &changes {
+            set_reschedule(map, *week, caretaker);
+        }
+        Ok(())
+    })
+}
+
+fn import_roster(config_path: &str, file: &str) -> io::Result<()> {
+    let roster = import::roster_from_csv(&std::fs::read_to_string(file)?);
+    let action = format!("import roster {file}");
+    merge_roster(config_path, &action, file, &roster)
+}
+
+// Appends new names to the roster and fills in their contacts, leaving
+// everyone else where they are.
+fn merge_roster(
+    config_path: &str,
+    action: &str,
+    source: &str,
+    roster: &[import::RosterEntry],
+) -> io::Result<()> {
+    let conf = get_config(config_path)?;
+    for entry in roster {
+        let known = conf.contacts.get(&entry.name).cloned().unwrap_or_default();
+        let mut details = Vec::new();
+        for (kind, new, old) in [
+            ("email", &entry.contact.email, &known.email),
+            ("phone", &entry.contact.phone, &known.phone),
+        ] {
+            if let Some(new) = new.as_ref().filter(|new| Some(*new) != old.as_ref()) {
+                details.push(format!("{kind} {new}"));
             }
-        })
-        .collect::<Vec<CareWeek>>()
+        }
+        let new = !conf.is_caretaker(&entry.name);
+        if new || !details.is_empty() {
+            let mark = if new { '+' } else { ' ' };
+            let line = format!("{mark} {} {}", entry.name, details.join(", "));
+            println!("{}", line.trim_end());
+        }
+    }
+    for caretaker in conf.everyone() {
+        if !roster.iter().any(|e| &e.name == caretaker) {
+            println!("! {caretaker} is not in {source}, keeping them");
+        }
+    }
+
+    mutate::update_config(config_path, action, |config| {
+        let caretakers = config
+            .get_mut("caretakers")
+            .and_then(serde_json::Value::as_array_mut)
+            .ok_or_else(|| io::Error::other("caretakers is not a JSON array"))?;
+        for entry in roster {
+            if !caretakers.iter().any(|c| c == entry.name.as_str()) {
+                caretakers.push(entry.name.as_str().into());
+            }
+        }
+        let contacts = mutate::object(config, "contacts")?;
+        for entry in roster {
+            let fields = [
+                ("email", &entry.contact.email),
+                ("phone", &entry.contact.phone),
+            ];
+            for (key, value) in fields {
+                let Some(value) = value else { continue };
+                let contact = contacts
+                    .entry(entry.name.as_str())
+                    .or_insert_with(|| serde_json::json!({}))
+                    .as_object_mut()
+                    .ok_or_else(|| {
+                        io::Error::other(format!("contact {} is not a JSON object", entry.name))
+                    })?;
+                contact.insert(key.to_string(), value.as_str().into());
+            }
+        }
+        Ok(())
+    })
 }
 
-fn main() {
-    let weeks_to_preview = if env::args().len() == 2 {
-        let arg: Vec<String> = env::args().into_iter().collect();
+fn reschedule(config_path: &str, week: &str, caretaker: &str) -> io::Result<()> {
+    let start = parse_week(week).ok_or_else(|| usage(&cli::synopsis("reschedule")))?;
+    let change = HashMap::from([(iso_week_key(start), caretaker.to_string())]);
+    rotation::validate_reschedules(&change)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let conf = get_config(config_path)?;
+    let (first, second) = split_reschedule(caretaker);
+    for name in [Some(first), second].into_iter().flatten() {
+        if !conf.is_caretaker(name) {
+            warning!("{name} is not in the list of caretakers");
+        }
+    }
+    let action = format!("reschedule add {} {caretaker}", iso_week_key(start));
+    mutate::update_config(config_path, &action, |config| {
+        set_reschedule(
+            mutate::reschedule_map(config)?,
+            Week::containing(start),
+            caretaker,
+        );
+        Ok(())
+    })
+}
+
+// The reschedule value giving a week to its caretakers, split or not.
+fn assignment(week: &CareWeek) -> String {
+    match &week.split {
+        Some(split) => format!("{}/{}", week.caretaker, split.caretaker),
+        None => week.caretaker.to_string(),
+    }
+}
+
+// Trades the caretakers of two weeks, whoever they currently are.
+fn swap(config_path: &str, first: &str, second: &str) -> io::Result<()> {
+    let (Some(first), Some(second)) = (parse_week(first), parse_week(second)) else {
+        return Err(usage(&cli::synopsis("swap")));
+    };
+    let conf = get_config(config_path)?;
+    let (a, b) = (get_week(&conf, first), get_week(&conf, second));
+    if assignment(&a) == assignment(&b) {
+        return Err(io::Error::other(format!(
+            "{} has both weeks, nothing to swap",
+            a.caretakers()
+        )));
+    }
+    println!(
+        "{}: {} -> {}",
+        iso_week_key(first),
+        a.caretakers(),
+        b.caretakers()
+    );
+    println!(
+        "{}: {} -> {}",
+        iso_week_key(second),
+        b.caretakers(),
+        a.caretakers()
+    );
+    let action = format!(
+        "swap {} {} {}<->{}",
+        iso_week_key(first),
+        iso_week_key(second),
+        a.caretakers(),
+        b.caretakers()
+    );
+    mutate::update_config(config_path, &action, |config| {
+        let map = mutate::reschedule_map(config)?;
+        set_reschedule(map, Week::containing(first), &assignment(&b));
+        set_reschedule(map, Week::containing(second), &assignment(&a));
+        Ok(())
+    })
+}
+
+// Drops reschedules of weeks that are over.
+fn prune(config_path: &str, today: NaiveDate) -> io::Result<()> {
+    let current = start_of_week(today);
+    mutate::update_config(config_path, "prune", |config| {
+        mutate::reschedule_map(config)?.retain(|week, _| {
+            let past = parse_week(week).is_some_and(|start| start < current);
+            if past {
+                println!("removed {week}");
+            }
+            !past
+        });
+        Ok(())
+    })
+}
+
+// Lets the user edit a copy of the config and only takes it over once it
+// loads, so a typo never ends up in the real file.
+fn edit(config_path: &str) -> io::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let draft = format!("{config_path}.edit");
+    let original: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(config_path)?)?;
+    std::fs::copy(config_path, &draft)?;
 
-        match arg[1].parse::<u32>() {
-            Ok(n) => n,
-            Err(e) => panic!("{e}"),
+    let result = loop {
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{editor} \"$1\""))
+            .arg("sh")
+            .arg(&draft)
+            .status()?;
+        if !status.success() {
+            break Err(io::Error::other(format!("{editor} exited with {status}")));
+        }
+        match get_config(&draft) {
+            Ok(_) => break Ok(()),
+            Err(e) => {
+                eprintln!("{e}");
+                eprint!("re-edit? [Y/n] ");
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if answer.trim().eq_ignore_ascii_case("n") {
+                    break Err(io::Error::other("config left unchanged"));
+                }
+            }
         }
-    } else {
-        4
     };
 
-    match get_config(PATH) {
-        Ok(conf) => {
+    let mut conflict = false;
+    let edited = result.and_then(|()| {
+        let edited: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&draft)?)?;
+        mutate::update_config(config_path, "edit", |config| {
+            if *config != original {
+                conflict = true;
+                return Err(io::Error::other(format!(
+                    "{config_path} was changed while you were editing, your version is in {draft}"
+                )));
+            }
+            *config = edited;
+            Ok(())
+        })
+    });
+    // Kept if someone else's change is in the way, to merge by hand.
+    if !conflict {
+        std::fs::remove_file(&draft)?;
+    }
+    edited
+}
+
+// Sends the state the daemon publishes on handoffs and reminders, to refresh
+// days_remaining from cron.
+#[cfg(feature = "notifications")]
+fn publish_mqtt(conf: &Config, weeks: &[CareWeek]) -> io::Result<()> {
+    let events = [notify::Event::Handoff, notify::Event::Reminder];
+    let timeout = std::time::Duration::from_secs(conf.notifications.timeout_secs);
+    for channel in &conf.notifications.channels {
+        if let notify::Channel::Mqtt(_) = channel {
+            for (event, week) in events.into_iter().zip(weeks) {
+                channel.send(
+                    &conf.formatting,
+                    &conf.contacts,
+                    event,
+                    week,
+                    conf.today(),
+                    timeout,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Fills a --format template like "{week}\t{caretaker}". Backslash escapes
+// are expanded since shells pass them literally.
+fn format_week(template: &str, formatting: &i18n::Formatting, week: &CareWeek) -> String {
+    [
+        ("{week}", week.week.to_string()),
+        ("{iso_week}", iso_week_key(week.start_date)),
+        ("{start}", formatting.date(week.start_date)),
+        ("{end}", formatting.date(week.end_date)),
+        ("{caretaker}", week.caretakers()),
+        ("{holidays}", week.holiday_note().trim().to_string()),
+        (
+            "{block}",
+            week.block.as_deref().unwrap_or_default().to_string(),
+        ),
+    ]
+    .iter()
+    .fold(
+        template.replace("\\t", "\t").replace("\\n", "\n"),
+        |line, (field, value)| line.replace(field, value),
+    )
+}
+
+// With --summary, `everyone` without any of the weeks show up with 0.
+fn print_weeks(conf: &Config, options: &Options, everyone: &[&str], weeks: &[CareWeek]) {
+    let formatting = &conf.formatting;
+    print_listing(
+        formatting,
+        options.format.as_deref(),
+        options.by_month,
+        weeks,
+    );
+    if options.summary {
+        println!("{}", i18n::tr(formatting.locale, "total"));
+        println!("{}", i18n::subtotal_line(formatting, everyone, weeks));
+    }
+}
+
+fn print_listing(
+    formatting: &i18n::Formatting,
+    template: Option<&str>,
+    by_month: bool,
+    weeks: &[CareWeek],
+) {
+    let print = |week| match template {
+        Some(template) => println!("{}", format_week(template, formatting, week)),
+        None => println!("{}", i18n::week_line(formatting, week)),
+    };
+    if !by_month {
+        return weeks.iter().for_each(print);
+    }
+    // A header per month of the weeks' Mondays, and who has how many.
+    for month in weeks.chunk_by(|a, b| a.start_date.month() == b.start_date.month()) {
+        println!("{}", i18n::month_line(formatting, month[0].start_date));
+        month.iter().for_each(print);
+        println!("{}", i18n::subtotal_line(formatting, &[], month));
+    }
+}
+
+fn me(conf: &Config, user: &str) -> io::Result<String> {
+    let caretaker = conf.users.get(user).map_or(user, String::as_str);
+    if !conf.is_caretaker(caretaker) {
+        return Err(io::Error::other(format!(
+            "--me: {user} is not a caretaker, map it to one under \"users\" in the config"
+        )));
+    }
+    Ok(caretaker.to_string())
+}
+
+fn profile_dir() -> Option<std::path::PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config")))?;
+    Some(config_home.join("whocares").join("profiles"))
+}
+
+// Named configs like "home" or "work" in the profile directory.
+fn profile_path(dir: &std::path::Path, name: &str) -> io::Result<String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid profile name {name}"),
+        ));
+    }
+    Ok(dir
+        .join(format!("{name}.json"))
+        .to_string_lossy()
+        .into_owned())
+}
+
+// Names and config paths of all profiles, sorted by name.
+fn profiles() -> io::Result<Vec<(String, String)>> {
+    let dir = profile_dir().ok_or_else(|| io::Error::other("HOME is not set"))?;
+    let mut profiles = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if let Some(name) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".json"))
+        {
+            profiles.push((name.to_string(), profile_path(&dir, name)?));
+        }
+    }
+    profiles.sort();
+    Ok(profiles)
+}
+
+// Flags accepted anywhere on the command line, removed from the arguments
+// before the subcommand is dispatched.
+#[derive(Default)]
+struct Options {
+    config_path: String,
+    // --weeks, takes precedence over a WEEKS argument.
+    weeks: Option<u32>,
+    caretaker: Option<String>,
+    me: bool,
+    watch: bool,
+    format: Option<String>,
+    // --group-by month.
+    by_month: bool,
+    // --summary, totals after the weeks.
+    summary: bool,
+    // Number of -v flags, or -1 for -q.
+    verbosity: i8,
+}
+
+impl Options {
+    fn parse(args: &mut Vec<String>) -> io::Result<Self> {
+        let mut options = Options {
+            config_path: PATH.to_string(),
+            ..Default::default()
+        };
+        while let Some(i) = args.iter().position(|a| a == "--profile") {
+            args.remove(i);
+            if i == args.len() {
+                return Err(usage("--profile <name>"));
+            }
+            let dir =
+                profile_dir().ok_or_else(|| io::Error::other("--profile: HOME is not set"))?;
+            options.config_path = profile_path(&dir, &args.remove(i))?;
+        }
+        while let Some(i) = args.iter().position(|a| a == "--dry-run") {
+            args.remove(i);
+            dry_run::enable();
+        }
+        while let Some(i) = args.iter().position(|a| a == "--watch") {
+            args.remove(i);
+            options.watch = true;
+        }
+        while let Some(i) = args.iter().position(|a| a == "--me") {
+            args.remove(i);
+            options.me = true;
+        }
+        while let Some(i) = args.iter().position(|a| {
+            matches!(
+                a.as_str(),
+                "-v" | "-vv" | "-vvv" | "--verbose" | "-q" | "--quiet"
+            )
+        }) {
+            options.verbosity = match args.remove(i).as_str() {
+                "-q" | "--quiet" => -1,
+                "-vv" => options.verbosity.max(0) + 2,
+                "-vvv" => options.verbosity.max(0) + 3,
+                _ => options.verbosity.max(0) + 1,
+            };
+        }
+        while let Some(i) = args.iter().position(|a| a == "--format") {
+            args.remove(i);
+            if i == args.len() {
+                return Err(usage("--format <template>"));
+            }
+            options.format = Some(args.remove(i));
+        }
+        while let Some(i) = args.iter().position(|a| a == "--summary") {
+            args.remove(i);
+            options.summary = true;
+        }
+        while let Some(i) = args.iter().position(|a| a == "--group-by") {
+            args.remove(i);
+            if i == args.len() || args.remove(i) != "month" {
+                return Err(usage("--group-by month"));
+            }
+            options.by_month = true;
+        }
+        while let Some(i) = args.iter().position(|a| a == "--weeks") {
+            args.remove(i);
+            if i == args.len() {
+                return Err(usage("--weeks <n>"));
+            }
+            options.weeks = Some(parse_weeks(Some(&args.remove(i)), 0)?);
+        }
+        while let Some(i) = args.iter().position(|a| a == "--caretaker") {
+            args.remove(i);
+            if i == args.len() {
+                return Err(usage("--caretaker <caretaker>"));
+            }
+            options.caretaker = Some(args.remove(i));
+        }
+        Ok(options)
+    }
+
+    fn weeks(&self, arg: Option<&String>, default: u32) -> io::Result<u32> {
+        match self.weeks {
+            Some(weeks) => Ok(weeks),
+            None => parse_weeks(arg, default),
+        }
+    }
+
+    // Turns --me into the caretaker of the invoking user, taken from
+    // WHOCARES_ME or the OS user and looked up in the "users" mapping.
+    fn resolve_me(&mut self) -> io::Result<()> {
+        if !self.me {
+            return Ok(());
+        }
+        let user = ["WHOCARES_ME", "USER", "USERNAME"]
+            .into_iter()
+            .find_map(|var| env::var(var).ok())
+            .ok_or_else(|| io::Error::other("--me: cannot determine the current user"))?;
+        self.caretaker = Some(me(&get_config(&self.config_path)?, &user)?);
+        Ok(())
+    }
+
+    // The caretaker given by --caretaker/--me or else as the next argument,
+    // and the arguments following it.
+    fn caretaker_arg<'a>(&'a self, args: &'a [String]) -> (Option<&'a str>, &'a [String]) {
+        match &self.caretaker {
+            Some(caretaker) => (Some(caretaker), args),
+            None => (
+                args.first().map(String::as_str),
+                args.get(1..).unwrap_or_default(),
+            ),
+        }
+    }
+
+    // Weeks the caretaker has a part of, split, per weekday or in a shift.
+    fn keeps(&self, week: &CareWeek) -> bool {
+        self.caretaker
+            .as_ref()
+            .is_none_or(|c| week.everyone().contains(&c.as_str()))
+    }
+
+    fn filter(&self, weeks: Vec<CareWeek>) -> Vec<CareWeek> {
+        weeks.into_iter().filter(|w| self.keeps(w)).collect()
+    }
+
+    fn filter_changes(&self, changes: Vec<diff::Change>) -> Vec<diff::Change> {
+        match &self.caretaker {
+            Some(caretaker) => changes
+                .into_iter()
+                .filter(|c| *c.week.caretaker == **caretaker || c.old_caretaker == *caretaker)
+                .collect(),
+            None => changes,
+        }
+    }
+}
+
+fn run(args: &[String], options: &Options) -> io::Result<()> {
+    let config_path = options.config_path.as_str();
+    let arg = |i: usize| args.get(i).map(String::as_str);
+
+    match arg(1) {
+        Some("--help" | "-h" | "help") => {
+            print!("{}", cli::help());
+            Ok(())
+        }
+        #[cfg(feature = "server")]
+        Some("serve") => server::run(arg(2).unwrap_or(server::DEFAULT_ADDR), config_path),
+        #[cfg(feature = "notifications")]
+        Some("daemon") => daemon::run(config_path),
+        #[cfg(feature = "notifications")]
+        Some("notify") => {
+            let event = match arg(2) {
+                None | Some("handoff") => notify::Event::Handoff,
+                Some("reminder") => notify::Event::Reminder,
+                Some(_) => return Err(usage(&cli::synopsis("notify"))),
+            };
+            daemon::fire(config_path, event)
+        }
+        #[cfg(feature = "server")]
+        Some("webcal") => {
+            print!("{}", server::subscription_links(&get_config(config_path)?));
+            Ok(())
+        }
+        #[cfg(not(feature = "server"))]
+        Some("serve" | "webcal") => Err(disabled("server")),
+        #[cfg(not(feature = "notifications"))]
+        Some("daemon" | "notify") => Err(disabled("notifications")),
+        #[cfg(not(feature = "tui"))]
+        Some("tui") => Err(disabled("tui")),
+        Some("import") => match (arg(2), arg(3)) {
+            (Some("ics"), Some(file)) => import_ics(config_path, file),
+            (Some("roster"), Some(file)) => import_roster(config_path, file),
+            _ => Err(usage(&cli::synopsis("import"))),
+        },
+        Some("sync") => match arg(2) {
+            Some("ldap") => {
+                let conf = get_config(config_path)?;
+                let Some(ldap) = &conf.ldap else {
+                    return Err(io::Error::other(format!(
+                        "no ldap configured in {config_path}"
+                    )));
+                };
+                let members = ldap.members()?;
+                merge_roster(config_path, "sync ldap", &ldap.url, &members)
+            }
+            _ => Err(usage(&cli::synopsis("sync"))),
+        },
+        Some("stats") => {
+            let conf = get_config(config_path)?;
+            let today = conf.today();
+            let (from, to) = stats::parse_period(&args[2..], today)
+                .ok_or_else(|| usage(&cli::synopsis("stats")))?;
+            print!("{}", stats::report(&conf, config_path, from, to)?);
+            Ok(())
+        }
+        Some("record") => {
+            let (Some(week), Some(caretaker)) = (arg(2), arg(3)) else {
+                return Err(usage(&cli::synopsis("record")));
+            };
+            let start = parse_week(week).ok_or_else(|| usage(&cli::synopsis("record")))?;
+            let conf = get_config(config_path)?;
+            if !conf.is_caretaker(caretaker) {
+                warning!("{caretaker} is not in the list of caretakers");
+            }
+            let mut history = history::History::load(config_path, &conf)?;
+            history.record(start, caretaker);
+            history.save()?;
+            println!("{}: covered by {caretaker}", iso_week_key(start));
+            Ok(())
+        }
+        Some("forecast") => {
+            let conf = get_config(config_path)?;
+            let count = options.weeks(args.get(2), DEFAULT_FORECAST_WEEKS)?;
+            let today = conf.today();
+            let mut forecast = forecast::forecast(&conf, today, count as usize);
+            if let Some(caretaker) = &options.caretaker {
+                forecast.retain(|(c, _)| c == caretaker);
+            }
+            print!("{}", forecast::report(&forecast));
+            Ok(())
+        }
+        Some("plan") => {
+            let weeks = options.weeks(args.get(2), DEFAULT_EXPORT_WEEKS / 4)?;
+            plan_weeks(config_path, weeks as usize)
+        }
+        Some("grid") => {
+            let conf = get_config(config_path)?;
+            let weeks = options.weeks(args.get(2), 4)?;
+            print!(
+                "{}",
+                grid::grid(&options.filter(get_next_weeks(&conf, weeks)))
+            );
+            Ok(())
+        }
+        Some("next-for") => {
+            let (caretaker, rest) = options.caretaker_arg(&args[2..]);
+            let caretaker = caretaker.ok_or_else(|| usage(&cli::synopsis("next-for")))?;
+            let count = options.weeks(rest.first(), 1)?;
+            let conf = get_config(config_path)?;
+            let today = conf.today();
+            let forecast = forecast::forecast(&conf, today, count as usize);
+            match forecast.into_iter().find(|(c, _)| c == caretaker) {
+                Some((_, weeks)) => print_weeks(&conf, options, &[], &weeks),
+                None => warning!("{caretaker} is not in the list of caretakers"),
+            }
+            Ok(())
+        }
+        Some("until-my-turn") => {
+            let (caretaker, _) = options.caretaker_arg(&args[2..]);
+            let caretaker = caretaker.ok_or_else(|| usage(&cli::synopsis("until-my-turn")))?;
+            let conf = get_config(config_path)?;
+            println!("{}", forecast::until_turn(&conf, caretaker, conf.today()));
+            Ok(())
+        }
+        Some("on-duty") => {
+            let (caretaker, _) = options.caretaker_arg(&args[2..]);
+            let caretaker = caretaker.ok_or_else(|| usage(&cli::synopsis("on-duty")))?;
+            let conf = get_config(config_path)?;
+            let today = on_duty(&conf, conf.today());
+            let on_duty = today.iter().any(|c| c == caretaker);
+            if options.verbosity > 0 {
+                match (on_duty, today.is_empty()) {
+                    (true, _) => println!("{caretaker} is on duty"),
+                    (false, true) => println!("{caretaker} is not on duty, nobody is"),
+                    (false, false) => {
+                        println!("{caretaker} is not on duty, {} is", today.join("/"))
+                    }
+                }
+            }
+            std::process::exit(if on_duty { 0 } else { exit::FAILURE })
+        }
+        Some("diff") => {
+            let (Some(old), Some(new)) = (arg(2), arg(3)) else {
+                return Err(usage(&cli::synopsis("diff")));
+            };
+            let weeks = options.weeks(args.get(4), DEFAULT_EXPORT_WEEKS)?;
+            let new = get_config(new)?;
+            let changes = diff::diff(&get_config(old)?, &new, new.today(), weeks as usize);
+            print!("{}", diff::report(&options.filter_changes(changes)));
+            Ok(())
+        }
+        Some("changes") => {
+            let conf = get_config(config_path)?;
+            let today = conf.today();
+            let weeks = get_weeks(&conf, today, DEFAULT_EXPORT_WEEKS as usize);
+            let snapshot = snapshot::Snapshot::load(config_path)?;
+            snapshot.save(&weeks)?;
+            if snapshot.weeks.is_some() {
+                let changes = options.filter_changes(snapshot.changes(weeks));
+                print!("{}", diff::report(&changes));
+            } else {
+                println!("no snapshot yet, recorded the next {} weeks", weeks.len());
+            }
+            Ok(())
+        }
+        Some("roster") => match (arg(2), arg(3)) {
+            (Some(action @ ("add" | "remove")), Some(name)) => {
+                let keep = options.weeks(args.get(4), 4)?;
+                change_roster(config_path, action == "add", name, keep as usize)
+            }
+            _ => Err(usage(&cli::synopsis("roster"))),
+        },
+        Some("reschedule") => match (arg(2), arg(3), arg(4)) {
+            (Some("add"), Some(week), Some(caretaker)) => reschedule(config_path, week, caretaker),
+            _ => Err(usage(&cli::synopsis("reschedule"))),
+        },
+        Some("swap") => match (arg(2), arg(3)) {
+            (Some(first), Some(second)) => swap(config_path, first, second),
+            _ => Err(usage(&cli::synopsis("swap"))),
+        },
+        Some("swaps") => {
+            let me = options.caretaker.as_deref();
+            let week = |arg: Option<&str>| {
+                arg.and_then(swaps::parse)
+                    .ok_or_else(|| usage(&cli::synopsis("swaps")))
+            };
+            match (arg(2), arg(3)) {
+                (Some("list"), None) => {
+                    print!("{}", swaps::list(&get_config(config_path)?));
+                    Ok(())
+                }
+                (Some("propose"), first) => {
+                    swaps::propose(config_path, week(first)?, week(arg(4))?, me)
+                }
+                (Some(decision @ ("approve" | "decline")), first) => {
+                    let approve = decision == "approve";
+                    println!("{}", swaps::decide(config_path, week(first)?, approve, me)?);
+                    Ok(())
+                }
+                _ => Err(usage(&cli::synopsis("swaps"))),
+            }
+        }
+        Some("edit") => edit(config_path),
+        Some("mangen") => cli::mangen(arg(2).unwrap_or("man"), chrono::Local::now().date_naive()),
+        #[cfg(feature = "tui")]
+        Some("tui") => tui::run(config_path, today(config_path)),
+        Some("doctor") => {
+            let checks = doctor::checks(config_path, today(config_path));
+            print!("{}", doctor::report(&checks));
+            if checks.iter().any(|c| c.status == doctor::Status::Fail) {
+                std::process::exit(exit::FAILURE);
+            }
+            Ok(())
+        }
+        Some("prune") => prune(config_path, today(config_path)),
+        Some("credits") => {
+            let conf = get_config(config_path)?;
+            let today = conf.today();
+            let balances = credits::balances_until(&conf, config_path, today)?;
+            print!("{}", credits::report(&conf, &balances));
+            Ok(())
+        }
+        Some("fairness") => {
+            let conf = get_config(config_path)?;
+            let today = conf.today();
+            let (from, to) = match &args[2..] {
+                [] => (conf.startdate, today),
+                period => stats::parse_period(period, today)
+                    .ok_or_else(|| usage(&cli::synopsis("fairness")))?,
+            };
+            print!(
+                "{}",
+                stats::fairness_report(&conf, config_path, from, to, today)?
+            );
+            Ok(())
+        }
+        // Streamed, so even a century of weeks is never held in memory.
+        Some("export") if arg(2).is_some_and(|f| export::FORMATS.contains(&f)) => {
+            let conf = get_config(config_path)?;
+            let weeks = options.weeks(args.get(3), DEFAULT_EXPORT_WEEKS)? as usize;
+            let weeks = weeks_from(&conf, conf.today().max(conf.startdate))
+                .take(weeks)
+                .filter(|w| options.keeps(w));
+            let mut out = io::BufWriter::new(io::stdout().lock());
+            export::write(&mut out, arg(2).unwrap_or_default(), &conf, weeks)?;
+            out.flush()
+        }
+        Some("export-all") => {
+            let (Some(formats), Some(dir)) = (arg(2), arg(3)) else {
+                return Err(usage(&cli::synopsis("export-all")));
+            };
+            let formats: Vec<&str> = formats.split(',').collect();
+            if let Some(format) = formats.iter().find(|f| !export::FORMATS.contains(f)) {
+                return Err(usage(&format!(
+                    "unknown format {format}, expected {}",
+                    export::FORMATS.join(", ")
+                )));
+            }
+            let weeks = options.weeks(args.get(4), DEFAULT_EXPORT_WEEKS)? as usize;
+            let profiles = profiles()?;
+            if profiles.is_empty() {
+                return Err(io::Error::other("no profiles to export"));
+            }
+            export::export_all(&profiles, &formats, std::path::Path::new(dir), weeks)
+                .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e))
+        }
+        Some(command @ ("export" | "publish")) => {
+            let conf = get_config(config_path)?;
+            #[cfg_attr(
+                not(any(feature = "sync", feature = "notifications")),
+                allow(unused_variables)
+            )]
+            let weeks = get_next_weeks(&conf, options.weeks(args.get(3), DEFAULT_EXPORT_WEEKS)?);
+            match (command, arg(2)) {
+                #[cfg(feature = "sync")]
+                ("export", Some("pagerduty")) => {
+                    let export = pagerduty::export(&conf, &weeks)?;
+                    println!("{}", serde_json::to_string_pretty(&export)?);
+                    Ok(())
+                }
+                // Publishing only part of the schedule would delete the
+                // other caretakers' events from the remote calendar.
+                ("publish", _) if options.caretaker.is_some() => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--caretaker is not supported when publishing",
+                )),
+                #[cfg(feature = "sync")]
+                ("publish", Some("caldav")) => caldav::publish(&conf, config_path, &weeks)
+                    .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e)),
+                #[cfg(feature = "sync")]
+                ("publish", Some("outlook")) => outlook::publish(&conf, config_path, &weeks)
+                    .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e)),
+                #[cfg(feature = "sync")]
+                ("publish", Some("pagerduty")) => pagerduty::publish(&conf, &weeks)
+                    .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e)),
+                #[cfg(feature = "notifications")]
+                ("publish", Some("mqtt")) => publish_mqtt(&conf, &weeks)
+                    .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e)),
+                #[cfg(feature = "sync")]
+                ("publish", Some("github")) => {
+                    github::publish(&conf.github, conf.name.as_deref(), &conf.formatting, &weeks)
+                        .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e))
+                }
+                #[cfg(feature = "sync")]
+                ("publish", Some("opsgenie")) => opsgenie::publish(&conf, &weeks)
+                    .map_err(|e| exit::with_code(exit::PUBLISH_FAILED, e)),
+                #[cfg(not(feature = "sync"))]
+                ("export", Some("pagerduty"))
+                | ("publish", Some("caldav" | "outlook" | "pagerduty" | "github" | "opsgenie")) => {
+                    Err(disabled("sync"))
+                }
+                #[cfg(not(feature = "notifications"))]
+                ("publish", Some("mqtt")) => Err(disabled("notifications")),
+                ("export", _) => Err(usage(&cli::synopsis("export"))),
+                _ => Err(usage(&cli::synopsis("publish"))),
+            }
+        }
+        _ if args.len() > 2 => Err(usage(&format!(
+            "unknown command \"{}\", see whocares --help",
+            args[1]
+        ))),
+        _ => {
+            // Checked with --weeks too, so a mistyped command isn't ignored.
+            let weeks_arg = parse_weeks(args.get(1), 4)?;
+            let weeks_to_preview = options.weeks.unwrap_or(weeks_arg);
+            let conf = get_config(config_path)?;
             let weeks = get_next_weeks(&conf, weeks_to_preview);
-            for week in weeks {
+            if let (None, Some(gap)) = (&options.format, weeks_until_start(&conf, conf.today())) {
                 println!(
-                    "week #{} {} - {}: {}",
-                    week.week, week.start_date, week.end_date, week.caretaker
+                    "{}",
+                    i18n::starts_line(&conf.formatting, conf.startdate, gap)
                 );
             }
+            // With --caretaker the others having 0 goes without saying.
+            let everyone: Vec<&str> = match &options.caretaker {
+                Some(_) => Vec::new(),
+                None => conf.caretakers.iter().map(String::as_str).collect(),
+            };
+            print_weeks(&conf, options, &everyone, &options.filter(weeks));
+            Ok(())
         }
-        _ => panic!("Failed to open file"),
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+
+    let options = Options::parse(&mut args).and_then(|mut options| {
+        logging::init(options.verbosity);
+        options.resolve_me()?;
+        Ok(options)
+    });
+    let result = options.and_then(|options| match options.watch {
+        true if !watch::supported(args.get(1).map(String::as_str)) => Err(usage(
+            "--watch only works with commands that display the schedule",
+        )),
+        true => watch::run(&options.config_path, || run(&args, &options)),
+        false => run(&args, &options),
+    });
+    if let Err(e) = result {
+        eprintln!("whocares: {e}");
+        std::process::exit(exit::code(&e));
     }
 }
 
@@ -132,6 +1616,34 @@ mod tests {
         assert!(config.startdate == NaiveDate::from_str("2024-05-27").unwrap());
     }
 
+    #[test]
+    fn config_error_points_at_the_key() {
+        let json = "{\n    \"startdate\": \"2024-05-27\",\n    \"caretakers\": \"Edgar\",\n}";
+        let e = serde_json::from_str::<Config>(json).err().unwrap();
+        assert_eq!(
+            config_error("config.json", json, &e),
+            "invalid config config.json:3:25: invalid type: string \"Edgar\", expected a sequence (in \"caretakers\")\n  |\n3 |     \"caretakers\": \"Edgar\",\n  |                         ^"
+        );
+    }
+
+    #[test]
+    fn validate_caretakers_works() {
+        let e = validate_caretakers(&[], PATH).unwrap_err();
+        assert_eq!(exit::code(&e), exit::NO_CARETAKER);
+        let e = validate_caretakers(&["A".to_string(), " ".to_string()], PATH).unwrap_err();
+        assert_eq!(exit::code(&e), exit::CONFIG_INVALID);
+        assert!(validate_caretakers(&["A".to_string()], PATH).is_ok());
+        let e = validate_caretakers(&["A".to_string(), "A".to_string()], PATH).unwrap_err();
+        assert_eq!(exit::code(&e), exit::CONFIG_INVALID);
+
+        let caretakers = ["bob ", "Alice", "Bob", "Mary  Ann", "mary ann"].map(String::from);
+        assert_eq!(
+            near_duplicates(&caretakers),
+            vec![("bob ", "Bob"), ("Mary  Ann", "mary ann")]
+        );
+        assert!(validate_caretakers(&caretakers, PATH).is_ok());
+    }
+
     #[test]
     fn get_current_caretaker_works() {
         let config = get_config(PATH).unwrap();
@@ -139,6 +1651,55 @@ mod tests {
         assert!(config.caretakers.contains(&current_caretaker));
     }
 
+    #[test]
+    fn get_next_weeks_before_the_start_works() {
+        let today = tz::Zone::default().today();
+        let config = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: today + chrono::Days::new(15),
+            ..Default::default()
+        };
+        let weeks = get_next_weeks(&config, 2);
+        assert_eq!(weeks[0].start_date, start_of_week(config.startdate));
+        assert_eq!(&*weeks[0].caretaker, "A");
+        assert_eq!(&*weeks[1].caretaker, "B");
+        #[cfg(any(feature = "notifications", feature = "server"))]
+        assert!(current_week(&config).is_none());
+        assert!(matches!(weeks_until_start(&config, today), Some(2 | 3)));
+        assert_eq!(weeks_until_start(&config, config.startdate), None);
+    }
+
+    #[test]
+    fn large_ranges_work() {
+        let config = get_config(PATH).unwrap();
+        let weeks = get_weeks(&config, NaiveDate::MAX - chrono::Days::new(20), 10);
+        assert!((1..10).contains(&weeks.len()));
+        assert!(parse_weeks(Some(&MAX_WEEKS.to_string()), 4).is_ok());
+        for weeks in ["1000000", "0", "-3", "exprot"] {
+            let e = parse_weeks(Some(&weeks.to_string()), 4).unwrap_err();
+            assert_eq!(exit::code(&e), exit::USAGE);
+        }
+
+        let mut args: Vec<String> = ["whocares", "--weeks", "8", "forecast"]
+            .map(String::from)
+            .to_vec();
+        let options = Options::parse(&mut args).unwrap();
+        assert_eq!(args, ["whocares", "forecast"]);
+        assert_eq!(options.weeks(None, 4).unwrap(), 8);
+        let mut args: Vec<String> = ["whocares", "--weeks", "eight"].map(String::from).to_vec();
+        assert!(Options::parse(&mut args).is_err());
+
+        for typo in [
+            &["whocares", "exprot", "ics"][..],
+            &["whocares", "--weeks", "8", "exprot"],
+        ] {
+            let mut args: Vec<String> = typo.iter().map(|a| a.to_string()).collect();
+            let options = Options::parse(&mut args).unwrap();
+            let e = run(&args, &options).unwrap_err();
+            assert_eq!(exit::code(&e), exit::USAGE, "{typo:?}");
+        }
+    }
+
     #[test]
     fn get_next_weeks_across_years_works() {
         let config = get_config(PATH).unwrap();
@@ -148,8 +1709,9 @@ mod tests {
 
     #[test]
     fn reschedule_works() {
-        let current_week = chrono::Local::now().date_naive().iso_week().week();
-        let current_year = chrono::Local::now().date_naive().year_ce().1;
+        let today = tz::Zone::default().today();
+        let current_week = today.iso_week().week();
+        let current_year = today.year_ce().1;
         let config = Config {
             caretakers: vec!["A".to_string(), "B".to_string(), "C".to_string()],
             startdate: NaiveDate::from_str("2024-01-01").unwrap(),
@@ -167,12 +1729,256 @@ mod tests {
                     "A".to_string(),
                 ),
             ]),
+            ..Default::default()
         };
 
         let weeks = get_next_weeks(&config, 3);
         assert!(weeks.len() == 3);
-        assert!(weeks[0].caretaker == "C");
-        assert!(weeks[1].caretaker == "B");
-        assert!(weeks[2].caretaker == "A");
+        assert!(&*weeks[0].caretaker == "C");
+        assert!(&*weeks[1].caretaker == "B");
+        assert!(&*weeks[2].caretaker == "A");
+    }
+
+    #[test]
+    fn substitute_owing_most_credits() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let config = Config {
+            caretakers: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            startdate: start,
+            unavailable: availability::Unavailability::from([(
+                "A".to_string(),
+                vec![(start, start + chrono::Days::new(7))],
+            )]),
+            credits: credits::CreditConfig {
+                prefer_owing: true,
+                ..Default::default()
+            },
+            balances: credits::Balances::from([("B".to_string(), 1.0), ("C".to_string(), -2.0)]),
+            ..Default::default()
+        };
+
+        assert!(&*get_week(&config, start).caretaker == "C");
+    }
+
+    #[cfg(feature = "notifications")]
+    #[test]
+    fn reminder_hours_stay_within_the_week() {
+        let dir = std::env::temp_dir().join(format!("whocares-reminder-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        let path = path.to_str().unwrap();
+        let config = |hours: u32| {
+            format!(
+                r#"{{"startdate": "2024-07-01", "caretakers": ["A"], "reschedule": {{}}, "notifications": {{"reminder_hours": {hours}}}}}"#
+            )
+        };
+        fs::write(path, config(167)).unwrap();
+        assert!(get_config(path).is_ok());
+        fs::write(path, config(168)).unwrap();
+        let Err(e) = get_config(path) else {
+            panic!("168 hours are accepted");
+        };
+        assert_eq!(exit::code(&e), exit::CONFIG_INVALID);
+        assert!(e.to_string().contains("at most 167 hours"), "{e}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_reschedules_works() {
+        let reschedule = |key: &str| HashMap::from([(key.to_string(), "A".to_string())]);
+        assert!(validate_reschedules(&reschedule("2025-W07"), PATH).is_ok());
+        assert!(validate_reschedules(&reschedule("2025-7"), PATH).is_ok());
+        for key in ["2025-W7", "25-7", "2025/07"] {
+            let e = validate_reschedules(&reschedule(key), PATH).unwrap_err();
+            assert_eq!(exit::code(&e), exit::CONFIG_INVALID);
+            assert!(e.to_string().ends_with("did you mean 2025-W07?"), "{e}");
+        }
+        let e = validate_reschedules(&reschedule("next week"), PATH).unwrap_err();
+        assert!(e.to_string().ends_with("like 2025-W07"));
+
+        let split = HashMap::from([("2025-W07".to_string(), "A/".to_string())]);
+        let e = validate_reschedules(&split, PATH).unwrap_err();
+        assert_eq!(exit::code(&e), exit::CONFIG_INVALID);
+    }
+
+    #[test]
+    fn year_boundary_works() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let config = Config {
+            caretakers: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            startdate: date("2024-12-16"),
+            reschedule: HashMap::from([
+                // the first week of 2024, not 2025-W01
+                ("2024-1".to_string(), "B".to_string()),
+                ("2025-W02".to_string(), "C".to_string()),
+                ("2026-53".to_string(), "C".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let caretakers = |from, n| -> Vec<(u32, String)> {
+            get_weeks(&config, date(from), n)
+                .into_iter()
+                .map(|w| (w.week, w.caretaker.to_string()))
+                .collect()
+        };
+        let expected = |weeks: &[(u32, &str)]| -> Vec<(u32, String)> {
+            weeks.iter().map(|(w, c)| (*w, c.to_string())).collect()
+        };
+        assert_eq!(
+            caretakers("2024-12-23", 3),
+            expected(&[(52, "B"), (1, "C"), (2, "C")])
+        );
+        // 2026 has 53 weeks, the rotation carries on through all of them
+        assert_eq!(
+            caretakers("2026-12-21", 3),
+            expected(&[(52, "A"), (53, "C"), (1, "C")])
+        );
+        assert_eq!(iso_week_key(date("2026-12-28")), "2026-W53");
+        assert_eq!(parse_week("2026-W53"), Some(date("2026-12-28")));
+
+        let mut map = serde_json::Map::new();
+        map.insert("2026-53".to_string(), "C".into());
+        map.insert("2024-1".to_string(), "B".into());
+        set_reschedule(&mut map, Week::containing(date("2026-12-31")), "B");
+        set_reschedule(&mut map, Week::new(2025, 1).unwrap(), "A");
+        assert_eq!(
+            serde_json::Value::Object(map),
+            serde_json::json!({ "2024-1": "B", "2025-W01": "A", "2026-W53": "B" })
+        );
+    }
+
+    #[test]
+    fn on_duty_follows_the_days() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let config = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: date("2024-01-08"),
+            reschedule: HashMap::from([("2024-W03".to_string(), "A/B".to_string())]),
+            blackouts: serde_json::from_str(
+                r#"[{"name": "closed", "start": "2024-01-22", "end": "2024-01-28"}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        assert!(on_duty(&config, date("2024-01-04")).is_empty());
+        assert_eq!(on_duty(&config, date("2024-01-08")), ["A"]);
+        // A until Wednesday, B from Thursday.
+        assert_eq!(on_duty(&config, date("2024-01-16")), ["A"]);
+        assert_eq!(on_duty(&config, date("2024-01-19")), ["B"]);
+        assert!(on_duty(&config, date("2024-01-23")).is_empty());
+
+        let per_weekday = Config {
+            per_weekday: true,
+            reschedule: HashMap::new(),
+            blackouts: Vec::new(),
+            ..config
+        };
+        assert_eq!(on_duty(&per_weekday, date("2024-01-08")), ["A"]);
+        assert_eq!(on_duty(&per_weekday, date("2024-01-09")), ["B"]);
+
+        let shifts = Config {
+            per_weekday: false,
+            shifts: serde_json::from_str(
+                r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00", "offset": 1}]"#,
+            )
+            .unwrap(),
+            ..per_weekday
+        };
+        assert_eq!(on_duty(&shifts, date("2024-01-08")), ["A", "B"]);
+    }
+
+    #[test]
+    fn caretaker_option_works() {
+        let mut args: Vec<String> = ["whocares", "--caretaker", "B", "12"]
+            .map(String::from)
+            .to_vec();
+        let options = Options::parse(&mut args).unwrap();
+        assert_eq!(args, ["whocares", "12"]);
+
+        let config = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: NaiveDate::from_str("2024-01-01").unwrap(),
+            ..Default::default()
+        };
+        let weeks = options.filter(get_next_weeks(&config, 12));
+        assert_eq!(weeks.len(), 6);
+        assert!(weeks.iter().all(|w| &*w.caretaker == "B"));
+
+        // A takes the first days of the split week, B the rest.
+        let config = Config {
+            reschedule: HashMap::from([("2024-W01".to_string(), "A/B".to_string())]),
+            ..config
+        };
+        let weeks = options.filter(get_weeks(&config, config.startdate, 4));
+        let starts: Vec<String> = weeks.iter().map(|w| w.start_date.to_string()).collect();
+        assert_eq!(starts, ["2024-01-01", "2024-01-08", "2024-01-22"]);
+
+        let mut args: Vec<String> = ["whocares", "--caretaker"].map(String::from).to_vec();
+        assert!(Options::parse(&mut args).is_err());
+    }
+
+    #[test]
+    fn profile_path_works() {
+        let dir = std::path::Path::new("/home/dawe/.config/whocares/profiles");
+        assert_eq!(
+            profile_path(dir, "work").unwrap(),
+            "/home/dawe/.config/whocares/profiles/work.json"
+        );
+        assert!(profile_path(dir, "../work").is_err());
+        assert!(profile_path(dir, "").is_err());
+    }
+
+    #[test]
+    fn format_week_works() {
+        let week = CareWeek {
+            week: 31,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2025-07-28").unwrap(),
+            end_date: NaiveDate::from_str("2025-08-03").unwrap(),
+            ..Default::default()
+        };
+        let formatting = i18n::Formatting::default();
+        assert_eq!(
+            format_week("{week}\\t{caretaker}", &formatting, &week),
+            "31\tEdgar"
+        );
+        assert_eq!(
+            format_week("{iso_week} {start}..{end} {nope}", &formatting, &week),
+            "2025-W31 2025-07-28..2025-08-03 {nope}"
+        );
+    }
+
+    #[test]
+    fn me_works() {
+        let config = Config {
+            caretakers: vec!["dawe".to_string(), "Edgar".to_string()],
+            users: HashMap::from([("dawedawe".to_string(), "dawe".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(me(&config, "dawedawe").unwrap(), "dawe");
+        assert_eq!(me(&config, "Edgar").unwrap(), "Edgar");
+        assert!(me(&config, "root").is_err());
+    }
+
+    #[test]
+    fn unavailable_caretaker_is_substituted() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let config = Config {
+            caretakers: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            startdate: start,
+            unavailable: availability::Unavailability::from([
+                ("A".to_string(), vec![(start, start + chrono::Days::new(7))]),
+                (
+                    "B".to_string(),
+                    vec![(start, start + chrono::Days::new(14))],
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        assert!(&*get_week(&config, start).caretaker == "C");
+        assert!(&*get_week(&config, start + chrono::Days::new(7)).caretaker == "C");
+        assert!(&*get_week(&config, start + chrono::Days::new(14)).caretaker == "C");
+        assert!(&*get_week(&config, start + chrono::Days::new(21)).caretaker == "A");
     }
 }
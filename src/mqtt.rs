@@ -0,0 +1,234 @@
+use crate::notify::Event;
+use crate::CareWeek;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const PASSWORD_ENV: &str = "WHOCARES_MQTT_PASSWORD";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const KEEP_ALIVE_SECS: u16 = 60;
+
+#[derive(Deserialize)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_topic")]
+    topic: String,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    // Home Assistant picks up the sensors from "<prefix>/sensor/..."
+    #[serde(default)]
+    discovery_prefix: Option<String>,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_topic() -> String {
+    "whocares".to_string()
+}
+
+fn remaining_length(mut len: usize) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        encoded.push(byte);
+        if len == 0 {
+            return encoded;
+        }
+    }
+}
+
+fn string(s: &str) -> Vec<u8> {
+    let mut encoded = (s.len() as u16).to_be_bytes().to_vec();
+    encoded.extend_from_slice(s.as_bytes());
+    encoded
+}
+
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![kind];
+    packet.extend(remaining_length(body.len()));
+    packet.extend_from_slice(body);
+    packet
+}
+
+pub fn connect_packet(client_id: &str, username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut flags = 0x02; // clean session
+    if username.is_some() {
+        flags |= 0x80;
+    }
+    if password.is_some() {
+        flags |= 0x40;
+    }
+    let mut body = string("MQTT");
+    body.push(4); // 3.1.1
+    body.push(flags);
+    body.extend(KEEP_ALIVE_SECS.to_be_bytes());
+    body.extend(string(client_id));
+    for field in [username, password].into_iter().flatten() {
+        body.extend(string(field));
+    }
+    packet(0x10, &body)
+}
+
+// QoS 0 and retained, so subscribers that connect later still get the
+// current state.
+pub fn publish_packet(topic: &str, payload: &str) -> Vec<u8> {
+    let mut body = string(topic);
+    body.extend_from_slice(payload.as_bytes());
+    packet(0x31, &body)
+}
+
+impl MqttConfig {
+    fn password(&self) -> Option<String> {
+        env::var(PASSWORD_ENV)
+            .ok()
+            .or_else(|| self.password.clone())
+    }
+
+    // The messages for a week, as (topic, payload).
+    pub fn messages(
+        &self,
+        event: Event,
+        week: &CareWeek,
+        today: NaiveDate,
+    ) -> Vec<(String, String)> {
+        let topic = self.topic.trim_end_matches('/');
+        let mut messages = Vec::new();
+        if let Some(prefix) = &self.discovery_prefix {
+            let node = topic.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+            for (sensor, name, unit) in [
+                ("caretaker", "Caretaker", None),
+                ("next_caretaker", "Next caretaker", None),
+                ("days_remaining", "Days remaining", Some("d")),
+            ] {
+                let mut config = serde_json::json!({
+                    "name": name,
+                    "state_topic": format!("{topic}/{sensor}"),
+                    "unique_id": format!("{node}_{sensor}"),
+                    "device": { "identifiers": [node], "name": topic },
+                });
+                if let Some(unit) = unit {
+                    config["unit_of_measurement"] = unit.into();
+                }
+                messages.push((
+                    format!(
+                        "{}/sensor/{node}/{sensor}/config",
+                        prefix.trim_end_matches('/')
+                    ),
+                    config.to_string(),
+                ));
+            }
+        }
+        // A suspended week says so instead of naming its caretaker.
+        let caretaker = match week.suspended {
+            Some(_) => week.caretakers(),
+            None => week.caretaker.to_string(),
+        };
+        match event {
+            Event::Handoff => {
+                let days = (week.end_date - today).num_days() + 1;
+                messages.push((format!("{topic}/caretaker"), caretaker));
+                messages.push((format!("{topic}/days_remaining"), days.max(0).to_string()));
+            }
+            Event::Reminder => messages.push((format!("{topic}/next_caretaker"), caretaker)),
+        }
+        messages
+    }
+
+    pub fn send(&self, messages: &[(String, String)]) -> io::Result<()> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let client_id = format!("whocares-{}", std::process::id());
+        let password = self.password();
+        stream.write_all(&connect_packet(
+            &client_id,
+            self.username.as_deref(),
+            password.as_deref(),
+        ))?;
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::other(format!(
+                "mqtt broker {} refused the connection ({})",
+                self.host, connack[3]
+            )));
+        }
+        for (topic, payload) in messages {
+            stream.write_all(&publish_packet(topic, payload))?;
+        }
+        stream.write_all(&[0xe0, 0x00])?;
+        stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn packets_and_messages() {
+        assert_eq!(remaining_length(321), vec![0xc1, 0x02]);
+        assert_eq!(
+            publish_packet("a/b", "Edgar"),
+            b"\x31\x0a\x00\x03a/bEdgar".to_vec()
+        );
+        assert_eq!(
+            &connect_packet("id", Some("u"), None)[..12],
+            b"\x10\x11\x00\x04MQTT\x04\x82\x00\x3c"
+        );
+
+        let conf: MqttConfig = serde_json::from_str(
+            r#"{"host": "localhost", "topic": "home/trash", "discovery_prefix": "homeassistant"}"#,
+        )
+        .unwrap();
+        let week = CareWeek {
+            week: 27,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            ..Default::default()
+        };
+        let today = NaiveDate::from_str("2024-07-03").unwrap();
+        let messages = conf.messages(Event::Handoff, &week, today);
+        assert_eq!(
+            messages[0].0,
+            "homeassistant/sensor/home_trash/caretaker/config"
+        );
+        assert_eq!(
+            messages[3],
+            ("home/trash/caretaker".to_string(), "Edgar".to_string())
+        );
+        assert_eq!(
+            messages[4],
+            ("home/trash/days_remaining".to_string(), "5".to_string())
+        );
+
+        let suspended = CareWeek {
+            suspended: Some("Renovation".into()),
+            ..week
+        };
+        let messages = conf.messages(Event::Reminder, &suspended, today);
+        assert_eq!(
+            messages[3],
+            (
+                "home/trash/next_caretaker".to_string(),
+                "suspended (Renovation)".to_string()
+            )
+        );
+    }
+}
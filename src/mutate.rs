@@ -0,0 +1,236 @@
+use crate::{audit, dry_run, git};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BACKUPS: usize = 5;
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Backups sort by age since the timestamp is part of the name.
+fn backups(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let prefix = format!("{name}.");
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".bak"))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+fn backup(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let stamp = chrono::Local::now().format("%Y%m%dT%H%M%S%.9f");
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{stamp}.bak"));
+    fs::copy(path, backup)?;
+
+    let backups = backups(path)?;
+    for old in &backups[..backups.len().saturating_sub(BACKUPS)] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+// Writes next to the config and renames over it, so readers see either the
+// old or the new file but never a partial one.
+pub fn write_config(path: &str, config: &Value) -> io::Result<()> {
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    config.serialize(&mut serializer)?;
+    buf.push(b'\n');
+
+    let path = Path::new(path);
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    // Configs with secrets are often 0600, the new one must not be readable
+    // by more people. Set before anything is written to it.
+    if let Ok(metadata) = fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.write_all(&buf)?;
+    file.sync_all()?;
+    backup(path)?;
+    fs::rename(&tmp, path)
+}
+
+// Taken on a file next to the config, the config itself is replaced on
+// every write. Held until dropped.
+fn lock(path: &str) -> io::Result<File> {
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(format!("{path}.lock"))?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) if start.elapsed() < LOCK_TIMEOUT => {
+                thread::sleep(Duration::from_millis(100))
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::other(format!(
+                    "{path} is being changed by another whocares, try again"
+                )))
+            }
+            // Some network filesystems have no locks, the check before
+            // writing still catches conflicts there.
+            Err(TryLockError::Error(e)) if e.kind() == io::ErrorKind::Unsupported => {
+                return Ok(file)
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+    }
+}
+
+// Applies a change to the raw JSON of the config file, so settings this
+// version doesn't know about are preserved, records it in the audit log and
+// commits it if git auto_commit is enabled.
+pub fn update_config<F>(path: &str, action: &str, change: F) -> io::Result<()>
+where
+    F: FnOnce(&mut Value) -> io::Result<()>,
+{
+    let _lock = match dry_run::enabled() {
+        true => None,
+        false => Some(lock(path)?),
+    };
+    let text = fs::read_to_string(path)?;
+    let before: Value = serde_json::from_str(&text)?;
+    let mut config = before.clone();
+    change(&mut config)?;
+    if config == before {
+        return Ok(());
+    }
+    if dry_run::enabled() {
+        println!("{}would write {path}:", dry_run::prefix());
+        for change in audit::changes(&before, &config) {
+            println!(
+                "  {}: {} -> {}",
+                change["key"].as_str().unwrap_or_default(),
+                change["old"],
+                change["new"]
+            );
+        }
+        return Ok(());
+    }
+    // Sync tools and editors don't take the lock.
+    if fs::read_to_string(path)? != text {
+        return Err(io::Error::other(format!(
+            "{path} changed while applying {action}, nothing was written, try again"
+        )));
+    }
+    write_config(path, &config)?;
+    audit::append(path, action, &before, &config)?;
+
+    let git: git::GitConfig = serde_json::from_value(config["git"].clone()).unwrap_or_default();
+    if git.auto_commit {
+        git::commit(path, &format!("whocares: {action}"))?;
+    }
+    Ok(())
+}
+
+pub fn reschedule_map(config: &mut Value) -> io::Result<&mut serde_json::Map<String, Value>> {
+    object(config, "reschedule")
+}
+
+// The object under key, created if missing.
+pub fn object<'a>(
+    config: &'a mut Value,
+    key: &str,
+) -> io::Result<&'a mut serde_json::Map<String, Value>> {
+    let Some(object) = config.as_object_mut() else {
+        return Err(io::Error::other("config is not a JSON object"));
+    };
+    object
+        .entry(key)
+        .or_insert_with(|| Value::Object(Default::default()))
+        .as_object_mut()
+        .ok_or_else(|| io::Error::other(format!("{key} is not a JSON object")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_config_keeps_backups() {
+        let dir = std::env::temp_dir().join(format!("whocares-mutate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        let path = path.to_str().unwrap();
+
+        for i in 0..BACKUPS + 2 {
+            write_config(path, &serde_json::json!({ "version": i })).unwrap();
+        }
+        let config: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(config["version"], BACKUPS + 1);
+        assert_eq!(backups(Path::new(path)).unwrap().len(), BACKUPS);
+        assert!(!Path::new(path).with_extension("json.tmp").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_config_keeps_the_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("whocares-mode-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_config(path.to_str().unwrap(), &serde_json::json!({ "version": 1 })).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn update_config_detects_concurrent_writes() {
+        let dir = std::env::temp_dir().join(format!("whocares-conflict-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        let path = path.to_str().unwrap();
+        fs::write(path, r#"{"version": 1}"#).unwrap();
+
+        let e = update_config(path, "test", |config| {
+            fs::write(path, r#"{"version": 2}"#)?;
+            config["version"] = 3.into();
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(e.to_string().contains("changed while applying test"));
+        assert_eq!(fs::read_to_string(path).unwrap(), r#"{"version": 2}"#);
+
+        // the lock is released again
+        let lock = lock(path).unwrap();
+        drop(lock);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reschedule_map_is_created() {
+        let mut config = serde_json::json!({ "caretakers": ["A"] });
+        reschedule_map(&mut config)
+            .unwrap()
+            .insert("2024-27".to_string(), Value::from("A"));
+        assert_eq!(config["reschedule"]["2024-27"], "A");
+        assert_eq!(config["caretakers"][0], "A");
+    }
+}
@@ -0,0 +1,360 @@
+use crate::contacts::Contacts;
+use crate::dry_run;
+use crate::i18n::{self, Formatting};
+use crate::logging::{error, info};
+use crate::{http, irc, mqtt, signal, sns, twilio, CareWeek};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The reminder is about the coming week, so it has to be sent after the
+// handoff to the current one.
+pub const MAX_REMINDER_HOURS: u32 = 7 * 24 - 1;
+
+#[derive(Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default = "default_reminder_hours")]
+    pub reminder_hours: u32,
+    // How long a command or webhook may take, the other channels have
+    // their own timeouts.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        NotificationConfig {
+            reminder_hours: default_reminder_hours(),
+            timeout_secs: default_timeout_secs(),
+            channels: Vec::new(),
+        }
+    }
+}
+
+fn default_reminder_hours() -> u32 {
+    24
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Channel {
+    Command { command: String },
+    Webhook { url: String },
+    Mqtt(mqtt::MqttConfig),
+    Irc(irc::IrcConfig),
+    Signal(signal::SignalConfig),
+    Twilio(twilio::TwilioConfig),
+    Sns(sns::SnsConfig),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Event {
+    Handoff,
+    Reminder,
+}
+
+impl Channel {
+    fn kind(&self) -> &'static str {
+        match self {
+            Channel::Command { .. } => "command",
+            Channel::Webhook { .. } => "webhook",
+            Channel::Mqtt(_) => "mqtt",
+            Channel::Irc(_) => "irc",
+            Channel::Signal(_) => "signal",
+            Channel::Twilio(_) => "twilio",
+            Channel::Sns(_) => "sns",
+        }
+    }
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Handoff => "handoff",
+            Event::Reminder => "reminder",
+        }
+    }
+}
+
+pub fn text(formatting: &Formatting, event: Event, week: &CareWeek) -> String {
+    let message = match &week.suspended {
+        Some(blackout) => i18n::format(
+            formatting.locale,
+            &format!("suspended-{}", event.name()),
+            &[("blackout", blackout)],
+        ),
+        None => {
+            let caretakers = formatting.caretakers(week);
+            i18n::format(
+                formatting.locale,
+                event.name(),
+                &[("caretaker", &caretakers)],
+            )
+        }
+    };
+    let (first, last) = week.period();
+    format!(
+        "{message} (#{} {} - {}){}{}",
+        week.week,
+        formatting.date(first),
+        formatting.date(last),
+        week.overlap_note(),
+        week.holiday_note()
+    )
+}
+
+pub fn payload(formatting: &Formatting, event: Event, week: &CareWeek) -> serde_json::Value {
+    serde_json::json!({
+        "event": event.name(),
+        "caretaker": week.caretaker,
+        "suspended": week.suspended,
+        "week": week.week,
+        "start_date": week.start_date.to_string(),
+        "end_date": week.end_date.to_string(),
+        "holidays": week.holidays_json(),
+        "text": text(formatting, event, week),
+    })
+}
+
+// Kills the command once the timeout is up.
+fn run_with_stdin(mut command: Command, input: &str, timeout: Duration) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let status = loop {
+        match child.try_wait()? {
+            Some(status) => break status,
+            None if Instant::now() >= deadline => {
+                child.kill()?;
+                child.wait()?;
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{command:?} timed out after {}s", timeout.as_secs()),
+                ));
+            }
+            None => thread::sleep(Duration::from_millis(50)),
+        }
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{command:?} exited with {status}"
+        )))
+    }
+}
+
+impl Channel {
+    pub fn send(
+        &self,
+        formatting: &Formatting,
+        contacts: &Contacts,
+        event: Event,
+        week: &CareWeek,
+        today: NaiveDate,
+        timeout: Duration,
+    ) -> io::Result<()> {
+        let payload = payload(formatting, event, week).to_string();
+        if dry_run::enabled() {
+            let message = match self {
+                Channel::Command { command } => format!("run {command} with {payload}"),
+                Channel::Webhook { url } => format!("POST {payload} to {url}"),
+                Channel::Mqtt(mqtt) => {
+                    let messages: Vec<String> = mqtt
+                        .messages(event, week, today)
+                        .into_iter()
+                        .map(|(topic, payload)| format!("{topic}={payload}"))
+                        .collect();
+                    format!("publish {} to {}", messages.join(", "), mqtt.host)
+                }
+                Channel::Irc(irc) => format!(
+                    "say {:?} in {} on {}",
+                    text(formatting, event, week),
+                    irc.channel,
+                    irc.server
+                ),
+                Channel::Signal(signal) => format!(
+                    "send {:?} via signal-cli at {}",
+                    text(formatting, event, week),
+                    signal.url
+                ),
+                Channel::Twilio(twilio) => format!(
+                    "text {:?} to {}",
+                    text(formatting, event, week),
+                    twilio.recipient(contacts, week)?
+                ),
+                Channel::Sns(sns) => format!("publish {payload} to {}", sns.topic_arn),
+            };
+            println!("{}would {message}", dry_run::prefix());
+            return Ok(());
+        }
+        match self {
+            Channel::Command { command } => {
+                let mut cmd = Command::new("sh");
+                cmd.arg("-c")
+                    .arg(command)
+                    .env("WHOCARES_EVENT", event.name())
+                    .env("WHOCARES_CARETAKER", &*week.caretaker)
+                    .env("WHOCARES_TEXT", text(formatting, event, week));
+                run_with_stdin(cmd, &payload, timeout)
+            }
+            Channel::Webhook { url } => http::Request {
+                method: "POST",
+                url,
+                headers: vec![("Content-Type", "application/json".to_string())],
+                body: Some(&payload),
+                timeout: Some(timeout),
+                ..Default::default()
+            }
+            .send()?
+            .error_for_status()
+            .map(|_| ()),
+            Channel::Mqtt(mqtt) => mqtt.send(&mqtt.messages(event, week, today)),
+            Channel::Irc(irc) => irc.send(&text(formatting, event, week)),
+            Channel::Signal(signal) => signal.send(&text(formatting, event, week)),
+            Channel::Twilio(twilio) => twilio.send(contacts, week, &text(formatting, event, week)),
+            Channel::Sns(sns) => sns.send(event.name(), &payload),
+        }
+    }
+}
+
+// Channels are sent to at the same time, so a slow one only delays
+// itself. Dry runs go one by one to print in config order.
+pub fn send_all(
+    conf: &NotificationConfig,
+    formatting: &Formatting,
+    contacts: &Contacts,
+    event: Event,
+    week: &CareWeek,
+    today: NaiveDate,
+) -> io::Result<()> {
+    let timeout = Duration::from_secs(conf.timeout_secs);
+    let send = |channel: &Channel| channel.send(formatting, contacts, event, week, today, timeout);
+    let results: Vec<io::Result<()>> = if dry_run::enabled() {
+        conf.channels.iter().map(send).collect()
+    } else {
+        thread::scope(|scope| {
+            let handles: Vec<_> = conf
+                .channels
+                .iter()
+                .map(|channel| scope.spawn(move || send(channel)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(io::Error::other("sending panicked")))
+                })
+                .collect()
+        })
+    };
+
+    let mut failed = 0;
+    for (channel, result) in conf.channels.iter().zip(results) {
+        match result {
+            Ok(()) => info!("sent {} notification via {}", event.name(), channel.kind()),
+            Err(e) => {
+                error!(
+                    "{} notification via {} failed: {e}",
+                    event.name(),
+                    channel.kind()
+                );
+                failed += 1;
+            }
+        }
+    }
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{failed} of {} notifications failed",
+            conf.channels.len()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn payload_works() {
+        let week = CareWeek {
+            week: 27,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            ..Default::default()
+        };
+        let payload = payload(&Formatting::default(), Event::Reminder, &week);
+        assert_eq!(payload["event"], "reminder");
+        assert_eq!(payload["caretaker"], "Edgar");
+        assert_eq!(payload["start_date"], "2024-07-01");
+        assert_eq!(
+            payload["text"],
+            "Reminder: Edgar is taking care next week (#27 2024-07-01 - 2024-07-07)"
+        );
+        assert_eq!(
+            text(
+                &Formatting {
+                    locale: i18n::Locale::De,
+                    date_format: Some("%a %d.%m.".to_string()),
+                    ..Default::default()
+                },
+                Event::Handoff,
+                &week
+            ),
+            "Edgar kümmert sich diese Woche (#27 Mo 01.07. - So 07.07.)"
+        );
+    }
+
+    #[test]
+    fn channels_deserialize() {
+        let conf: NotificationConfig = serde_json::from_str(
+            r#"{"channels": [{"type": "command", "command": "true"}, {"type": "webhook", "url": "http://localhost"}, {"type": "mqtt", "host": "localhost"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(conf.reminder_hours, 24);
+        assert_eq!(conf.channels.len(), 3);
+    }
+
+    #[test]
+    fn send_all_is_concurrent_with_timeouts() {
+        let conf: NotificationConfig = serde_json::from_str(
+            r#"{"timeout_secs": 1, "channels": [
+                {"type": "command", "command": "sleep 0.5"},
+                {"type": "command", "command": "sleep 0.5"},
+                {"type": "command", "command": "sleep 5"}
+            ]}"#,
+        )
+        .unwrap();
+        let start = Instant::now();
+        let result = send_all(
+            &conf,
+            &Formatting::default(),
+            &Contacts::default(),
+            Event::Handoff,
+            &CareWeek::default(),
+            NaiveDate::default(),
+        );
+        assert!(start.elapsed() < Duration::from_secs(3));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "1 of 3 notifications failed"
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use crate::dry_run;
+use crate::logging::warning;
+use crate::{http, iso_week_key, regular_caretaker, start_of_week, CareWeek, Config};
+use chrono::Timelike;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use whocares_core::rotation::{shift_end, Shift};
+
+const API_KEY_ENV: &str = "WHOCARES_OPSGENIE_API_KEY";
+const ROTATION_NAME: &str = "whocares";
+const MAX_OVERRIDES: usize = 7;
+
+#[derive(Deserialize)]
+pub struct OpsgenieConfig {
+    // name of the schedule, it has to exist already
+    schedule: Option<String>,
+    #[serde(default)]
+    api_key: Option<String>,
+    // "https://api.eu.opsgenie.com" for the EU instance
+    #[serde(default = "default_api_url")]
+    api_url: String,
+    // caretaker name -> Opsgenie username (their email)
+    #[serde(default)]
+    users: HashMap<String, String>,
+}
+
+impl Default for OpsgenieConfig {
+    fn default() -> Self {
+        OpsgenieConfig {
+            schedule: None,
+            api_key: None,
+            api_url: default_api_url(),
+            users: HashMap::new(),
+        }
+    }
+}
+
+fn default_api_url() -> String {
+    "https://api.opsgenie.com".to_string()
+}
+
+impl OpsgenieConfig {
+    fn api_key(&self) -> io::Result<String> {
+        env::var(API_KEY_ENV)
+            .ok()
+            .or_else(|| self.api_key.clone())
+            .or_else(|| dry_run::enabled().then(String::new))
+            .ok_or_else(|| io::Error::other("no opsgenie api key configured"))
+    }
+
+    fn user(&self, caretaker: &str) -> io::Result<Value> {
+        let username = self.users.get(caretaker).ok_or_else(|| {
+            io::Error::other(format!(
+                "no opsgenie user for {caretaker} in opsgenie.users"
+            ))
+        })?;
+        Ok(serde_json::json!({ "type": "user", "username": username }))
+    }
+}
+
+fn time(date: chrono::NaiveDate) -> String {
+    format!("{date}T00:00:00Z")
+}
+
+pub fn rotations(config: &Config) -> io::Result<Vec<Value>> {
+    let conf = &config.opsgenie;
+    if !config.odd_week_caretakers.is_empty() {
+        return Err(io::Error::other(
+            "Opsgenie rotations can't alternate rosters, odd_week_caretakers aren't supported",
+        ));
+    }
+    if !config.blocks.is_empty() {
+        return Err(io::Error::other(
+            "Opsgenie rotations can't switch rosters by date, blocks aren't supported",
+        ));
+    }
+    if !config.blackouts.is_empty() {
+        return Err(io::Error::other(
+            "Opsgenie rotations can't pause, blackouts aren't supported",
+        ));
+    }
+    let rotation = |name: String, shift: Option<&Shift>| -> io::Result<Value> {
+        let participants = config
+            .turn_order(shift)?
+            .into_iter()
+            .map(|c| conf.user(c))
+            .collect::<io::Result<Vec<Value>>>()?;
+        Ok(serde_json::json!({
+            "name": name,
+            "startDate": time(start_of_week(config.startdate)),
+            "type": "weekly",
+            "length": 1,
+            "participants": participants,
+        }))
+    };
+    if config.shifts.is_empty() {
+        return Ok(vec![rotation(ROTATION_NAME.to_string(), None)?]);
+    }
+    // Shifts get a rotation each, restricted to their hours of the day.
+    config
+        .shifts
+        .iter()
+        .enumerate()
+        .map(|(i, shift)| {
+            let mut rotation = rotation(shift_rotation(shift), Some(shift))?;
+            let end = shift_end(&config.shifts, i);
+            rotation["timeRestriction"] = serde_json::json!({
+                "type": "time-of-day",
+                "restriction": {
+                    "startHour": shift.start.hour(),
+                    "startMin": shift.start.minute(),
+                    "endHour": end.hour(),
+                    "endMin": end.minute(),
+                },
+            });
+            Ok(rotation)
+        })
+        .collect()
+}
+
+fn shift_rotation(shift: &Shift) -> String {
+    format!("{ROTATION_NAME} {}", shift.name)
+}
+
+// The alias identifies an override of a week, so publishing again updates
+// it instead of adding another one. Later spans of the week count on.
+pub fn alias(week: &CareWeek, n: usize) -> String {
+    match n {
+        0 => format!("whocares-{}", iso_week_key(week.start_date)),
+        n => format!("whocares-{}-{}", iso_week_key(week.start_date), n + 1),
+    }
+}
+
+// An override for each span of the week someone other than the regular
+// caretaker is on duty. Suspended weeks have nobody to put on duty, so they
+// get none.
+pub fn overrides_for(config: &Config, week: &CareWeek) -> io::Result<Vec<Value>> {
+    if week.suspended.is_some() {
+        warning!(
+            "week #{} is suspended, Opsgenie keeps its rotation",
+            week.week
+        );
+        return Ok(Vec::new());
+    }
+    if !week.shifts.is_empty() {
+        return shift_overrides(config, week);
+    }
+    let regular = regular_caretaker(config, week.start_date);
+    let mut overrides = Vec::new();
+    for (caretaker, start, end) in week.spans() {
+        if caretaker == regular {
+            continue;
+        }
+        let end = end.succ_opt().unwrap_or(end);
+        overrides.push(serde_json::json!({
+            "alias": alias(week, overrides.len()),
+            "user": config.opsgenie.user(caretaker)?,
+            "startDate": time(start),
+            "endDate": time(end),
+        }));
+    }
+    Ok(overrides)
+}
+
+// A shift someone else has is overridden in its rotation only, for each
+// run of covered days.
+fn shift_overrides(config: &Config, week: &CareWeek) -> io::Result<Vec<Value>> {
+    let rotation = config.rotation();
+    let mut overrides = Vec::new();
+    for (i, on_shift) in week.shifts.iter().enumerate() {
+        // Weeks made before a config change can have other shifts.
+        let Some(shift) = config.shifts.get(i) else {
+            continue;
+        };
+        if *on_shift.caretaker == *rotation.regular_on_shift(week.start_date, shift) {
+            continue;
+        }
+        for (start, end) in week.coverage.runs(week.start_date, week.end_date) {
+            let end = end.succ_opt().unwrap_or(end);
+            overrides.push(serde_json::json!({
+                "alias": alias(week, overrides.len()),
+                "user": config.opsgenie.user(&on_shift.caretaker)?,
+                "startDate": time(start),
+                "endDate": time(end),
+                "rotations": [{ "name": shift_rotation(shift), "type": "rotation" }],
+            }));
+        }
+    }
+    Ok(overrides)
+}
+
+pub fn publish(config: &Config, weeks: &[CareWeek]) -> io::Result<()> {
+    let conf = &config.opsgenie;
+    let Some(schedule) = conf.schedule.as_deref() else {
+        return Err(io::Error::other("no opsgenie schedule configured"));
+    };
+    let schedule_url = format!(
+        "{}/v2/schedules/{}",
+        conf.api_url.trim_end_matches('/'),
+        http::url_encode(schedule)
+    );
+    let headers = vec![
+        ("Authorization", format!("GenieKey {}", conf.api_key()?)),
+        ("Content-Type", "application/json".to_string()),
+    ];
+    let by_name = "identifierType=name";
+
+    let existing: Vec<Value> = if dry_run::enabled() {
+        Vec::new()
+    } else {
+        let response = http::Request {
+            method: "GET",
+            url: &format!("{schedule_url}/rotations?{by_name}"),
+            headers: headers.clone(),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()?;
+        let rotations: Value = serde_json::from_str(&response.body)?;
+        rotations["data"].as_array().cloned().unwrap_or_default()
+    };
+    for rotation in rotations(config)? {
+        let name = rotation["name"].as_str().unwrap_or_default();
+        let id = existing
+            .iter()
+            .find(|r| r["name"] == name)
+            .and_then(|r| r["id"].as_str());
+        let (method, url) = match id {
+            Some(id) => ("PATCH", format!("{schedule_url}/rotations/{id}?{by_name}")),
+            None => ("POST", format!("{schedule_url}/rotations?{by_name}")),
+        };
+        http::Request {
+            method,
+            url: &url,
+            headers: headers.clone(),
+            body: Some(&rotation.to_string()),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()?;
+        println!("{}updated rotation {name}", dry_run::prefix());
+    }
+
+    for week in weeks {
+        let overrides = overrides_for(config, week)?;
+        for body in &overrides {
+            let alias = body["alias"].as_str().unwrap_or_default();
+            let override_url = format!("{schedule_url}/overrides/{alias}?{by_name}");
+            let body = body.to_string();
+            let mut request = http::Request {
+                method: "PUT",
+                url: &override_url,
+                headers: headers.clone(),
+                body: Some(&body),
+                ..Default::default()
+            };
+            let response = request.send()?;
+            if response.status == 404 {
+                let url = format!("{schedule_url}/overrides?{by_name}");
+                request.method = "POST";
+                request.url = &url;
+                request.send()?.error_for_status()?;
+            } else {
+                response.error_for_status()?;
+            }
+            println!("{}updated override {alias}", dry_run::prefix());
+        }
+        // Overrides of spans the week doesn't have anymore come next, up to
+        // one a day of each shift.
+        for n in overrides.len()..MAX_OVERRIDES * config.shifts.len().max(1) {
+            let alias = alias(week, n);
+            let response = http::Request {
+                method: "DELETE",
+                url: &format!("{schedule_url}/overrides/{alias}?{by_name}"),
+                headers: headers.clone(),
+                ..Default::default()
+            }
+            .send()?;
+            if response.status == 404 {
+                break;
+            }
+            response.error_for_status()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn rotation_and_overrides() {
+        let config = Config {
+            startdate: NaiveDate::from_str("2024-07-03").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            reschedule: HashMap::from([("2024-28".to_string(), "Edgar".to_string())]),
+            opsgenie: OpsgenieConfig {
+                users: HashMap::from([
+                    ("Edgar".to_string(), "edgar@example.com".to_string()),
+                    ("Jimmy".to_string(), "jimmy@example.com".to_string()),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let rotation = rotations(&config).unwrap().remove(0);
+        assert_eq!(rotation["startDate"], "2024-07-01T00:00:00Z");
+        assert_eq!(rotation["participants"][1]["username"], "jimmy@example.com");
+
+        let weeks = crate::get_weeks(&config, config.startdate, 2);
+        assert!(overrides_for(&config, &weeks[0]).unwrap().is_empty());
+        let rescheduled = overrides_for(&config, &weeks[1]).unwrap().remove(0);
+        assert_eq!(rescheduled["alias"], "whocares-2024-W28");
+        assert_eq!(rescheduled["user"]["username"], "edgar@example.com");
+        assert_eq!(rescheduled["endDate"], "2024-07-15T00:00:00Z");
+
+        let suspended = CareWeek {
+            suspended: Some("Renovation".into()),
+            ..weeks.into_iter().nth(1).unwrap()
+        };
+        assert!(overrides_for(&config, &suspended).unwrap().is_empty());
+
+        // Edgar until Wednesday of Jimmy's week.
+        let split = Config {
+            reschedule: HashMap::from([("2024-28".to_string(), "Edgar/Jimmy".to_string())]),
+            ..config
+        };
+        let weeks = crate::get_weeks(&split, split.startdate, 2);
+        let overrides = overrides_for(&split, &weeks[1]).unwrap();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0]["alias"], "whocares-2024-W28");
+        assert_eq!(overrides[0]["startDate"], "2024-07-08T00:00:00Z");
+        assert_eq!(overrides[0]["endDate"], "2024-07-11T00:00:00Z");
+        assert_eq!(alias(&weeks[1], 1), "whocares-2024-W28-2");
+
+        // Jimmy's days in Edgar's week.
+        let per_weekday = Config {
+            per_weekday: true,
+            reschedule: HashMap::new(),
+            ..split
+        };
+        let weeks = crate::get_weeks(&per_weekday, per_weekday.startdate, 1);
+        let overrides = overrides_for(&per_weekday, &weeks[0]).unwrap();
+        let aliases: Vec<&Value> = overrides.iter().map(|o| &o["alias"]).collect();
+        assert_eq!(
+            aliases,
+            [
+                "whocares-2024-W27",
+                "whocares-2024-W27-2",
+                "whocares-2024-W27-3"
+            ]
+        );
+        assert_eq!(overrides[1]["startDate"], "2024-07-04T00:00:00Z");
+        assert_eq!(overrides[1]["endDate"], "2024-07-05T00:00:00Z");
+        assert_eq!(overrides[1]["user"]["username"], "jimmy@example.com");
+    }
+
+    #[test]
+    fn shifts_get_rotations() {
+        let config = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            reschedule: HashMap::from([("2024-28".to_string(), "Edgar".to_string())]),
+            shifts: serde_json::from_str(
+                r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00", "offset": 1}]"#,
+            )
+            .unwrap(),
+            opsgenie: OpsgenieConfig {
+                users: HashMap::from([
+                    ("Edgar".to_string(), "edgar@example.com".to_string()),
+                    ("Jimmy".to_string(), "jimmy@example.com".to_string()),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let rotations = rotations(&config).unwrap();
+        assert_eq!(rotations[1]["name"], "whocares night");
+        assert_eq!(
+            rotations[1]["participants"][0]["username"],
+            "jimmy@example.com"
+        );
+        assert_eq!(
+            rotations[1]["timeRestriction"]["restriction"],
+            serde_json::json!({ "startHour": 20, "startMin": 0, "endHour": 8, "endMin": 0 })
+        );
+
+        // Edgar has Jimmy's days of the second week, the nights are his anyway.
+        let weeks = crate::get_weeks(&config, config.startdate, 2);
+        assert!(overrides_for(&config, &weeks[0]).unwrap().is_empty());
+        let overrides = overrides_for(&config, &weeks[1]).unwrap();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0]["user"]["username"], "edgar@example.com");
+        assert_eq!(overrides[0]["endDate"], "2024-07-15T00:00:00Z");
+        assert_eq!(overrides[0]["rotations"][0]["name"], "whocares day");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::dry_run;
+use crate::sync::{SyncState, SyncedEvent};
+use crate::{http, ics, CareWeek, Config};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+
+const GRAPH_URL: &str = "https://graph.microsoft.com/v1.0";
+const CLIENT_SECRET_ENV: &str = "WHOCARES_OUTLOOK_CLIENT_SECRET";
+const ACCESS_TOKEN_ENV: &str = "WHOCARES_OUTLOOK_TOKEN";
+const STATE_FILE: &str = "outlook-state.json";
+
+#[derive(Deserialize, Default)]
+pub struct OutlookConfig {
+    // e.g. "groups/<group-id>" or "users/<user-id>/calendars/<calendar-id>"
+    calendar: Option<String>,
+    #[serde(default)]
+    tenant_id: String,
+    #[serde(default)]
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+impl OutlookConfig {
+    fn access_token(&self) -> io::Result<String> {
+        if let Ok(token) = env::var(ACCESS_TOKEN_ENV) {
+            return Ok(token);
+        }
+        if dry_run::enabled() {
+            return Ok(String::new());
+        }
+
+        let secret = env::var(CLIENT_SECRET_ENV)
+            .ok()
+            .or_else(|| self.client_secret.clone())
+            .ok_or_else(|| io::Error::other("no outlook client secret configured"))?;
+        let form = format!(
+            "client_id={}&client_secret={}&scope={}&grant_type=client_credentials",
+            http::url_encode(&self.client_id),
+            http::url_encode(&secret),
+            http::url_encode("https://graph.microsoft.com/.default")
+        );
+        let response = http::Request {
+            method: "POST",
+            url: &format!(
+                "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+                self.tenant_id
+            ),
+            headers: vec![(
+                "Content-Type",
+                "application/x-www-form-urlencoded".to_string(),
+            )],
+            body: Some(&form),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()?;
+
+        let token: serde_json::Value = serde_json::from_str(&response.body)?;
+        token["access_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| io::Error::other("token response without access_token"))
+    }
+}
+
+// An all-day event for each caretaker's span of the week, with its UID.
+pub fn events(week: &CareWeek) -> Vec<(String, serde_json::Value)> {
+    ics::spans(week)
+        .into_iter()
+        .map(|(uid, caretaker, start, end)| {
+            let subject = match &week.suspended {
+                Some(blackout) => format!("Suspended: {blackout}"),
+                None => caretaker.to_string(),
+            };
+            let end = end.succ_opt().unwrap_or(end);
+            let event = serde_json::json!({
+                "subject": subject,
+                "body": {
+                    "contentType": "text",
+                    "content": format!("week #{}{}", week.week, week.holiday_note()),
+                },
+                "isAllDay": true,
+                "showAs": "free",
+                "start": { "dateTime": format!("{start}T00:00:00"), "timeZone": "UTC" },
+                "end": { "dateTime": format!("{end}T00:00:00"), "timeZone": "UTC" },
+                "transactionId": uid,
+            });
+            (uid, event)
+        })
+        .collect()
+}
+
+pub fn publish(config: &Config, config_path: &str, weeks: &[CareWeek]) -> io::Result<()> {
+    let conf = &config.outlook;
+    let Some(calendar) = conf.calendar.as_deref() else {
+        return Err(io::Error::other("no outlook calendar configured"));
+    };
+    let events_url = format!("{GRAPH_URL}/{}/events", calendar.trim_matches('/'));
+    let auth = vec![
+        ("Authorization", format!("Bearer {}", conf.access_token()?)),
+        ("Content-Type", "application/json".to_string()),
+    ];
+
+    let mut state = SyncState::load(config_path, STATE_FILE, config.database.as_deref())?;
+    let mut current = BTreeMap::new();
+    for week in weeks {
+        for (uid, event) in events(week) {
+            let body = event.to_string();
+            let existing = state.events.get(&uid).map(|e| e.remote_id.clone());
+
+            let remote_id = match existing {
+                Some(id) => {
+                    http::Request {
+                        method: "PATCH",
+                        url: &format!("{events_url}/{id}"),
+                        headers: auth.clone(),
+                        body: Some(&body),
+                        ..Default::default()
+                    }
+                    .send()?
+                    .error_for_status()?;
+                    id
+                }
+                None => {
+                    let response = http::Request {
+                        method: "POST",
+                        url: &events_url,
+                        headers: auth.clone(),
+                        body: Some(&body),
+                        ..Default::default()
+                    }
+                    .send()?
+                    .error_for_status()?;
+                    let created: serde_json::Value = serde_json::from_str(&response.body)?;
+                    created["id"].as_str().unwrap_or_default().to_string()
+                }
+            };
+            println!("{}updated {uid}", dry_run::prefix());
+            current.insert(uid, SyncedEvent::new(week.start_date, remote_id));
+        }
+    }
+
+    let from = weeks.first().map_or(NaiveDate::MAX, |w| w.start_date);
+    for (uid, synced) in state.stale(&current, from) {
+        let response = http::Request {
+            method: "DELETE",
+            url: &format!("{events_url}/{}", synced.remote_id),
+            headers: auth.clone(),
+            ..Default::default()
+        }
+        .send()?;
+        if response.status != 404 {
+            response.error_for_status()?;
+        }
+        println!("{}deleted {uid}", dry_run::prefix());
+    }
+
+    state.events = current;
+    state.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn events_are_all_day() {
+        let week = CareWeek {
+            week: 27,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            ..Default::default()
+        };
+        let (uid, event) = events(&week).remove(0);
+        assert_eq!(event["subject"], "Edgar");
+        assert_eq!(event["isAllDay"], true);
+        assert_eq!(event["start"]["dateTime"], "2024-07-01T00:00:00");
+        assert_eq!(event["end"]["dateTime"], "2024-07-08T00:00:00");
+        assert_eq!(uid, "whocares-2024-W27-edgar-877f4b9f");
+        assert_eq!(event["transactionId"], uid);
+
+        let split = CareWeek {
+            split: Some(whocares_core::rotation::Split {
+                from: NaiveDate::from_str("2024-07-04").unwrap(),
+                caretaker: "Jimmy".into(),
+            }),
+            ..week
+        };
+        let events = super::events(&split);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].1["end"]["dateTime"], "2024-07-04T00:00:00");
+        assert_eq!(events[1].1["subject"], "Jimmy");
+        assert_eq!(events[1].1["start"]["dateTime"], "2024-07-04T00:00:00");
+
+        let suspended = CareWeek {
+            suspended: Some("Renovation".into()),
+            split: None,
+            ..split
+        };
+        let events = super::events(&suspended);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].1["subject"], "Suspended: Renovation");
+        assert_eq!(events[0].1["showAs"], "free");
+    }
+
+    #[test]
+    fn days_make_events() {
+        let week = CareWeek {
+            week: 27,
+            caretaker: "Edgar".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            days: [
+                "Edgar", "Edgar", "Jimmy", "Jimmy", "Jimmy", "Edgar", "Edgar",
+            ]
+            .map(Into::into)
+            .to_vec(),
+            ..Default::default()
+        };
+        let events = events(&week);
+        let subjects: Vec<&serde_json::Value> = events.iter().map(|(_, e)| &e["subject"]).collect();
+        assert_eq!(subjects, ["Edgar", "Jimmy", "Edgar"]);
+        assert_eq!(events[1].1["start"]["dateTime"], "2024-07-03T00:00:00");
+        assert_eq!(events[1].1["end"]["dateTime"], "2024-07-06T00:00:00");
+        assert_eq!(events[2].0, "whocares-2024-W27-edgar-877f4b9f-20240706");
+    }
+}
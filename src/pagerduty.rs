@@ -0,0 +1,426 @@
+use crate::dry_run;
+use crate::logging::warning;
+use crate::{http, regular_caretaker, start_of_week, CareWeek, Config};
+use chrono::Duration;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use whocares_core::rotation::{shift_end, Shift};
+
+const API_URL: &str = "https://api.pagerduty.com";
+const TOKEN_ENV: &str = "WHOCARES_PAGERDUTY_TOKEN";
+const WEEK_SECS: u32 = 7 * 24 * 60 * 60;
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Deserialize)]
+pub struct PagerDutyConfig {
+    schedule_id: Option<String>,
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default = "default_time_zone")]
+    time_zone: String,
+    // caretaker name -> PagerDuty user id
+    #[serde(default)]
+    users: HashMap<String, String>,
+}
+
+impl Default for PagerDutyConfig {
+    fn default() -> Self {
+        PagerDutyConfig {
+            schedule_id: None,
+            token: None,
+            time_zone: default_time_zone(),
+            users: HashMap::new(),
+        }
+    }
+}
+
+fn default_time_zone() -> String {
+    "UTC".to_string()
+}
+
+impl PagerDutyConfig {
+    fn token(&self) -> io::Result<String> {
+        env::var(TOKEN_ENV)
+            .ok()
+            .or_else(|| self.token.clone())
+            .or_else(|| dry_run::enabled().then(String::new))
+            .ok_or_else(|| io::Error::other("no pagerduty token configured"))
+    }
+
+    fn user(&self, caretaker: &str) -> io::Result<Value> {
+        let id = self.users.get(caretaker).ok_or_else(|| {
+            io::Error::other(format!(
+                "no pagerduty user for {caretaker} in pagerduty.users"
+            ))
+        })?;
+        Ok(serde_json::json!({ "id": id, "type": "user_reference" }))
+    }
+}
+
+fn time(date: chrono::NaiveDate) -> String {
+    format!("{date}T00:00:00")
+}
+
+// One weekly layer turning over on Mondays, in roster order from the start
+// date, so PagerDuty computes the regular rotation on its own.
+pub fn schedule(config: &Config) -> io::Result<Value> {
+    let conf = &config.pagerduty;
+    if !config.odd_week_caretakers.is_empty() {
+        return Err(io::Error::other(
+            "PagerDuty layers can't alternate rosters, odd_week_caretakers aren't supported",
+        ));
+    }
+    if !config.blocks.is_empty() {
+        return Err(io::Error::other(
+            "PagerDuty layers can't switch rosters by date, blocks aren't supported",
+        ));
+    }
+    if !config.blackouts.is_empty() {
+        return Err(io::Error::other(
+            "PagerDuty layers can't pause, blackouts aren't supported",
+        ));
+    }
+    let start = time(start_of_week(config.startdate));
+    let layer = |name: &str, shift: Option<&Shift>| -> io::Result<Value> {
+        let users = config
+            .turn_order(shift)?
+            .into_iter()
+            .map(|c| conf.user(c).map(|user| serde_json::json!({ "user": user })))
+            .collect::<io::Result<Vec<Value>>>()?;
+        Ok(serde_json::json!({
+            "name": name,
+            "start": start,
+            "rotation_virtual_start": start,
+            "rotation_turn_length_seconds": WEEK_SECS,
+            "users": users,
+        }))
+    };
+    // Shifts get a layer each, restricted to their hours of the day.
+    let layers = match config.shifts.is_empty() {
+        true => vec![layer("whocares rotation", None)?],
+        false => config
+            .shifts
+            .iter()
+            .enumerate()
+            .map(|(i, shift)| {
+                let mut layer = layer(&format!("whocares {}", shift.name), Some(shift))?;
+                layer["restrictions"] = serde_json::json!([{
+                    "type": "daily_restriction",
+                    "start_time_of_day": shift.start.format("%H:%M:%S").to_string(),
+                    "duration_seconds": shift_length(config, i).num_seconds(),
+                }]);
+                Ok(layer)
+            })
+            .collect::<io::Result<Vec<Value>>>()?,
+    };
+    Ok(serde_json::json!({
+        "schedule": {
+            "type": "schedule",
+            "name": config.name.as_deref().unwrap_or("whocares"),
+            "time_zone": conf.time_zone,
+            "schedule_layers": layers,
+        }
+    }))
+}
+
+fn shift_length(config: &Config, i: usize) -> Duration {
+    let length = shift_end(&config.shifts, i) - config.shifts[i].start;
+    match length > Duration::zero() {
+        true => length,
+        false => length + Duration::days(1),
+    }
+}
+
+// The spans of weeks where reschedules, splits or availability put someone
+// other than the regular caretaker on duty. Suspended weeks have nobody to
+// put on duty.
+pub fn overrides(config: &Config, weeks: &[CareWeek]) -> io::Result<Vec<Value>> {
+    let conf = &config.pagerduty;
+    let mut overrides = Vec::new();
+    for week in weeks {
+        if week.suspended.is_some() {
+            warning!(
+                "week #{} is suspended, PagerDuty keeps its rotation",
+                week.week
+            );
+            continue;
+        }
+        if !week.shifts.is_empty() {
+            overrides.extend(shift_overrides(config, week)?);
+            continue;
+        }
+        let regular = regular_caretaker(config, week.start_date);
+        for (caretaker, start, end) in week.spans() {
+            if caretaker == regular {
+                continue;
+            }
+            let end = end.succ_opt().unwrap_or(end);
+            overrides.push(serde_json::json!({
+                "start": time(start),
+                "end": time(end),
+                "time_zone": conf.time_zone,
+                "user": conf.user(caretaker)?,
+            }));
+        }
+    }
+    Ok(overrides)
+}
+
+// PagerDuty overrides don't repeat, so a shift someone else has gets one
+// for each covered day.
+fn shift_overrides(config: &Config, week: &CareWeek) -> io::Result<Vec<Value>> {
+    let conf = &config.pagerduty;
+    let rotation = config.rotation();
+    let mut overrides = Vec::new();
+    let days = || {
+        week.start_date
+            .iter_days()
+            .take(7)
+            .filter(|d| week.coverage.contains(*d))
+    };
+    for (i, on_shift) in week.shifts.iter().enumerate() {
+        // Weeks made before a config change can have other shifts.
+        let Some(shift) = config.shifts.get(i) else {
+            continue;
+        };
+        if *on_shift.caretaker == *rotation.regular_on_shift(week.start_date, shift) {
+            continue;
+        }
+        for day in days() {
+            let start = day.and_time(shift.start);
+            let end = start + shift_length(config, i);
+            overrides.push(serde_json::json!({
+                "start": start.format(TIME_FORMAT).to_string(),
+                "end": end.format(TIME_FORMAT).to_string(),
+                "time_zone": conf.time_zone,
+                "user": conf.user(&on_shift.caretaker)?,
+            }));
+        }
+    }
+    Ok(overrides)
+}
+
+pub fn export(config: &Config, weeks: &[CareWeek]) -> io::Result<Value> {
+    let mut export = schedule(config)?;
+    export["overrides"] = overrides(config, weeks)?.into();
+    Ok(export)
+}
+
+pub fn publish(config: &Config, weeks: &[CareWeek]) -> io::Result<()> {
+    let conf = &config.pagerduty;
+    let headers = vec![
+        ("Authorization", format!("Token token={}", conf.token()?)),
+        (
+            "Accept",
+            "application/vnd.pagerduty+json;version=2".to_string(),
+        ),
+        ("Content-Type", "application/json".to_string()),
+    ];
+    let body = schedule(config)?.to_string();
+    let overrides = overrides(config, weeks)?;
+
+    let id = match conf.schedule_id.as_deref() {
+        Some(id) => {
+            http::Request {
+                method: "PUT",
+                url: &format!("{API_URL}/schedules/{id}"),
+                headers: headers.clone(),
+                body: Some(&body),
+                ..Default::default()
+            }
+            .send()?
+            .error_for_status()?;
+            id.to_string()
+        }
+        None => {
+            let response = http::Request {
+                method: "POST",
+                url: &format!("{API_URL}/schedules"),
+                headers: headers.clone(),
+                body: Some(&body),
+                ..Default::default()
+            }
+            .send()?
+            .error_for_status()?;
+            let created: Value = serde_json::from_str(&response.body).unwrap_or_default();
+            let id = created["schedule"]["id"].as_str().unwrap_or_default();
+            println!(
+                "{}created schedule {id}, set pagerduty.schedule_id",
+                dry_run::prefix()
+            );
+            id.to_string()
+        }
+    };
+    println!("{}updated schedule {id}", dry_run::prefix());
+
+    // Replace the overrides in the published range, so publishing again
+    // doesn't stack them up.
+    let (Some(first), Some(last)) = (weeks.first(), weeks.last()) else {
+        return Ok(());
+    };
+    let overrides_url = format!("{API_URL}/schedules/{id}/overrides");
+    if !dry_run::enabled() {
+        let since = time(first.start_date);
+        let until = time(last.end_date.succ_opt().unwrap_or(last.end_date));
+        let response = http::Request {
+            method: "GET",
+            url: &format!(
+                "{overrides_url}?since={}&until={}&time_zone={}",
+                http::url_encode(&since),
+                http::url_encode(&until),
+                http::url_encode(&conf.time_zone)
+            ),
+            headers: headers.clone(),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()?;
+        let existing: Value = serde_json::from_str(&response.body)?;
+        for old in existing["overrides"].as_array().into_iter().flatten() {
+            let old_id = old["id"].as_str().unwrap_or_default();
+            http::Request {
+                method: "DELETE",
+                url: &format!("{overrides_url}/{old_id}"),
+                headers: headers.clone(),
+                ..Default::default()
+            }
+            .send()?
+            .error_for_status()?;
+        }
+    }
+    if overrides.is_empty() {
+        return Ok(());
+    }
+    let count = overrides.len();
+    http::Request {
+        method: "POST",
+        url: &overrides_url,
+        headers,
+        body: Some(&serde_json::json!({ "overrides": overrides }).to_string()),
+        ..Default::default()
+    }
+    .send()?
+    .error_for_status()?;
+    println!("{}created {count} overrides", dry_run::prefix());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn export_works() {
+        let config = Config {
+            startdate: NaiveDate::from_str("2024-07-03").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            reschedule: HashMap::from([("2024-28".to_string(), "Edgar".to_string())]),
+            pagerduty: PagerDutyConfig {
+                users: HashMap::from([
+                    ("Edgar".to_string(), "PEDGAR".to_string()),
+                    ("Jimmy".to_string(), "PJIMMY".to_string()),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let weeks = crate::get_weeks(&config, config.startdate, 3);
+        let export = export(&config, &weeks).unwrap();
+
+        let layer = &export["schedule"]["schedule_layers"][0];
+        assert_eq!(layer["rotation_virtual_start"], "2024-07-01T00:00:00");
+        assert_eq!(layer["rotation_turn_length_seconds"], WEEK_SECS);
+        assert_eq!(layer["users"][1]["user"]["id"], "PJIMMY");
+        assert_eq!(export["overrides"].as_array().unwrap().len(), 1);
+        assert_eq!(export["overrides"][0]["start"], "2024-07-08T00:00:00");
+        assert_eq!(export["overrides"][0]["end"], "2024-07-15T00:00:00");
+        assert_eq!(export["overrides"][0]["user"]["id"], "PEDGAR");
+
+        let suspended: Vec<CareWeek> = weeks
+            .into_iter()
+            .map(|w| CareWeek {
+                suspended: Some("Renovation".into()),
+                ..w
+            })
+            .collect();
+        assert!(overrides(&config, &suspended).unwrap().is_empty());
+
+        // Jimmy until Wednesday of Edgar's week.
+        let split = Config {
+            reschedule: HashMap::from([("2024-29".to_string(), "Jimmy/Edgar".to_string())]),
+            ..config
+        };
+        let weeks = crate::get_weeks(&split, split.startdate, 3);
+        let overrides = overrides(&split, &weeks).unwrap();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0]["start"], "2024-07-15T00:00:00");
+        assert_eq!(overrides[0]["end"], "2024-07-18T00:00:00");
+        assert_eq!(overrides[0]["user"]["id"], "PJIMMY");
+
+        // Jimmy's days in Edgar's week.
+        let per_weekday = Config {
+            per_weekday: true,
+            reschedule: HashMap::new(),
+            ..split
+        };
+        let weeks = crate::get_weeks(&per_weekday, per_weekday.startdate, 1);
+        let overrides = super::overrides(&per_weekday, &weeks).unwrap();
+        let starts: Vec<&Value> = overrides.iter().map(|o| &o["start"]).collect();
+        assert_eq!(
+            starts,
+            [
+                "2024-07-02T00:00:00",
+                "2024-07-04T00:00:00",
+                "2024-07-06T00:00:00"
+            ]
+        );
+        assert_eq!(overrides[0]["end"], "2024-07-03T00:00:00");
+        assert_eq!(overrides[0]["user"]["id"], "PJIMMY");
+
+        let config = Config {
+            caretakers: vec!["Robert".to_string()],
+            ..per_weekday
+        };
+        assert!(schedule(&config).is_err());
+    }
+
+    #[test]
+    fn shifts_get_layers() {
+        let config = Config {
+            startdate: NaiveDate::from_str("2024-07-01").unwrap(),
+            caretakers: vec!["Edgar".to_string(), "Jimmy".to_string()],
+            reschedule: HashMap::from([("2024-28".to_string(), "Edgar".to_string())]),
+            shifts: serde_json::from_str(
+                r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00", "offset": 1}]"#,
+            )
+            .unwrap(),
+            pagerduty: PagerDutyConfig {
+                users: HashMap::from([
+                    ("Edgar".to_string(), "PEDGAR".to_string()),
+                    ("Jimmy".to_string(), "PJIMMY".to_string()),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let schedule = schedule(&config).unwrap();
+        let night = &schedule["schedule"]["schedule_layers"][1];
+        assert_eq!(night["name"], "whocares night");
+        assert_eq!(night["users"][0]["user"]["id"], "PJIMMY");
+        assert_eq!(night["restrictions"][0]["start_time_of_day"], "20:00:00");
+        assert_eq!(night["restrictions"][0]["duration_seconds"], 12 * 60 * 60);
+
+        // Edgar has Jimmy's days of the second week, the nights are his anyway.
+        let weeks = crate::get_weeks(&config, config.startdate, 2);
+        let overrides = overrides(&config, &weeks).unwrap();
+        assert_eq!(overrides.len(), 7);
+        assert_eq!(overrides[0]["start"], "2024-07-08T08:00:00");
+        assert_eq!(overrides[0]["end"], "2024-07-08T20:00:00");
+        assert_eq!(overrides[0]["user"]["id"], "PEDGAR");
+    }
+}
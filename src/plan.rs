@@ -0,0 +1,185 @@
+use crate::{get_weeks, preferences, start_of_week, Config};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+use whocares_core::Week;
+
+// Gives up on rules that can't be met instead of searching forever.
+const MAX_STEPS: usize = 200_000;
+
+#[derive(Deserialize, Default)]
+pub struct PlanConfig {
+    // Relative shares of the weeks, 1 for everyone not listed.
+    #[serde(default)]
+    pub weights: HashMap<String, f64>,
+    // At most this many weeks in a row.
+    #[serde(default)]
+    pub max_consecutive: Option<usize>,
+    // At least this many weeks off before the next turn.
+    #[serde(default)]
+    pub min_gap: usize,
+}
+
+impl PlanConfig {
+    fn weight(&self, caretaker: &str) -> f64 {
+        self.weights.get(caretaker).copied().unwrap_or(1.0)
+    }
+}
+
+struct Search<'a> {
+    conf: &'a Config,
+    weeks: Vec<(NaiveDate, Vec<&'a str>)>,
+    // The weeks before the plan as they are, then the planned ones.
+    assigned: Vec<&'a str>,
+    counts: HashMap<&'a str, f64>,
+    steps: usize,
+}
+
+impl<'a> Search<'a> {
+    fn allowed(&self, caretaker: &str) -> bool {
+        let rules = &self.conf.plan;
+        let run = self
+            .assigned
+            .iter()
+            .rev()
+            .take_while(|c| **c == caretaker)
+            .count();
+        if run > 0 {
+            return rules.max_consecutive.is_none_or(|max| run < max);
+        }
+        let gap = self.assigned.iter().rev().position(|c| *c == caretaker);
+        gap.is_none_or(|gap| gap >= rules.min_gap)
+    }
+
+    // Candidates in the order to try them: whoever is furthest behind
+    // their share of the weeks so far first. A preferred week counts as if
+    // they were a week further behind, an avoided one a week ahead.
+    fn candidates(&self, i: usize) -> Vec<&'a str> {
+        let (monday, roster) = &self.weeks[i];
+        let rotation = self.conf.rotation();
+        let total: f64 = roster.iter().map(|c| self.conf.plan.weight(c)).sum();
+        let planned = (i + 1) as f64;
+        let deficit = |c: &str| {
+            let share = self.conf.plan.weight(c) / total;
+            share * planned - self.counts.get(c).copied().unwrap_or_default()
+                + preferences::score(self.conf, c, *monday) as f64
+        };
+        let mut candidates: Vec<&'a str> = roster
+            .iter()
+            .copied()
+            .filter(|c| self.conf.plan.weight(c) > 0.0)
+            .filter(|c| rotation.is_available(c, *monday) && self.allowed(c))
+            .collect();
+        candidates.sort_by(|a, b| deficit(b).total_cmp(&deficit(a)));
+        candidates
+    }
+
+    fn solve(&mut self, i: usize) -> bool {
+        if i == self.weeks.len() {
+            return true;
+        }
+        for caretaker in self.candidates(i) {
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return false;
+            }
+            self.assigned.push(caretaker);
+            *self.counts.entry(caretaker).or_default() += 1.0;
+            if self.solve(i + 1) {
+                return true;
+            }
+            *self.counts.entry(caretaker).or_default() -= 1.0;
+            self.assigned.pop();
+        }
+        false
+    }
+}
+
+// Who takes care of each of the next `weeks` weeks from `from` under the
+// plan rules. The weeks before are taken as they are, so a turn just
+// before the plan counts for the gap. Suspended weeks are left out.
+pub fn plan(conf: &Config, from: NaiveDate, weeks: usize) -> io::Result<Vec<(Week, String)>> {
+    let from = start_of_week(from.max(conf.startdate));
+    let rotation = conf.rotation();
+    let lookback = conf
+        .plan
+        .min_gap
+        .max(conf.plan.max_consecutive.unwrap_or(0));
+    let before = from
+        .checked_sub_days(chrono::Days::new(7 * lookback as u64))
+        .unwrap_or(from)
+        .max(start_of_week(conf.startdate));
+    let assigned: Vec<String> = get_weeks(conf, before, (from - before).num_weeks() as usize)
+        .into_iter()
+        .filter(|w| w.suspended.is_none())
+        .map(|w| w.caretaker.to_string())
+        .collect();
+
+    let mut search = Search {
+        conf,
+        weeks: from
+            .iter_weeks()
+            .take(weeks)
+            .filter(|d| !conf.blackouts.iter().any(|b| b.contains(*d)))
+            .map(|d| (d, rotation.roster(d).0.iter().map(String::as_str).collect()))
+            .collect(),
+        assigned: assigned.iter().map(String::as_str).collect(),
+        counts: HashMap::new(),
+        steps: 0,
+    };
+    let planned_from = search.assigned.len();
+    if !search.solve(0) {
+        return Err(io::Error::other(
+            "no plan meets the rules and availability, try a smaller max_consecutive or min_gap",
+        ));
+    }
+    Ok(search
+        .weeks
+        .iter()
+        .zip(&search.assigned[planned_from..])
+        .map(|((monday, _), caretaker)| (Week::containing(*monday), caretaker.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn plan_honors_rules() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let conf = Config {
+            startdate: date("2024-01-01"),
+            caretakers: ["A", "B", "C"].map(String::from).to_vec(),
+            unavailable: HashMap::from([(
+                "B".to_string(),
+                vec![(date("2024-01-08"), date("2024-01-15"))],
+            )]),
+            plan: PlanConfig {
+                weights: HashMap::from([("A".to_string(), 2.0)]),
+                max_consecutive: Some(2),
+                min_gap: 1,
+            },
+            ..Default::default()
+        };
+        let planned: Vec<String> = plan(&conf, date("2024-01-01"), 8)
+            .unwrap()
+            .into_iter()
+            .map(|(_, c)| c)
+            .collect();
+        // A gets twice the weeks, B is away in the second one.
+        assert_eq!(planned, ["A", "C", "B", "A", "A", "B", "C", "A"]);
+
+        let strict = Config {
+            plan: PlanConfig {
+                max_consecutive: Some(1),
+                min_gap: 3,
+                ..Default::default()
+            },
+            ..conf
+        };
+        assert!(plan(&strict, date("2024-01-01"), 4).is_err());
+    }
+}
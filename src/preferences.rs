@@ -0,0 +1,117 @@
+use crate::{parse_week, Config};
+use chrono::{Datelike, Days, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Weeks somebody would rather have or rather not have, like "W51" for
+// every year, "2025-W32", "holidays" for weeks with a public holiday or
+// "2025-07-28..2025-09-07" for school holidays.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub enum WeekPattern {
+    EveryYear(u32),
+    Week(NaiveDate),
+    Holidays,
+    Dates(NaiveDate, NaiveDate),
+}
+
+impl TryFrom<String> for WeekPattern {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = || {
+            format!("invalid week {s}, expected W51, 2025-W51, holidays or 2025-07-28..2025-09-07")
+        };
+        if s == "holidays" {
+            return Ok(WeekPattern::Holidays);
+        }
+        if let Some((from, to)) = s.split_once("..") {
+            let from = from.parse().map_err(|_| invalid())?;
+            let to = to.parse().map_err(|_| invalid())?;
+            return match from <= to {
+                true => Ok(WeekPattern::Dates(from, to)),
+                false => Err(invalid()),
+            };
+        }
+        if let Some(week) = s.strip_prefix('W') {
+            return week
+                .parse()
+                .ok()
+                .filter(|w| (1..=53).contains(w))
+                .map(WeekPattern::EveryYear)
+                .ok_or_else(invalid);
+        }
+        parse_week(&s).map(WeekPattern::Week).ok_or_else(invalid)
+    }
+}
+
+impl WeekPattern {
+    fn matches(&self, conf: &Config, monday: NaiveDate) -> bool {
+        match self {
+            WeekPattern::EveryYear(week) => monday.iso_week().week() == *week,
+            WeekPattern::Week(start) => *start == monday,
+            WeekPattern::Holidays => !conf.holidays.in_week(monday).is_empty(),
+            WeekPattern::Dates(from, to) => *from <= monday + Days::new(6) && monday <= *to,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct Preferences {
+    #[serde(default)]
+    pub prefer: Vec<WeekPattern>,
+    #[serde(default)]
+    pub avoid: Vec<WeekPattern>,
+}
+
+pub type PreferenceConfig = HashMap<String, Preferences>;
+
+// 1 if the caretaker prefers the week, -1 if they would rather avoid it,
+// 0 otherwise. Avoiding wins when both match.
+pub fn score(conf: &Config, caretaker: &str, monday: NaiveDate) -> i32 {
+    let Some(preferences) = conf.preferences.get(caretaker) else {
+        return 0;
+    };
+    let matches = |patterns: &[WeekPattern]| patterns.iter().any(|p| p.matches(conf, monday));
+    if matches(&preferences.avoid) {
+        -1
+    } else if matches(&preferences.prefer) {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn score_works() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let patterns = |p: &[&str]| {
+            p.iter()
+                .map(|p| WeekPattern::try_from(p.to_string()).unwrap())
+                .collect()
+        };
+        let conf = Config {
+            preferences: HashMap::from([(
+                "A".to_string(),
+                Preferences {
+                    prefer: patterns(&["2024-07-29..2024-09-06", "2024-W40"]),
+                    avoid: patterns(&["W51", "W52", "2024-W36"]),
+                },
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(score(&conf, "A", date("2024-08-05")), 1);
+        assert_eq!(score(&conf, "A", date("2024-09-30")), 1);
+        assert_eq!(score(&conf, "A", date("2024-09-02")), -1);
+        assert_eq!(score(&conf, "A", date("2025-12-15")), -1);
+        assert_eq!(score(&conf, "A", date("2024-10-07")), 0);
+        assert_eq!(score(&conf, "B", date("2024-12-16")), 0);
+        assert!(WeekPattern::try_from("W54".to_string()).is_err());
+        assert!(WeekPattern::try_from("2024-09-06..2024-07-29".to_string()).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::i18n;
+use crate::svg::{color, escape};
+use crate::{CareWeek, Config};
+use chrono::Datelike;
+use std::io::{self, Write};
+
+// A page to print and pin up: A4, large type, a section per month and
+// the caretakers' colors, the same as in the SVG export. Browsers save it
+// as PDF from the print dialog.
+pub fn write_html(
+    out: &mut impl Write,
+    conf: &Config,
+    weeks: impl Iterator<Item = CareWeek>,
+) -> io::Result<()> {
+    let formatting = &conf.formatting;
+    let weeks: Vec<CareWeek> = weeks.collect();
+    let mut legend: Vec<&str> = weeks
+        .iter()
+        .flat_map(|w| w.spans().into_iter().map(|(c, _, _)| c))
+        .collect();
+    legend.sort_by_key(|c| (conf.everyone().position(|e| e == c), *c));
+    legend.dedup();
+    let swatch = |caretaker: &str| {
+        format!(
+            r#"<span class="swatch" style="background: {}"></span>"#,
+            color(conf, caretaker)
+        )
+    };
+    let title = escape(conf.name.as_deref().unwrap_or("whocares"));
+
+    write!(
+        out,
+        r#"<!DOCTYPE html>
+<html lang="{}">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+@page {{ size: A4; margin: 15mm; }}
+body {{ font-family: sans-serif; font-size: 14pt; margin: 0; }}
+* {{ -webkit-print-color-adjust: exact; print-color-adjust: exact; }}
+h1 {{ font-size: 24pt; margin: 0 0 0.3em; }}
+h2 {{ font-size: 18pt; margin: 1em 0 0.3em; }}
+section {{ break-inside: avoid; }}
+table {{ width: 100%; border-collapse: collapse; }}
+td {{ padding: 0.25em 0.5em; border-bottom: 1px solid #999; vertical-align: top; }}
+.notes {{ font-size: 11pt; }}
+.swatch {{ display: inline-block; width: 0.9em; height: 0.9em; margin-right: 0.3em; border-radius: 0.15em; vertical-align: -0.1em; }}
+.legend span {{ margin-right: 1.2em; white-space: nowrap; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p class="legend">"#,
+        formatting.locale.code()
+    )?;
+    for caretaker in &legend {
+        write!(
+            out,
+            "<span>{}{}</span>",
+            swatch(caretaker),
+            escape(caretaker)
+        )?;
+    }
+    writeln!(out, "</p>")?;
+
+    let mut month = None;
+    for week in &weeks {
+        let this_month = (week.start_date.year(), week.start_date.month());
+        if month != Some(this_month) {
+            if month.is_some() {
+                writeln!(out, "</table>\n</section>")?;
+            }
+            month = Some(this_month);
+            writeln!(
+                out,
+                "<section>\n<h2>{}</h2>\n<table>",
+                escape(&i18n::month_line(formatting, week.start_date))
+            )?;
+        }
+        let mut caretakers: Vec<&str> = week.spans().into_iter().map(|(c, _, _)| c).collect();
+        caretakers.dedup();
+        let name = match &week.suspended {
+            Some(blackout) => {
+                i18n::format(formatting.locale, "suspended", &[("blackout", blackout)])
+            }
+            None => format!("{}{}", week.caretakers(), week.overlap_note()),
+        };
+        let (first, last) = week.period();
+        writeln!(
+            out,
+            r#"<tr><td>#{}</td><td>{} - {}</td><td>{}{}</td><td class="notes">{}</td></tr>"#,
+            week.week,
+            escape(&formatting.date(first)),
+            escape(&formatting.date(last)),
+            caretakers.iter().map(|c| swatch(c)).collect::<String>(),
+            escape(&name),
+            escape(week.holiday_note().trim())
+        )?;
+    }
+    if month.is_some() {
+        writeln!(out, "</table>\n</section>")?;
+    }
+    writeln!(out, "</body>\n</html>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_weeks;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn html_has_a_section_per_month() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-01-22").unwrap(),
+            caretakers: vec!["A".to_string(), "<B>".to_string()],
+            ..Default::default()
+        };
+        let mut html = Vec::new();
+        write_html(
+            &mut html,
+            &conf,
+            get_weeks(&conf, conf.startdate, 4).into_iter(),
+        )
+        .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("@page { size: A4;"));
+        assert_eq!(html.matches("<section>").count(), 2);
+        assert!(html.contains("<h2>January 2024</h2>"));
+        assert!(html.contains("<h2>February 2024</h2>"));
+        assert!(html.contains(
+            r#"<tr><td>#5</td><td>2024-01-29 - 2024-02-04</td><td><span class="swatch" style="background: #f28e2b"></span>&lt;B&gt;</td>"#
+        ));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
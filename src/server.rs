@@ -0,0 +1,990 @@
+use crate::crypto::constant_time_eq;
+use crate::i18n::{self, Formatting};
+use crate::logging::{error, info};
+use crate::watch::config_modified;
+use crate::{
+    atom, current_week, get_config, get_next_weeks, graphql, grpc, http, ics, slack, swaps,
+    CareWeek, Config, DEFAULT_EXPORT_WEEKS, MAX_WEEKS,
+};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+const DASHBOARD_WEEKS: u32 = 8;
+const DASHBOARD_REFRESH_SECS: u32 = 300;
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+// Responses are reused for at most this long, so a new day or changed
+// availability calendars show up without a config change.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+const CACHE_ENTRIES: usize = 256;
+const API_TOKEN_ENV: &str = "WHOCARES_API_TOKEN";
+// Limits on what a client can make the server hold or wait for.
+const MAX_BODY: usize = 64 * 1024;
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+const MAX_CONNECTIONS: usize = 64;
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Default)]
+pub struct ServerConfig {
+    // The address the server is reachable at from other devices.
+    public_url: Option<String>,
+    // Bearer token for the API endpoints that change the config.
+    #[serde(default)]
+    api_token: Option<String>,
+}
+
+impl ServerConfig {
+    fn api_token(&self) -> Option<String> {
+        env::var(API_TOKEN_ENV)
+            .ok()
+            .or_else(|| self.api_token.clone())
+    }
+
+    fn public_url(&self) -> String {
+        self.public_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{DEFAULT_ADDR}"))
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
+
+fn webcal(url: &str) -> String {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    format!("webcal://{rest}")
+}
+
+pub fn subscription_links(conf: &Config) -> String {
+    let base = conf.server.public_url();
+    let mut links = format!(
+        "Subscribe to the whole schedule:\n  {}\n",
+        webcal(&format!("{base}/calendar.ics"))
+    );
+    links.push_str("\nOnly the weeks of one caretaker:\n");
+    for caretaker in conf.everyone() {
+        links.push_str(&format!(
+            "  {caretaker}: {}\n",
+            webcal(&format!(
+                "{base}/calendar/{}.ics",
+                http::url_encode(caretaker)
+            ))
+        ));
+    }
+    let scheme = if base.starts_with("https://") {
+        "https://"
+    } else {
+        "http://"
+    };
+    links.push_str(&format!(
+        "
+Apple Calendar: File > New Calendar Subscription..., paste the webcal:// link.
+Google Calendar: Other calendars > From URL, paste the link with {scheme} instead of webcal://.
+Thunderbird: New Calendar > On the Network, paste the link.
+"
+    ));
+    links
+}
+
+#[derive(Deserialize, Default)]
+pub struct CorsConfig {
+    #[serde(default)]
+    allowed_origins: Vec<String>,
+}
+
+impl CorsConfig {
+    fn allows(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == origin)
+    }
+
+    fn headers(&self, origin: &str, request: &Request) -> Vec<(&'static str, String)> {
+        if !self.allows(origin) {
+            return Vec::new();
+        }
+
+        let mut headers = vec![
+            ("Access-Control-Allow-Origin", origin.to_string()),
+            ("Vary", "Origin".to_string()),
+        ];
+        if request.method == "OPTIONS" {
+            headers.push((
+                "Access-Control-Allow-Methods",
+                "GET, POST, OPTIONS".to_string(),
+            ));
+            headers.push((
+                "Access-Control-Allow-Headers",
+                request
+                    .header("Access-Control-Request-Headers")
+                    .unwrap_or("Content-Type")
+                    .to_string(),
+            ));
+            headers.push(("Access-Control-Max-Age", "86400".to_string()));
+        }
+        headers
+    }
+}
+
+// The loaded config and the responses computed from it, keyed by the hash
+// of the config file and the request.
+struct Cache {
+    hash: u64,
+    loaded: Instant,
+    conf: Arc<Config>,
+    responses: HashMap<(String, String), Response>,
+}
+
+struct Server {
+    config_path: String,
+    subscribers: Mutex<Vec<Sender<String>>>,
+    cache: Mutex<Option<Cache>>,
+}
+
+fn config_hash(path: &str) -> io::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    fs::read(path)?.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+impl Server {
+    fn new(config_path: &str) -> Self {
+        Server {
+            config_path: config_path.to_string(),
+            subscribers: Mutex::new(Vec::new()),
+            cache: Mutex::new(None),
+        }
+    }
+
+    // Only parses the config, and loads its availability calendars, when
+    // the file changed or the cache expired.
+    fn config(&self) -> io::Result<Arc<Config>> {
+        let hash = config_hash(&self.config_path)?;
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        match cache.as_ref() {
+            Some(cache) if cache.hash == hash && cache.loaded.elapsed() < CACHE_TTL => {
+                Ok(Arc::clone(&cache.conf))
+            }
+            _ => {
+                let conf = Arc::new(get_config(&self.config_path)?);
+                *cache = Some(Cache {
+                    hash,
+                    loaded: Instant::now(),
+                    conf: Arc::clone(&conf),
+                    responses: HashMap::new(),
+                });
+                Ok(conf)
+            }
+        }
+    }
+
+    // GETs that succeeded are cached until the config is reloaded.
+    fn respond_cached(&self, request: &Request, conf: &Arc<Config>) -> Response {
+        let cacheable = request.method == "GET";
+        let key = (request.path.clone(), request.query.clone());
+        let same_config = |cache: &Cache| Arc::ptr_eq(&cache.conf, conf);
+        if cacheable {
+            let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(response) = cache
+                .as_ref()
+                .filter(|cache| same_config(cache))
+                .and_then(|cache| cache.responses.get(&key))
+            {
+                return response.clone();
+            }
+        }
+        let response = route(request, conf);
+        if cacheable && response.status == "200 OK" {
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(cache) = cache.as_mut().filter(|cache| same_config(cache)) {
+                if cache.responses.len() >= CACHE_ENTRIES {
+                    cache.responses.clear();
+                }
+                cache.responses.insert(key, response.clone());
+            }
+        }
+        response
+    }
+
+    fn invalidate(&self) {
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn subscribe(&self) -> Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    fn broadcast(&self, event: &str, data: &serde_json::Value) {
+        let message = format!("event: {event}\ndata: {data}\n\n");
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.send(message.clone()).is_ok());
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub fn run(addr: &str, config_path: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    info!("serving on http://{addr}");
+
+    let server = Arc::new(Server::new(config_path));
+
+    let watcher = Arc::clone(&server);
+    thread::spawn(move || watch_schedule(&watcher));
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::SeqCst);
+            stream.set_write_timeout(Some(IO_TIMEOUT))?;
+            let busy = Response::text("503 Service Unavailable", "too many connections");
+            if let Err(e) = respond(&mut stream, &busy) {
+                error!("request failed: {e}");
+            }
+            continue;
+        }
+        let server = Arc::clone(&server);
+        let slot = Slot(Arc::clone(&connections));
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = handle_connection(stream, &server) {
+                error!("request failed: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+// Frees a connection's place when its thread ends, panicking or not.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn current_caretaker(server: &Server) -> Option<String> {
+    let conf = server.config().ok()?;
+    current_week(&conf).map(|w| w.caretaker.to_string())
+}
+
+fn watch_schedule(server: &Server) {
+    let mut modified = config_modified(&server.config_path);
+    let mut caretaker = current_caretaker(server);
+
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        let new_modified = config_modified(&server.config_path);
+        if new_modified != modified {
+            modified = new_modified;
+            server.invalidate();
+            server.broadcast("config-reloaded", &serde_json::json!({}));
+        }
+
+        let new_caretaker = current_caretaker(server);
+        if new_caretaker != caretaker {
+            caretaker = new_caretaker;
+            server.broadcast(
+                "caretaker-changed",
+                &serde_json::json!({ "caretaker": caretaker }),
+            );
+        }
+    }
+}
+
+fn stream_events(stream: &mut TcpStream, server: &Server) -> io::Result<()> {
+    let events = server.subscribe();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    stream.flush()?;
+
+    loop {
+        match events.recv_timeout(KEEPALIVE_INTERVAL) {
+            Ok(message) => stream.write_all(message.as_bytes())?,
+            Err(RecvTimeoutError::Timeout) => stream.write_all(b": keepalive\n\n")?,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        stream.flush()?;
+    }
+}
+
+// WatchChanges, a Change with the current week first and then one whenever
+// the schedule is reloaded or the caretaker changes. Like /events the
+// stream ends when the client goes away.
+fn stream_changes(stream: &mut TcpStream, server: &Server) -> io::Result<()> {
+    let events = server.subscribe();
+    let conf = server.config()?;
+    let today = conf.today();
+    let mut weeks = grpc::watched(&conf, today);
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        grpc::CONTENT_TYPE
+    )?;
+    stream.write_all(&grpc::change(&conf, &[], &[], today))?;
+    stream.flush()?;
+
+    loop {
+        match events.recv_timeout(KEEPALIVE_INTERVAL) {
+            Ok(_) => {
+                let conf = server.config()?;
+                let today = conf.today();
+                let after = grpc::watched(&conf, today);
+                stream.write_all(&grpc::change(&conf, &weeks, &after, today))?;
+                weeks = after;
+            }
+            // There's no empty message to send, so peek if the client
+            // closed the connection instead.
+            Err(RecvTimeoutError::Timeout) if client_gone(stream)? => return Ok(()),
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        stream.flush()?;
+    }
+}
+
+fn client_gone(stream: &TcpStream) -> io::Result<bool> {
+    stream.set_nonblocking(true)?;
+    let gone = matches!(stream.peek(&mut [0]), Ok(0));
+    stream.set_nonblocking(false)?;
+    Ok(gone)
+}
+
+#[derive(Clone)]
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            content_type,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response::new("200 OK", content_type, body)
+    }
+
+    fn json(value: &serde_json::Value) -> Self {
+        Response::ok("application/json", value.to_string())
+    }
+
+    fn text(status: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response::new(status, "text/plain; charset=utf-8", body)
+    }
+
+    fn not_found() -> Self {
+        Response::text("404 Not Found", "not found")
+    }
+
+    fn server_error(e: impl ToString) -> Self {
+        Response::text("500 Internal Server Error", e.to_string())
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, server: &Server) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let request = match read_request(&mut stream)? {
+        Ok(request) => request,
+        Err(response) => {
+            info!("rejected request: {}", response.status);
+            return respond(&mut stream, &response);
+        }
+    };
+
+    if (request.method.as_str(), request.path.as_str()) == ("GET", "/events") {
+        return stream_events(&mut stream, server);
+    }
+    if (request.method.as_str(), request.path.as_str()) == ("POST", grpc::WATCH_CHANGES)
+        && grpc::accepts(request.header("Content-Type"))
+    {
+        return stream_changes(&mut stream, server);
+    }
+
+    let conf = server.config();
+    let mut response = match &conf {
+        Ok(conf) if request.method == "POST" && request.path.starts_with("/api/swaps/") => {
+            decide_swap(&request, server, conf)
+        }
+        Ok(conf) => server.respond_cached(&request, conf),
+        Err(e) => {
+            error!("failed to load {}: {e}", server.config_path);
+            Response::server_error(e)
+        }
+    };
+    info!("{} {} {}", request.method, request.path, response.status);
+    if let (Ok(conf), Some(origin)) = (&conf, request.header("Origin")) {
+        response.headers.extend(conf.cors.headers(origin, &request));
+    }
+    respond(&mut stream, &response)
+}
+
+fn route(request: &Request, conf: &Config) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("OPTIONS", _) => Response::new("204 No Content", "text/plain", Vec::new()),
+        ("POST", "/slack/command") => handle_slack_command(request, conf),
+        ("GET", "/") => {
+            let weeks = weeks_param(request, DASHBOARD_WEEKS);
+            let html = render_dashboard(&conf.formatting, &get_next_weeks(conf, weeks));
+            Response::ok("text/html; charset=utf-8", html)
+        }
+        ("GET", "/subscribe") => Response::text("200 OK", subscription_links(conf)),
+        ("GET", "/calendar.ics") => {
+            let weeks = get_next_weeks(conf, weeks_param(request, DEFAULT_EXPORT_WEEKS));
+            calendar_response(conf, &weeks)
+        }
+        ("GET", path) if path.starts_with("/calendar/") && path.ends_with(".ics") => {
+            let name = url_decode(&path["/calendar/".len()..path.len() - ".ics".len()]);
+            if !conf.is_caretaker(&name) {
+                return Response::not_found();
+            }
+            // Their parts of split weeks and the days they overlap into
+            // the next caretaker's week count too.
+            let weeks = get_next_weeks(conf, weeks_param(request, DEFAULT_EXPORT_WEEKS));
+            Response::ok(
+                "text/calendar; charset=utf-8",
+                ics::person_calendar(conf, &weeks, &name, chrono::Utc::now()),
+            )
+        }
+        ("GET", "/feed.atom") => {
+            let mut feed = Vec::new();
+            let weeks = get_next_weeks(conf, weeks_param(request, DASHBOARD_WEEKS));
+            match atom::write_feed(&mut feed, conf, weeks.into_iter(), chrono::Utc::now()) {
+                Ok(()) => Response::ok("application/atom+xml; charset=utf-8", feed),
+                Err(e) => Response::server_error(e),
+            }
+        }
+        ("GET", "/api/current") => match current_week(conf) {
+            Some(week) => Response::json(&week.to_json()),
+            None => Response::not_found(),
+        },
+        ("GET", "/api/weeks") => {
+            let weeks = get_next_weeks(conf, weeks_param(request, DASHBOARD_WEEKS));
+            Response::json(&serde_json::Value::Array(
+                weeks.iter().map(CareWeek::to_json).collect(),
+            ))
+        }
+        ("GET", "/graphql") => match query_param(&request.query, "query") {
+            Some(query) => {
+                let variables = query_param(&request.query, "variables")
+                    .and_then(|v| serde_json::from_str(&url_decode(v)).ok())
+                    .unwrap_or_default();
+                graphql_response(conf, &url_decode(query), &variables)
+            }
+            None => Response::text("200 OK", graphql::SCHEMA),
+        },
+        ("POST", path) if path.starts_with(grpc::PREFIX) => {
+            if !grpc::accepts(request.header("Content-Type")) {
+                return Response::text("415 Unsupported Media Type", "expected gRPC-Web");
+            }
+            let body = grpc::call(conf, path, &request.body, conf.today());
+            Response::ok(grpc::CONTENT_TYPE, body)
+        }
+        ("POST", "/graphql") => match serde_json::from_slice::<serde_json::Value>(&request.body) {
+            Ok(body) => graphql_response(
+                conf,
+                body["query"].as_str().unwrap_or_default(),
+                &body["variables"],
+            ),
+            Err(e) => Response::text("400 Bad Request", e.to_string()),
+        },
+        ("GET", _) => Response::not_found(),
+        _ => Response::text("405 Method Not Allowed", "method not allowed"),
+    }
+}
+
+// POST /api/swaps/approve?week=2025-W10 or /api/swaps/decline, for the
+// counterpart's client. Only with a configured token.
+fn decide_swap(request: &Request, server: &Server, conf: &Config) -> Response {
+    let Some(token) = conf.server.api_token() else {
+        return Response::not_found();
+    };
+    let approve = match &request.path["/api/swaps/".len()..] {
+        "approve" => true,
+        "decline" => false,
+        _ => return Response::not_found(),
+    };
+    let bearer = request
+        .header("Authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !constant_time_eq(bearer.as_bytes(), token.as_bytes()) {
+        return Response::text("401 Unauthorized", "invalid token");
+    }
+    let Some(week) = query_param(&request.query, "week")
+        .map(url_decode)
+        .and_then(|w| swaps::parse(&w))
+    else {
+        return Response::text("400 Bad Request", "expected week=YYYY-Www");
+    };
+    match swaps::decide(&server.config_path, week, approve, None) {
+        Ok(message) => {
+            server.invalidate();
+            Response::text("200 OK", message)
+        }
+        Err(e) => Response::text("409 Conflict", e.to_string()),
+    }
+}
+
+fn graphql_response(conf: &Config, query: &str, variables: &serde_json::Value) -> Response {
+    Response::json(&graphql::execute(conf, query, variables, conf.today()))
+}
+
+fn calendar_response(conf: &Config, weeks: &[CareWeek]) -> Response {
+    Response::ok(
+        "text/calendar; charset=utf-8",
+        ics::calendar(conf, weeks, chrono::Utc::now()),
+    )
+}
+
+fn weeks_param(request: &Request, default: u32) -> u32 {
+    query_param(&request.query, "weeks")
+        .and_then(|w| w.parse::<u32>().ok())
+        .unwrap_or(default)
+        .min(MAX_WEEKS)
+}
+
+fn handle_slack_command(request: &Request, conf: &Config) -> Response {
+    let Some(secret) = conf.slack.signing_secret() else {
+        return Response::not_found();
+    };
+    let verified = slack::verify_signature(
+        &secret,
+        request
+            .header("X-Slack-Request-Timestamp")
+            .unwrap_or_default(),
+        &request.body,
+        request.header("X-Slack-Signature").unwrap_or_default(),
+        chrono::Utc::now().timestamp(),
+    );
+    if !verified {
+        return Response::text("401 Unauthorized", "invalid signature");
+    }
+
+    let body = String::from_utf8_lossy(&request.body);
+    let weeks = query_param(&body, "text")
+        .map(url_decode)
+        .and_then(|text| text.trim().parse::<u32>().ok())
+        .unwrap_or(DASHBOARD_WEEKS / 2)
+        .clamp(1, MAX_WEEKS);
+    Response::json(&slack::reply(
+        &conf.formatting,
+        &get_next_weeks(conf, weeks),
+    ))
+}
+
+// A line of at most MAX_LINE bytes, None if it's longer.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+    Ok(Some(line).filter(|line| line.len() <= MAX_LINE))
+}
+
+// The request, or the response refusing it if it's over the limits.
+fn read_request(stream: &mut impl Read) -> io::Result<Result<Request, Response>> {
+    let mut reader = BufReader::new(stream);
+    let Some(request_line) = read_line(&mut reader)? else {
+        return Ok(Err(Response::text(
+            "414 URI Too Long",
+            "request line too long",
+        )));
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let too_large = || {
+        Ok(Err(Response::text(
+            "431 Request Header Fields Too Large",
+            "headers too large",
+        )))
+    };
+    let mut headers = Vec::new();
+    loop {
+        let Some(line) = read_line(&mut reader)? else {
+            return too_large();
+        };
+        if line.trim_end().is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return too_large();
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY {
+        return Ok(Err(Response::text(
+            "413 Payload Too Large",
+            "request body too large",
+        )));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Ok(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body,
+    }))
+}
+
+fn respond(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn url_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'+', _) => decoded.push(b' '),
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 2;
+            }
+            (b, _) => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_dashboard(formatting: &Formatting, weeks: &[CareWeek]) -> String {
+    let current = match weeks.first() {
+        Some(CareWeek {
+            suspended: Some(blackout),
+            ..
+        }) => escape_html(&i18n::format(
+            formatting.locale,
+            "suspended",
+            &[("blackout", blackout)],
+        )),
+        Some(w) => escape_html(&w.caretaker),
+        None => i18n::tr(formatting.locale, "nobody").to_string(),
+    };
+    let lang = formatting.locale.code();
+    let this_week = i18n::tr(formatting.locale, "this-week");
+
+    let rows: String = weeks
+        .iter()
+        .map(|w| {
+            let (first, last) = w.period();
+            format!(
+                "<tr><td>#{}</td><td>{} - {}</td><td>{}</td><td>{}</td></tr>\n",
+                w.week,
+                formatting.date(first),
+                formatting.date(last),
+                escape_html(&format!("{}{}", w.caretakers(), w.overlap_note())),
+                escape_html(w.holiday_note().trim())
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="{DASHBOARD_REFRESH_SECS}">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>whocares</title>
+<style>
+body {{ font-family: sans-serif; text-align: center; margin: 2em; }}
+.current {{ font-size: 5em; font-weight: bold; margin: 0.5em 0; }}
+table {{ margin: 0 auto; border-collapse: collapse; font-size: 1.5em; }}
+td {{ padding: 0.3em 1em; border-bottom: 1px solid #ccc; }}
+</style>
+</head>
+<body>
+<div>{this_week}</div>
+<div class="current">{current}</div>
+<table>
+{rows}</table>
+<script>
+new EventSource("/events").addEventListener("caretaker-changed", () => location.reload());
+</script>
+</body>
+</html>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PATH;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn read_request_enforces_limits() {
+        let status = |raw: &str| match read_request(&mut raw.as_bytes()).unwrap() {
+            Ok(request) => format!("{} {}", request.method, request.body.len()),
+            Err(response) => response.status.to_string(),
+        };
+        assert_eq!(
+            status("POST /graphql HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"),
+            "POST 2"
+        );
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 100000000000000\r\n\r\n"),
+            "413 Payload Too Large"
+        );
+        let long = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(status(&long), "431 Request Header Fields Too Large");
+        let many = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X: 1\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert_eq!(status(&many), "431 Request Header Fields Too Large");
+        let target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(status(&target), "414 URI Too Long");
+    }
+
+    #[test]
+    fn query_param_works() {
+        assert_eq!(query_param("weeks=3&x=1", "weeks"), Some("3"));
+        assert_eq!(query_param("x=1", "weeks"), None);
+        assert_eq!(query_param("", "weeks"), None);
+    }
+
+    #[test]
+    fn url_decode_works() {
+        assert_eq!(url_decode("%2Fwhocares+4"), "/whocares 4");
+        assert_eq!(url_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn render_dashboard_escapes_caretakers() {
+        let weeks = vec![CareWeek {
+            week: 27,
+            caretaker: "<b>Edgar</b>".into(),
+            start_date: NaiveDate::from_str("2024-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2024-07-07").unwrap(),
+            ..Default::default()
+        }];
+        let html = render_dashboard(&Formatting::default(), &weeks);
+        assert!(html.contains("&lt;b&gt;Edgar&lt;/b&gt;"));
+        assert!(!html.contains("<b>Edgar</b>"));
+        assert!(html.contains("http-equiv=\"refresh\""));
+
+        let suspended = vec![CareWeek {
+            suspended: Some("Renovation".into()),
+            ..Default::default()
+        }];
+        let html = render_dashboard(&Formatting::default(), &suspended);
+        assert!(html.contains(r#"<div class="current">suspended (Renovation)</div>"#));
+    }
+
+    #[test]
+    fn cors_headers_only_for_allowed_origins() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://app.example".to_string()],
+        };
+        let request = Request {
+            method: "OPTIONS".to_string(),
+            path: "/api/weeks".to_string(),
+            query: String::new(),
+            headers: vec![(
+                "Access-Control-Request-Headers".to_string(),
+                "X-Custom".to_string(),
+            )],
+            body: Vec::new(),
+        };
+
+        let headers = cors.headers("https://app.example", &request);
+        assert!(headers.contains(&(
+            "Access-Control-Allow-Origin",
+            "https://app.example".to_string()
+        )));
+        assert!(headers.contains(&("Access-Control-Allow-Headers", "X-Custom".to_string())));
+        assert!(cors.headers("https://evil.example", &request).is_empty());
+    }
+
+    #[test]
+    fn subscription_links_works() {
+        let conf = Config {
+            caretakers: vec!["Jimmy Page".to_string()],
+            server: ServerConfig {
+                public_url: Some("https://home.example/".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let links = subscription_links(&conf);
+        assert!(links.contains("webcal://home.example/calendar.ics\n"));
+        assert!(links.contains("Jimmy Page: webcal://home.example/calendar/Jimmy%20Page.ics\n"));
+        assert!(links.contains("with https:// instead"));
+    }
+
+    #[test]
+    fn broadcast_reaches_subscribers() {
+        let server = Server::new(PATH);
+        let events = server.subscribe();
+        server.broadcast("config-reloaded", &serde_json::json!({}));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            "event: config-reloaded\ndata: {}\n\n"
+        );
+
+        drop(events);
+        server.broadcast("config-reloaded", &serde_json::json!({}));
+        assert!(server.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn grpc_needs_grpc_web() {
+        let conf = get_config(PATH).unwrap();
+        let request = |content_type: &str| Request {
+            method: "POST".to_string(),
+            path: "/whocares.v1.Rotation/ListWeeks".to_string(),
+            query: String::new(),
+            headers: vec![("content-type".to_string(), content_type.to_string())],
+            body: vec![0, 0, 0, 0, 0],
+        };
+        let response = route(&request("application/grpc-web+proto"), &conf);
+        assert_eq!(
+            (response.status, response.content_type),
+            ("200 OK", grpc::CONTENT_TYPE)
+        );
+        assert!(response.body.ends_with(b"grpc-status: 0\r\n"));
+        let response = route(&request("application/json"), &conf);
+        assert_eq!(response.status, "415 Unsupported Media Type");
+    }
+
+    #[test]
+    fn responses_are_cached_per_config() {
+        let dir = std::env::temp_dir().join(format!("whocares-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        let path = path.to_str().unwrap();
+        let config = |caretaker: &str| {
+            format!(
+                r#"{{"startdate": "2024-07-01", "caretakers": ["{caretaker}"], "reschedule": {{}}}}"#
+            )
+        };
+        fs::write(path, config("Edgar")).unwrap();
+        let server = Server::new(path);
+        let request = Request {
+            method: "GET".to_string(),
+            path: "/api/current".to_string(),
+            query: String::new(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+        let get = || {
+            let conf = server.config().unwrap();
+            String::from_utf8(server.respond_cached(&request, &conf).body).unwrap()
+        };
+
+        assert!(get().contains("Edgar"));
+        // The same shape as whocares-core's weeks_json.
+        assert!(get().contains(r#""iso_week":"#));
+        assert!(Arc::ptr_eq(
+            &server.config().unwrap(),
+            &server.config().unwrap()
+        ));
+        let cached = |server: &Server| {
+            let cache = server.cache.lock().unwrap();
+            cache.as_ref().map_or(0, |c| c.responses.len())
+        };
+        assert_eq!(cached(&server), 1);
+
+        fs::write(path, config("Jimmy")).unwrap();
+        assert!(get().contains("Jimmy"));
+        server.invalidate();
+        assert_eq!(cached(&server), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
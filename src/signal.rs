@@ -0,0 +1,88 @@
+use crate::http;
+use serde::Deserialize;
+use std::io;
+
+// signal-cli started with `daemon --http`
+const DEFAULT_URL: &str = "http://127.0.0.1:8080/api/v1/rpc";
+
+#[derive(Deserialize)]
+pub struct SignalConfig {
+    #[serde(default = "default_url")]
+    pub url: String,
+    // only needed if signal-cli serves more than one account
+    #[serde(default)]
+    account: Option<String>,
+    #[serde(default)]
+    recipients: Vec<String>,
+    #[serde(default)]
+    group_id: Option<String>,
+}
+
+fn default_url() -> String {
+    DEFAULT_URL.to_string()
+}
+
+impl SignalConfig {
+    pub fn request(&self, text: &str) -> serde_json::Value {
+        let mut params = serde_json::json!({ "message": text });
+        if let Some(account) = &self.account {
+            params["account"] = account.as_str().into();
+        }
+        if !self.recipients.is_empty() {
+            params["recipient"] = self.recipients.clone().into();
+        }
+        if let Some(group_id) = &self.group_id {
+            params["groupId"] = group_id.as_str().into();
+        }
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "send",
+            "params": params,
+            "id": "whocares",
+        })
+    }
+
+    pub fn send(&self, text: &str) -> io::Result<()> {
+        if self.recipients.is_empty() && self.group_id.is_none() {
+            return Err(io::Error::other(
+                "no signal recipients or group_id configured",
+            ));
+        }
+        let response = http::Request {
+            method: "POST",
+            url: &self.url,
+            headers: vec![("Content-Type", "application/json".to_string())],
+            body: Some(&self.request(text).to_string()),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()?;
+        let response: serde_json::Value = serde_json::from_str(&response.body).unwrap_or_default();
+        match response["error"]["message"].as_str() {
+            Some(message) => Err(io::Error::other(format!("signal-cli: {message}"))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_works() {
+        let conf: SignalConfig =
+            serde_json::from_str(r#"{"recipients": ["+4915112345678"], "group_id": "abc="}"#)
+                .unwrap();
+        assert_eq!(conf.url, DEFAULT_URL);
+        let request = conf.request("Edgar is taking care next week");
+        assert_eq!(request["method"], "send");
+        assert_eq!(request["params"]["recipient"][0], "+4915112345678");
+        assert_eq!(request["params"]["groupId"], "abc=");
+        assert_eq!(
+            request["params"]["message"],
+            "Edgar is taking care next week"
+        );
+        assert!(request["params"].get("account").is_none());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::crypto::{constant_time_eq, hex, hmac_sha256};
+use crate::i18n::{self, Formatting};
+use crate::CareWeek;
+use serde::Deserialize;
+use std::env;
+
+const MAX_REQUEST_AGE_SECS: u64 = 5 * 60;
+const SIGNING_SECRET_ENV: &str = "SLACK_SIGNING_SECRET";
+
+#[derive(Deserialize, Default)]
+pub struct SlackConfig {
+    #[serde(default)]
+    signing_secret: Option<String>,
+}
+
+impl SlackConfig {
+    pub fn signing_secret(&self) -> Option<String> {
+        env::var(SIGNING_SECRET_ENV)
+            .ok()
+            .or_else(|| self.signing_secret.clone())
+    }
+}
+
+pub fn verify_signature(
+    secret: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+    now: i64,
+) -> bool {
+    let Ok(ts) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    if now.abs_diff(ts) > MAX_REQUEST_AGE_SECS {
+        return false;
+    }
+
+    let mut base = format!("v0:{timestamp}:").into_bytes();
+    base.extend_from_slice(body);
+    let expected = format!("v0={}", hex(&hmac_sha256(secret.as_bytes(), &base)));
+    constant_time_eq(expected.as_bytes(), signature.as_bytes())
+}
+
+pub fn reply(formatting: &Formatting, weeks: &[CareWeek]) -> serde_json::Value {
+    let mut lines = weeks.iter().map(|w| i18n::week_line(formatting, w));
+
+    let this_week = i18n::tr(formatting.locale, "this-week");
+    let text = match lines.next() {
+        Some(current) => {
+            let next: Vec<String> = lines.collect();
+            if next.is_empty() {
+                format!("*{this_week}:* {current}")
+            } else {
+                format!(
+                    "*{this_week}:* {current}\n*{}:*\n{}",
+                    i18n::tr(formatting.locale, "next"),
+                    next.join("\n")
+                )
+            }
+        }
+        None => i18n::tr(formatting.locale, "nobody-scheduled").to_string(),
+    };
+
+    serde_json::json!({ "response_type": "in_channel", "text": text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+        let base = format!("v0:{timestamp}:{body}");
+        format!(
+            "v0={}",
+            hex(&hmac_sha256(secret.as_bytes(), base.as_bytes()))
+        )
+    }
+
+    #[test]
+    fn verify_signature_works() {
+        let body = "command=%2Fwhocares&text=";
+        let signature = sign("secret", "1700000000", body);
+        assert!(verify_signature(
+            "secret",
+            "1700000000",
+            body.as_bytes(),
+            &signature,
+            1700000010
+        ));
+        assert!(!verify_signature(
+            "other",
+            "1700000000",
+            body.as_bytes(),
+            &signature,
+            1700000010
+        ));
+        assert!(!verify_signature(
+            "secret",
+            "1700000000",
+            body.as_bytes(),
+            &signature,
+            1700001000
+        ));
+        let signature = sign("secret", &i64::MIN.to_string(), body);
+        assert!(!verify_signature(
+            "secret",
+            &i64::MIN.to_string(),
+            body.as_bytes(),
+            &signature,
+            1700000010
+        ));
+    }
+}
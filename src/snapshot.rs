@@ -0,0 +1,93 @@
+use crate::diff::Change;
+use crate::dry_run;
+use crate::{iso_week_key, CareWeek};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const FILE: &str = "snapshot.json";
+
+// The schedule as computed by the last `changes` run, keyed like "2025-W31",
+// so the next run can tell what moved in between.
+pub struct Snapshot {
+    path: PathBuf,
+    pub weeks: Option<BTreeMap<String, String>>,
+}
+
+impl Snapshot {
+    pub fn load(config_path: &str) -> io::Result<Self> {
+        let path = Path::new(config_path).with_file_name(FILE);
+        let weeks = match fs::read_to_string(&path) {
+            Ok(s) => Some(serde_json::from_str(&s)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        Ok(Snapshot { path, weeks })
+    }
+
+    pub fn save(&self, weeks: &[CareWeek]) -> io::Result<()> {
+        let weeks: BTreeMap<String, String> = weeks
+            .iter()
+            .map(|w| (iso_week_key(w.start_date), w.caretaker.to_string()))
+            .collect();
+        if dry_run::enabled() {
+            println!("{}would write {}", dry_run::prefix(), self.path.display());
+            return Ok(());
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&weeks)?)
+    }
+
+    // Weeks that were already part of the snapshot but are now assigned to
+    // someone else. Weeks that only came into range since are not changes.
+    pub fn changes(&self, current: Vec<CareWeek>) -> Vec<Change> {
+        let Some(previous) = &self.weeks else {
+            return Vec::new();
+        };
+        current
+            .into_iter()
+            .filter_map(|week| {
+                let old = previous.get(&iso_week_key(week.start_date))?;
+                (**old != *week.caretaker).then(|| Change {
+                    old_caretaker: old.clone(),
+                    week,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn changes_works() {
+        let w31 = NaiveDate::from_str("2025-07-28").unwrap();
+        let w32 = NaiveDate::from_str("2025-08-04").unwrap();
+        let w33 = NaiveDate::from_str("2025-08-11").unwrap();
+        let snapshot = Snapshot {
+            path: PathBuf::new(),
+            weeks: Some(BTreeMap::from([
+                ("2025-W31".to_string(), "Alice".to_string()),
+                ("2025-W32".to_string(), "Bob".to_string()),
+            ])),
+        };
+        let week = |start_date, caretaker: &str| CareWeek {
+            start_date,
+            caretaker: caretaker.into(),
+            ..Default::default()
+        };
+
+        let changes = snapshot.changes(vec![
+            week(w31, "Alice"),
+            week(w32, "Alice"),
+            week(w33, "Bob"),
+        ]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].week.start_date, w32);
+        assert_eq!(changes[0].old_caretaker, "Bob");
+    }
+}
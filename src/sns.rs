@@ -0,0 +1,185 @@
+use crate::crypto::{hex, hmac_sha256, sha256};
+use crate::http;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::env;
+use std::io;
+
+const SERVICE: &str = "sns";
+
+#[derive(Deserialize)]
+pub struct SnsConfig {
+    // arn:aws:sns:<region>:<account>:<topic>
+    pub topic_arn: String,
+    // AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN
+    // take precedence
+    #[serde(default)]
+    access_key_id: Option<String>,
+    #[serde(default)]
+    secret_access_key: Option<String>,
+}
+
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let key = hmac_sha256(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+impl SnsConfig {
+    pub fn region(&self) -> io::Result<&str> {
+        self.topic_arn
+            .split(':')
+            .nth(3)
+            .filter(|r| !r.is_empty())
+            .ok_or_else(|| io::Error::other(format!("invalid sns topic arn {}", self.topic_arn)))
+    }
+
+    pub fn host(&self) -> io::Result<String> {
+        Ok(format!("{SERVICE}.{}.amazonaws.com", self.region()?))
+    }
+
+    fn credentials(&self) -> io::Result<Credentials> {
+        let var = |name: &str, fallback: &Option<String>| env::var(name).ok().or(fallback.clone());
+        match (
+            var("AWS_ACCESS_KEY_ID", &self.access_key_id),
+            var("AWS_SECRET_ACCESS_KEY", &self.secret_access_key),
+        ) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: env::var("AWS_SESSION_TOKEN").ok(),
+            }),
+            _ => Err(io::Error::other("no aws credentials configured")),
+        }
+    }
+
+    // The event goes into a message attribute, so subscriptions can filter
+    // on it. Subjects have to be ASCII, so it's not the localized text.
+    pub fn form(&self, event: &str, message: &str) -> String {
+        let subject = format!("whocares {event}");
+        [
+            ("Action", "Publish"),
+            ("Version", "2010-03-31"),
+            ("TopicArn", self.topic_arn.as_str()),
+            ("Subject", subject.as_str()),
+            ("Message", message),
+            ("MessageAttributes.entry.1.Name", "event"),
+            ("MessageAttributes.entry.1.Value.DataType", "String"),
+            ("MessageAttributes.entry.1.Value.StringValue", event),
+        ]
+        .iter()
+        .map(|(k, v)| format!("{k}={}", http::url_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+    }
+
+    // AWS Signature Version 4 for a POST to "/", returns the headers to send.
+    fn sign(
+        &self,
+        credentials: &Credentials,
+        body: &str,
+        now: DateTime<Utc>,
+    ) -> io::Result<Vec<(&'static str, String)>> {
+        let region = self.region()?;
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &amz_date[..8];
+        let mut headers = vec![
+            (
+                "content-type",
+                "application/x-www-form-urlencoded".to_string(),
+            ),
+            ("host", self.host()?),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect();
+        let signed_headers: Vec<&str> = headers.iter().map(|(name, _)| *name).collect();
+        let signed_headers = signed_headers.join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{canonical_headers}\n{signed_headers}\n{}",
+            hex(&sha256(body.as_bytes()))
+        );
+        let scope = format!("{date}/{region}/{SERVICE}/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex(&sha256(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&credentials.secret_access_key, date, region, SERVICE);
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        headers.retain(|(name, _)| *name != "host");
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                credentials.access_key_id
+            ),
+        ));
+        Ok(headers)
+    }
+
+    pub fn send(&self, event: &str, message: &str) -> io::Result<()> {
+        let credentials = self.credentials()?;
+        let body = self.form(event, message);
+        let headers = self.sign(&credentials, &body, Utc::now())?;
+        http::Request {
+            method: "POST",
+            url: &format!("https://{}/", self.host()?),
+            headers,
+            body: Some(&body),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_works() {
+        // from the AWS Signature Version 4 documentation
+        assert_eq!(
+            hex(&signing_key(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                "20120215",
+                "us-east-1",
+                "iam"
+            )),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+
+        let conf: SnsConfig =
+            serde_json::from_str(r#"{"topic_arn": "arn:aws:sns:eu-central-1:123456789012:trash"}"#)
+                .unwrap();
+        assert_eq!(conf.host().unwrap(), "sns.eu-central-1.amazonaws.com");
+        let credentials = Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: None,
+        };
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let headers = conf.sign(&credentials, "Action=Publish", now).unwrap();
+        let authorization = &headers.last().unwrap().1;
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20231114/eu-central-1/sns/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature="
+        ));
+        assert!(!headers.iter().any(|(name, _)| *name == "host"));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::history::History;
+use crate::{get_weeks, preferences, regular_caretaker, start_of_week, CareWeek, Config};
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+use std::io;
+use whocares_core::Week;
+
+const REBALANCE_HORIZON_WEEKS: usize = 52;
+
+#[derive(PartialEq, Debug)]
+pub struct DutyCount {
+    pub caretaker: String,
+    pub weeks: usize,
+    pub regular: usize,
+}
+
+// Weeks that start within [from, to], beginning with the first week of the rotation.
+pub fn weeks_between(conf: &Config, from: NaiveDate, to: NaiveDate) -> Vec<CareWeek> {
+    let mut first = start_of_week(from);
+    if first < from {
+        first = first + chrono::Days::new(7);
+    }
+    let first = first.max(start_of_week(conf.startdate));
+    let count = ((to - first).num_days() / 7 + 1).max(0) as usize;
+    get_weeks(conf, first, count)
+}
+
+pub fn count(conf: &Config, weeks: &[CareWeek]) -> Vec<DutyCount> {
+    let mut counts: Vec<DutyCount> = conf
+        .everyone()
+        .map(|c| DutyCount {
+            caretaker: c.clone(),
+            weeks: 0,
+            regular: 0,
+        })
+        .collect();
+
+    // Nobody takes care in suspended weeks.
+    for week in weeks.iter().filter(|w| w.suspended.is_none()) {
+        let regular = regular_caretaker(conf, week.start_date);
+        for (caretaker, actual) in [(&*week.caretaker, true), (regular, false)] {
+            let idx = match counts.iter().position(|c| c.caretaker == caretaker) {
+                Some(idx) => idx,
+                None => {
+                    counts.push(DutyCount {
+                        caretaker: caretaker.to_string(),
+                        weeks: 0,
+                        regular: 0,
+                    });
+                    counts.len() - 1
+                }
+            };
+            if actual {
+                counts[idx].weeks += 1;
+            } else {
+                counts[idx].regular += 1;
+            }
+        }
+    }
+    counts
+}
+
+pub fn parse_period(args: &[String], today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let jan_first = |year| NaiveDate::from_ymd_opt(year, 1, 1);
+    let dec_last = |year| NaiveDate::from_ymd_opt(year, 12, 31);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] | ["ytd"] => Some((jan_first(today.year())?, today)),
+        ["year"] => Some((jan_first(today.year())?, dec_last(today.year())?)),
+        ["year", year] => {
+            let year = year.parse().ok()?;
+            Some((jan_first(year)?, dec_last(year)?))
+        }
+        [from, to] => {
+            let from = from.parse().ok()?;
+            let to = to.parse().ok()?;
+            (from <= to).then_some((from, to))
+        }
+        _ => None,
+    }
+}
+
+// Scheduled weeks, corrected by who actually covered them.
+pub fn served_weeks(
+    conf: &Config,
+    config_path: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> io::Result<Vec<CareWeek>> {
+    let mut weeks = weeks_between(conf, from, to);
+    History::load_between(config_path, conf, from, to)?.apply(&mut weeks);
+    Ok(weeks)
+}
+
+pub fn report(
+    conf: &Config,
+    config_path: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> io::Result<String> {
+    let weeks = served_weeks(conf, config_path, from, to)?;
+    let mut report = format!("{from} - {to}: {} weeks\n", weeks.len());
+    let (standins, counts): (Vec<DutyCount>, Vec<DutyCount>) = count(conf, &weeks)
+        .into_iter()
+        .partition(|c| conf.substitutes.contains(&c.caretaker));
+    for count in counts {
+        let diff = count.weeks as i64 - count.regular as i64;
+        let rescheduled = match diff {
+            0 => String::new(),
+            d => format!(" ({d:+} vs. regular rotation)"),
+        };
+        report.push_str(&format!(
+            "{}: {} weeks{rescheduled}\n",
+            count.caretaker, count.weeks
+        ));
+    }
+    for count in standins {
+        report.push_str(&format!(
+            "{}: {} stand-in weeks\n",
+            count.caretaker, count.weeks
+        ));
+    }
+    Ok(report)
+}
+
+// 1.0 when everyone served the same number of weeks, falling towards 0.0
+// as the mean absolute deviation approaches the mean.
+pub fn fairness_score(counts: &[DutyCount]) -> f64 {
+    if counts.is_empty() {
+        return 1.0;
+    }
+    let mean = counts.iter().map(|c| c.weeks as f64).sum::<f64>() / counts.len() as f64;
+    if mean == 0.0 {
+        return 1.0;
+    }
+    let deviation = counts
+        .iter()
+        .map(|c| (c.weeks as f64 - mean).abs())
+        .sum::<f64>()
+        / counts.len() as f64;
+    (1.0 - deviation / mean).max(0.0)
+}
+
+// Proposes reschedules of upcoming weeks from whoever served the most to
+// whoever served the least until nobody is more than one week apart. Among
+// the least served, whoever prefers the week gets it first and whoever
+// avoids it last.
+pub fn rebalance(
+    conf: &Config,
+    counts: &[DutyCount],
+    upcoming: &[CareWeek],
+) -> BTreeMap<Week, String> {
+    // Substitutes only stand in, they aren't owed or owing weeks.
+    let mut served: BTreeMap<&str, usize> = conf
+        .everyone()
+        .filter(|c| !conf.substitutes.contains(c))
+        .map(|c| (c.as_str(), 0))
+        .collect();
+    for count in counts {
+        if let Some(weeks) = served.get_mut(count.caretaker.as_str()) {
+            *weeks = count.weeks;
+        }
+    }
+
+    let mut proposals = BTreeMap::new();
+    for week in upcoming.iter().filter(|w| w.suspended.is_none()) {
+        let Some((&least, &min)) = served
+            .iter()
+            .min_by_key(|(c, n)| (**n, -preferences::score(conf, c, week.start_date)))
+        else {
+            break;
+        };
+        let Some(&current) = served.get(&*week.caretaker) else {
+            continue;
+        };
+        if current > min + 1 {
+            proposals.insert(Week::containing(week.start_date), least.to_string());
+            served.insert(least, min + 1);
+        } else {
+            served.insert(&week.caretaker, current + 1);
+        }
+    }
+    proposals
+}
+
+pub fn fairness_report(
+    conf: &Config,
+    config_path: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+) -> io::Result<String> {
+    let weeks = served_weeks(conf, config_path, from, to)?;
+    let mut counts = count(conf, &weeks);
+    counts.retain(|c| !conf.substitutes.contains(&c.caretaker));
+    let mut report = format!(
+        "{from} - {to}: fairness {:.0}%\n",
+        fairness_score(&counts) * 100.0
+    );
+
+    let upcoming = get_weeks(
+        conf,
+        start_of_week(to.max(today)) + chrono::Days::new(7),
+        REBALANCE_HORIZON_WEEKS,
+    );
+    let proposals = rebalance(conf, &counts, &upcoming);
+    if proposals.is_empty() {
+        report.push_str("no reschedules needed\n");
+    } else {
+        report.push_str("suggested reschedule entries:\n");
+        let entries: Vec<String> = proposals
+            .iter()
+            .map(|(week, caretaker)| {
+                format!(
+                    "    {}: {}",
+                    serde_json::json!(week),
+                    serde_json::json!(caretaker)
+                )
+            })
+            .collect();
+        report.push_str(&entries.join(",\n"));
+        report.push('\n');
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn count_includes_reschedules() {
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: date("2024-01-01"),
+            reschedule: HashMap::from([("2024-2".to_string(), "A".to_string())]),
+            ..Default::default()
+        };
+        let weeks = weeks_between(&conf, date("2023-06-01"), date("2024-01-28"));
+        assert_eq!(weeks.len(), 4);
+        assert_eq!(
+            count(&conf, &weeks),
+            vec![
+                DutyCount {
+                    caretaker: "A".to_string(),
+                    weeks: 3,
+                    regular: 2
+                },
+                DutyCount {
+                    caretaker: "B".to_string(),
+                    weeks: 1,
+                    regular: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fairness_score_works() {
+        let counts = |weeks: &[usize]| {
+            weeks
+                .iter()
+                .map(|w| DutyCount {
+                    caretaker: w.to_string(),
+                    weeks: *w,
+                    regular: *w,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fairness_score(&counts(&[3, 3, 3])), 1.0);
+        assert_eq!(fairness_score(&counts(&[6, 2])), 0.5);
+        assert_eq!(fairness_score(&counts(&[])), 1.0);
+    }
+
+    #[test]
+    fn rebalance_works() {
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: date("2024-01-01"),
+            reschedule: HashMap::from([
+                ("2024-2".to_string(), "A".to_string()),
+                ("2024-4".to_string(), "A".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let counts = count(
+            &conf,
+            &weeks_between(&conf, date("2024-01-01"), date("2024-01-28")),
+        );
+        let upcoming = get_weeks(&conf, date("2024-01-29"), 6);
+        let proposals = rebalance(&conf, &counts, &upcoming);
+        assert_eq!(
+            proposals,
+            BTreeMap::from([
+                (Week::new(2024, 5).unwrap(), "B".to_string()),
+                (Week::new(2024, 7).unwrap(), "B".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_period_works() {
+        let today = date("2024-07-03");
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_period(&args(&[]), today),
+            Some((date("2024-01-01"), today))
+        );
+        assert_eq!(
+            parse_period(&args(&["year", "2023"]), today),
+            Some((date("2023-01-01"), date("2023-12-31")))
+        );
+        assert_eq!(
+            parse_period(&args(&["2024-02-01", "2024-03-01"]), today),
+            Some((date("2024-02-01"), date("2024-03-01")))
+        );
+        assert_eq!(
+            parse_period(&args(&["2024-03-01", "2024-02-01"]), today),
+            None
+        );
+        assert_eq!(parse_period(&args(&["month"]), today), None);
+    }
+}
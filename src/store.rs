@@ -0,0 +1,107 @@
+use serde_json::Value;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// The tables replacing history.json, the sync state files and audit.log.
+// Week keys like "2025-W31" sort by date, so reports can ask for a range.
+const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS history (week TEXT PRIMARY KEY, caretaker TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS synced_events (
+  state TEXT NOT NULL,
+  uid TEXT NOT NULL,
+  start TEXT NOT NULL,
+  remote_id TEXT NOT NULL,
+  PRIMARY KEY (state, uid)
+);
+CREATE TABLE IF NOT EXISTS audit (
+  time TEXT NOT NULL,
+  user TEXT NOT NULL,
+  action TEXT NOT NULL,
+  changes TEXT NOT NULL
+);
+";
+
+// A SQLite database next to the config, used through the sqlite3 tool like
+// HTTP goes through curl.
+pub struct Db {
+    path: PathBuf,
+}
+
+// A string literal for SQL.
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+impl Db {
+    pub fn open(config_path: &str, file_name: &str) -> Self {
+        Db {
+            path: Path::new(config_path).with_file_name(file_name),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Runs the statements in one transaction, creating the tables first if
+    // needed. The rows of a query come back as JSON objects.
+    pub fn run(&self, sql: &str) -> io::Result<Vec<Value>> {
+        let mut child = Command::new("sqlite3")
+            .args(["-bail", "-json"])
+            .arg(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => {
+                    io::Error::other("the database needs sqlite3, but it isn't installed")
+                }
+                _ => e,
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            write!(stdin, "{SCHEMA}BEGIN;\n{sql}\nCOMMIT;\n")?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{}: {}",
+                self.path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "" => Ok(Vec::new()),
+            rows => Ok(serde_json::from_str(rows)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_works() {
+        let dir = std::env::temp_dir().join(format!("whocares-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        let db = Db::open(config.to_str().unwrap(), "whocares.db");
+
+        let name = quote("Jimmy 'Jim' Page");
+        db.run(&format!("INSERT INTO history VALUES ('2025-W31', {name});"))
+            .unwrap();
+        let rows = db.run("SELECT week, caretaker FROM history;").unwrap();
+        assert_eq!(
+            rows,
+            vec![serde_json::json!({ "week": "2025-W31", "caretaker": "Jimmy 'Jim' Page" })]
+        );
+        assert!(db
+            .run("SELECT * FROM history WHERE week > 'Z';")
+            .unwrap()
+            .is_empty());
+        assert!(db.run("SELECT nothing FROM history;").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use crate::i18n::{self, Formatting};
+use crate::{CareWeek, Config};
+use chrono::{Datelike, Months, NaiveDate};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+// Told apart in print too, from Tableau's 10 colors.
+const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+const UNKNOWN: &str = "#bab0ac";
+const BLANK: &str = "#eeeeee";
+const CELL: i64 = 22;
+const MARGIN: i64 = 16;
+const MONTH_WIDTH: i64 = 7 * CELL + MARGIN;
+// The month's name, the weekdays and up to six weeks.
+const MONTH_HEIGHT: i64 = 8 * CELL + MARGIN;
+const MONTHS_PER_ROW: i64 = 4;
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn color(conf: &Config, caretaker: &str) -> &'static str {
+    let idx = conf.everyone().position(|c| c == caretaker);
+    idx.map_or(UNKNOWN, |i| PALETTE[i % PALETTE.len()])
+}
+
+// A small calendar per month the weeks touch, four to a row, with every
+// covered day in its caretaker's color and a legend below. Holidays are
+// outlined, suspended and uncovered days stay blank.
+pub fn write_svg(
+    out: &mut impl Write,
+    conf: &Config,
+    weeks: impl Iterator<Item = CareWeek>,
+) -> io::Result<()> {
+    let mut days: BTreeMap<NaiveDate, String> = BTreeMap::new();
+    let mut holidays = BTreeSet::new();
+    for week in weeks {
+        for (caretaker, start, end) in week.spans() {
+            for day in start.iter_days().take_while(|d| *d <= end) {
+                days.insert(day, caretaker.to_string());
+            }
+        }
+        holidays.extend(week.holidays.iter().map(|h| h.date));
+    }
+    let months: Vec<NaiveDate> = match (days.keys().next(), days.keys().next_back()) {
+        (Some(first), Some(last)) => {
+            let last = last.with_day(1).unwrap_or(*last);
+            std::iter::successors(first.with_day(1), |m| m.checked_add_months(Months::new(1)))
+                .take_while(|m| *m <= last)
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    let mut legend: Vec<&str> = days.values().map(String::as_str).collect();
+    legend.sort_by_key(|c| (conf.everyone().position(|e| e == c), *c));
+    legend.dedup();
+
+    let title = conf.name.as_deref().unwrap_or("whocares");
+    let rows = (months.len() as i64 + MONTHS_PER_ROW - 1) / MONTHS_PER_ROW;
+    let width = MONTHS_PER_ROW * MONTH_WIDTH + MARGIN;
+    let legend_top = 2 * CELL + rows * MONTH_HEIGHT;
+    let height = legend_top + legend.len() as i64 * CELL + MARGIN;
+    let weekday = Formatting {
+        locale: conf.formatting.locale,
+        date_format: Some("%a".to_string()),
+        ..Default::default()
+    };
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="11">"#
+    )?;
+    writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    writeln!(
+        out,
+        r#"<text x="{MARGIN}" y="{}" font-size="16" font-weight="bold">{}</text>"#,
+        CELL + 4,
+        escape(title)
+    )?;
+    for (i, month) in months.iter().enumerate() {
+        let left = MARGIN + i as i64 % MONTHS_PER_ROW * MONTH_WIDTH;
+        let top = 2 * CELL + i as i64 / MONTHS_PER_ROW * MONTH_HEIGHT;
+        writeln!(
+            out,
+            r#"<text x="{left}" y="{}" font-weight="bold">{}</text>"#,
+            top + CELL - 6,
+            escape(&i18n::month_line(&conf.formatting, *month))
+        )?;
+        for (column, day) in month.iter_days().take(7).enumerate() {
+            let column = (column as u32 + month.weekday().num_days_from_monday()) % 7;
+            writeln!(
+                out,
+                r##"<text x="{}" y="{}" text-anchor="middle" fill="#666">{}</text>"##,
+                left + column as i64 * CELL + CELL / 2,
+                top + 2 * CELL - 6,
+                escape(&weekday.date(day).chars().take(2).collect::<String>())
+            )?;
+        }
+        let offset = month.weekday().num_days_from_monday() as i64;
+        let next = month.checked_add_months(Months::new(1));
+        for (n, day) in month
+            .iter_days()
+            .take_while(|d| Some(*d) != next)
+            .enumerate()
+        {
+            let cell = offset + n as i64;
+            let (x, y) = (left + cell % 7 * CELL, top + 2 * CELL + cell / 7 * CELL);
+            let caretaker = days.get(&day);
+            let fill = caretaker.map_or(BLANK, |c| color(conf, c));
+            let stroke = match holidays.contains(&day) {
+                true => r##" stroke="#333" stroke-width="2""##,
+                false => "",
+            };
+            let tooltip = caretaker.map_or(String::new(), |c| {
+                format!("<title>{}</title>", escape(&format!("{day}: {c}")))
+            });
+            writeln!(
+                out,
+                r#"<g>{tooltip}<rect x="{}" y="{}" width="{}" height="{}" rx="3" fill="{fill}"{stroke}/><text x="{}" y="{}" text-anchor="middle">{}</text></g>"#,
+                x + 1,
+                y + 1,
+                CELL - 2,
+                CELL - 2,
+                x + CELL / 2,
+                y + CELL / 2 + 4,
+                day.day()
+            )?;
+        }
+    }
+    for (i, caretaker) in legend.iter().enumerate() {
+        let y = legend_top + i as i64 * CELL;
+        writeln!(
+            out,
+            r#"<rect x="{MARGIN}" y="{y}" width="{}" height="{}" rx="3" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+            CELL - 4,
+            CELL - 4,
+            color(conf, caretaker),
+            MARGIN + CELL + 2,
+            y + CELL / 2 + 2,
+            escape(caretaker)
+        )?;
+    }
+    writeln!(out, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_weeks;
+    use std::str::FromStr;
+
+    #[test]
+    fn svg_colors_caretakers() {
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-01-29").unwrap(),
+            caretakers: vec!["A & B".to_string(), "C".to_string()],
+            ..Default::default()
+        };
+        let weeks = get_weeks(&conf, conf.startdate, 3);
+        let mut svg = Vec::new();
+        write_svg(&mut svg, &conf, weeks.into_iter()).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // January for its last days and February.
+        assert!(svg.contains(">January 2024</text>"));
+        assert!(svg.contains(">February 2024</text>"));
+        assert!(!svg.contains("March 2024"));
+        // The first Monday of February starts its second row.
+        assert!(svg.contains(&format!(
+            "<title>2024-02-05: C</title><rect x=\"{}\" y=\"{}\"",
+            MARGIN + MONTH_WIDTH + 1,
+            2 * CELL + 3 * CELL + 1
+        )));
+        assert!(svg.contains(r##"fill="#f28e2b"/><text"##));
+        assert!(svg.contains(">A &amp; B</text>"));
+    }
+}
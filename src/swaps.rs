@@ -0,0 +1,208 @@
+use crate::{
+    assignment, get_config, get_week, mutate, parse_week, set_reschedule, CareWeek, Config,
+};
+use serde::Deserialize;
+use std::io;
+use whocares_core::Week;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    #[default]
+    Pending,
+    Approved,
+    Declined,
+}
+
+// A trade of two weeks somebody proposed. It only becomes a reschedule once
+// whoever has the second week approves it.
+#[derive(Deserialize)]
+pub struct Proposal {
+    pub weeks: [Week; 2],
+    pub by: String,
+    #[serde(default)]
+    pub status: Status,
+}
+
+fn pending(conf: &Config, week: Week) -> Option<(usize, &Proposal)> {
+    conf.swaps
+        .iter()
+        .enumerate()
+        .find(|(_, p)| p.status == Status::Pending && p.weeks.contains(&week))
+}
+
+// Notes the other week of a pending swap on the week.
+pub fn mark(conf: &Config, week: &mut CareWeek) {
+    let this = Week::containing(week.start_date);
+    week.pending_swap =
+        pending(conf, this).and_then(|(_, p)| p.weeks.into_iter().find(|w| *w != this));
+}
+
+fn swaps(config: &mut serde_json::Value) -> io::Result<&mut Vec<serde_json::Value>> {
+    let Some(object) = config.as_object_mut() else {
+        return Err(io::Error::other("config is not a JSON object"));
+    };
+    object
+        .entry("swaps")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or_else(|| io::Error::other("swaps is not a JSON array"))
+}
+
+pub fn parse(week: &str) -> Option<Week> {
+    parse_week(week).map(Week::containing)
+}
+
+pub fn propose(config_path: &str, first: Week, second: Week, me: Option<&str>) -> io::Result<()> {
+    let conf = get_config(config_path)?;
+    let (a, b) = (
+        get_week(&conf, first.monday()),
+        get_week(&conf, second.monday()),
+    );
+    if assignment(&a) == assignment(&b) {
+        return Err(io::Error::other(format!(
+            "{} has both weeks, nothing to swap",
+            a.caretakers()
+        )));
+    }
+    if let Some((_, p)) = pending(&conf, first).or_else(|| pending(&conf, second)) {
+        return Err(io::Error::other(format!(
+            "a swap of {} and {} is already pending",
+            p.weeks[0], p.weeks[1]
+        )));
+    }
+    let by = me.map_or_else(|| assignment(&a), str::to_string);
+    println!(
+        "{first}: {} <-> {second}: {}, pending until {} approves",
+        a.caretakers(),
+        b.caretakers(),
+        b.caretakers()
+    );
+    let action = format!("swap propose {first} {second}");
+    mutate::update_config(config_path, &action, |config| {
+        swaps(config)?.push(serde_json::json!({
+            "weeks": [first, second],
+            "by": by,
+            "status": "pending",
+        }));
+        Ok(())
+    })
+}
+
+// Approves or declines the pending swap of a week, as `me` if given, who
+// then has to have the second week. Approving writes the reschedules.
+pub fn decide(
+    config_path: &str,
+    week: Week,
+    approve: bool,
+    me: Option<&str>,
+) -> io::Result<String> {
+    let conf = get_config(config_path)?;
+    let Some((i, proposal)) = pending(&conf, week) else {
+        return Err(io::Error::other(format!("no swap of {week} is pending")));
+    };
+    let [first, second] = proposal.weeks;
+    let (a, b) = (
+        get_week(&conf, first.monday()),
+        get_week(&conf, second.monday()),
+    );
+    let counterpart = assignment(&b);
+    if let Some(me) = me.filter(|me| *me != counterpart) {
+        return Err(io::Error::other(format!(
+            "{me} can't decide the swap of {first} and {second}, it's up to {counterpart}"
+        )));
+    }
+    let (status, message) = match approve {
+        true => (
+            "approved",
+            format!(
+                "{first}: {} -> {}\n{second}: {} -> {}",
+                a.caretakers(),
+                b.caretakers(),
+                b.caretakers(),
+                a.caretakers()
+            ),
+        ),
+        false => (
+            "declined",
+            format!("declined the swap of {first} and {second}"),
+        ),
+    };
+    let action = format!("swap {status} {first} {second}");
+    mutate::update_config(config_path, &action, |config| {
+        let proposal = swaps(config)?
+            .get_mut(i)
+            .and_then(serde_json::Value::as_object_mut)
+            .ok_or_else(|| io::Error::other("swaps changed, try again"))?;
+        proposal.insert("status".to_string(), status.into());
+        if approve {
+            let map = mutate::reschedule_map(config)?;
+            set_reschedule(map, first, &assignment(&b));
+            set_reschedule(map, second, &assignment(&a));
+        }
+        Ok(())
+    })?;
+    Ok(message)
+}
+
+// The pending swaps, one per line.
+pub fn list(conf: &Config) -> String {
+    conf.swaps
+        .iter()
+        .filter(|p| p.status == Status::Pending)
+        .map(|p| {
+            let [first, second] = p.weeks.map(|w| get_week(conf, w.monday()));
+            format!(
+                "{}: {} <-> {}: {}, proposed by {}\n",
+                p.weeks[0],
+                first.caretakers(),
+                p.weeks[1],
+                second.caretakers(),
+                p.by
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::str::FromStr;
+
+    #[test]
+    fn pending_swaps_are_marked() {
+        let week = |s: &str| parse(s).unwrap();
+        let conf = Config {
+            startdate: NaiveDate::from_str("2024-01-01").unwrap(),
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            swaps: vec![
+                Proposal {
+                    weeks: [week("2024-W02"), week("2024-W05")],
+                    by: "B".to_string(),
+                    status: Status::Pending,
+                },
+                Proposal {
+                    weeks: [week("2024-W03"), week("2024-W04")],
+                    by: "A".to_string(),
+                    status: Status::Declined,
+                },
+            ],
+            ..Default::default()
+        };
+        let mut weeks = crate::get_weeks(&conf, week("2024-W01").monday(), 5);
+        weeks.iter_mut().for_each(|w| mark(&conf, w));
+        let marked: Vec<Option<Week>> = weeks.iter().map(|w| w.pending_swap).collect();
+        assert_eq!(
+            marked,
+            [
+                None,
+                Some(week("2024-W05")),
+                None,
+                None,
+                Some(week("2024-W02"))
+            ]
+        );
+        assert_eq!(list(&conf), "2024-W02: B <-> 2024-W05: A, proposed by B\n");
+    }
+}
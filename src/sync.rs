@@ -0,0 +1,200 @@
+use crate::dry_run;
+#[cfg(feature = "sqlite")]
+use crate::store::{quote, Db};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SyncedEvent {
+    start: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub remote_id: String,
+}
+
+impl SyncedEvent {
+    pub fn new(start: NaiveDate, remote_id: String) -> Self {
+        SyncedEvent {
+            start: start.to_string(),
+            remote_id,
+        }
+    }
+
+    fn start(&self) -> Option<NaiveDate> {
+        self.start.parse().ok()
+    }
+}
+
+// Remembers which events were published to a remote calendar, keyed by
+// their stable uid, so later runs can update and delete them.
+pub struct SyncState {
+    path: PathBuf,
+    #[cfg(feature = "sqlite")]
+    db: Option<Db>,
+    pub events: BTreeMap<String, SyncedEvent>,
+}
+
+impl SyncState {
+    // A database keeps the events of each state under its file's name.
+    pub fn load(config_path: &str, file_name: &str, database: Option<&str>) -> io::Result<Self> {
+        let path = Path::new(config_path).with_file_name(file_name);
+        match database {
+            #[cfg(feature = "sqlite")]
+            Some(database) => {
+                let db = Db::open(config_path, database);
+                let rows = db.run(&format!(
+                    "SELECT uid, start, remote_id FROM synced_events WHERE state = {};",
+                    quote(file_name)
+                ))?;
+                let events = rows
+                    .iter()
+                    .filter_map(|row| {
+                        let event = SyncedEvent {
+                            start: row["start"].as_str()?.to_string(),
+                            remote_id: row["remote_id"].as_str()?.to_string(),
+                        };
+                        Some((row["uid"].as_str()?.to_string(), event))
+                    })
+                    .collect();
+                Ok(SyncState {
+                    path,
+                    db: Some(db),
+                    events,
+                })
+            }
+            _ => {
+                let events = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                    .unwrap_or_default();
+                Ok(SyncState {
+                    path,
+                    #[cfg(feature = "sqlite")]
+                    db: None,
+                    events,
+                })
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        #[cfg(feature = "sqlite")]
+        if let Some(db) = &self.db {
+            if dry_run::enabled() {
+                println!("{}would write {}", dry_run::prefix(), db.path().display());
+                return Ok(());
+            }
+            let state = quote(&self.path.file_name().unwrap_or_default().to_string_lossy());
+            let mut sql = format!("DELETE FROM synced_events WHERE state = {state};");
+            for (uid, event) in &self.events {
+                sql.push_str(&format!(
+                    "\nINSERT INTO synced_events VALUES ({state}, {}, {}, {});",
+                    quote(uid),
+                    quote(&event.start),
+                    quote(&event.remote_id)
+                ));
+            }
+            return db.run(&sql).map(drop);
+        }
+        if dry_run::enabled() {
+            println!("{}would write {}", dry_run::prefix(), self.path.display());
+            return Ok(());
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.events)?)
+    }
+
+    // Events of weeks that are still in the published range but no longer
+    // present, e.g. because the week was rescheduled to someone else.
+    pub fn stale(
+        &self,
+        current: &BTreeMap<String, SyncedEvent>,
+        from: NaiveDate,
+    ) -> Vec<(&String, &SyncedEvent)> {
+        self.events
+            .iter()
+            .filter(|(uid, event)| {
+                event.start().is_some_and(|start| start >= from) && !current.contains_key(*uid)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn stale_works() {
+        let w26 = NaiveDate::from_str("2024-06-24").unwrap();
+        let w27 = NaiveDate::from_str("2024-07-01").unwrap();
+        let w28 = NaiveDate::from_str("2024-07-08").unwrap();
+        let state = SyncState {
+            path: PathBuf::new(),
+            #[cfg(feature = "sqlite")]
+            db: None,
+            events: BTreeMap::from([
+                (
+                    "whocares-2024-W26-florian".to_string(),
+                    SyncedEvent::new(w26, String::new()),
+                ),
+                (
+                    "whocares-2024-W27-edgar".to_string(),
+                    SyncedEvent::new(w27, String::new()),
+                ),
+                (
+                    "whocares-2024-W28-dawe".to_string(),
+                    SyncedEvent::new(w28, String::new()),
+                ),
+            ]),
+        };
+        let current = BTreeMap::from([
+            (
+                "whocares-2024-W27-edgar".to_string(),
+                SyncedEvent::new(w27, String::new()),
+            ),
+            (
+                "whocares-2024-W28-jimmy".to_string(),
+                SyncedEvent::new(w28, String::new()),
+            ),
+        ]);
+
+        let stale: Vec<&String> = state
+            .stale(&current, w27)
+            .into_iter()
+            .map(|(uid, _)| uid)
+            .collect();
+        assert_eq!(stale, vec!["whocares-2024-W28-dawe"]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn database_keeps_states_apart() {
+        let dir = std::env::temp_dir().join(format!("whocares-sync-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        let config = config.to_str().unwrap();
+        let w28 = NaiveDate::from_str("2024-07-08").unwrap();
+
+        let mut caldav = SyncState::load(config, "caldav.json", Some("whocares.db")).unwrap();
+        caldav.events.insert(
+            "whocares-2024-W28-dawe".to_string(),
+            SyncedEvent::new(w28, "it's/1".to_string()),
+        );
+        caldav.save().unwrap();
+        let outlook = SyncState::load(config, "outlook.json", Some("whocares.db")).unwrap();
+        assert!(outlook.events.is_empty());
+
+        let caldav = SyncState::load(config, "caldav.json", Some("whocares.db")).unwrap();
+        let event = &caldav.events["whocares-2024-W28-dawe"];
+        assert_eq!(
+            (event.start(), event.remote_id.as_str()),
+            (Some(w28), "it's/1")
+        );
+        assert!(!dir.join("caldav.json").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
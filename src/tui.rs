@@ -0,0 +1,243 @@
+use crate::{get_config, get_weeks, iso_week_key, start_of_week, CareWeek, Config};
+use chrono::{Days, NaiveDate};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+const COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+const HEADER_LINES: usize = 3;
+
+enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Char(u8),
+}
+
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let mut buf = [0u8; 1];
+    input.read_exact(&mut buf)?;
+    if buf[0] != 0x1b {
+        return Ok(Some(Key::Char(buf[0])));
+    }
+    let mut seq = [0u8; 2];
+    input.read_exact(&mut seq)?;
+    Ok(match seq {
+        [b'[', b'A'] => Some(Key::Up),
+        [b'[', b'B'] => Some(Key::Down),
+        [b'[', b'5'] | [b'[', b'6'] => {
+            input.read_exact(&mut buf)?; // trailing '~'
+            Some(if seq[1] == b'5' {
+                Key::PageUp
+            } else {
+                Key::PageDown
+            })
+        }
+        _ => None,
+    })
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(File::open("/dev/tty")?)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed, is this a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn terminal_height() -> usize {
+    stty(&["size"])
+        .ok()
+        .and_then(|size| size.split_whitespace().next()?.parse().ok())
+        .filter(|rows| *rows > 0)
+        .unwrap_or(24)
+}
+
+struct State {
+    first: NaiveDate,
+    selected: usize,
+    rows: usize,
+    marked: Option<NaiveDate>,
+    status: String,
+}
+
+impl State {
+    fn selected_week(&self) -> NaiveDate {
+        self.first + Days::new(7 * self.selected as u64)
+    }
+
+    fn move_by(&mut self, weeks: i64) {
+        let selected = self.selected as i64 + weeks;
+        if selected < 0 {
+            self.first = self.first - Days::new(7 * selected.unsigned_abs());
+            self.selected = 0;
+        } else if selected as usize >= self.rows {
+            let overflow = selected as u64 - self.rows as u64 + 1;
+            self.first = self.first + Days::new(7 * overflow);
+            self.selected = self.rows - 1;
+        } else {
+            self.selected = selected as usize;
+        }
+    }
+}
+
+fn color(conf: &Config, caretaker: &str) -> u8 {
+    let idx = conf.everyone().position(|c| c == caretaker);
+    idx.map_or(37, |i| COLORS[i % COLORS.len()])
+}
+
+fn render(conf: &Config, weeks: &[CareWeek], state: &State) -> String {
+    let mut screen = String::from("\x1b[H\x1b[2J");
+    screen.push_str(
+        "whocares  j/k or arrows: move  PgUp/PgDn: scroll  s: swap  v: vacation  q: quit\r\n",
+    );
+    screen.push_str(&state.status);
+    screen.push_str("\r\n\r\n");
+    for (i, week) in weeks.iter().enumerate() {
+        let marker = if state.marked == Some(week.start_date) {
+            '*'
+        } else {
+            ' '
+        };
+        let selected = if i == state.selected { "\x1b[7m" } else { "" };
+        let (first, last) = week.period();
+        screen.push_str(&format!(
+            "{selected}{marker} week #{:>2} {first} - {last}  \x1b[{}m{}\x1b[0m{}{}\r\n",
+            week.week,
+            color(conf, &week.caretaker),
+            week.caretakers(),
+            week.overlap_note(),
+            week.holiday_note()
+        ));
+    }
+    screen
+}
+
+// The caretaker after the current one in the week's roster takes over.
+fn vacation(config_path: &str, conf: &Config, week: &CareWeek) -> io::Result<String> {
+    let (roster, _) = conf.rotation().roster(week.start_date);
+    let idx = roster.iter().position(|c| **c == *week.caretaker);
+    let substitute = &roster[idx.map_or(0, |i| (i + 1) % roster.len())];
+    if **substitute == *week.caretaker {
+        return Ok(format!("nobody can take over for {}", week.caretaker));
+    }
+    crate::reschedule(config_path, &iso_week_key(week.start_date), substitute)?;
+    Ok(format!(
+        "{} is away in {}, {substitute} takes over",
+        week.caretaker,
+        iso_week_key(week.start_date)
+    ))
+}
+
+fn handle(config_path: &str, conf: &Config, state: &mut State, key: Key) -> io::Result<bool> {
+    match key {
+        Key::Up | Key::Char(b'k') => state.move_by(-1),
+        Key::Down | Key::Char(b'j') => state.move_by(1),
+        Key::PageUp => state.move_by(-(state.rows as i64)),
+        Key::PageDown => state.move_by(state.rows as i64),
+        Key::Char(b's') => {
+            let selected = state.selected_week();
+            state.status = match state.marked.take() {
+                None => {
+                    state.marked = Some(selected);
+                    format!(
+                        "swap {} with? select a week and press s",
+                        iso_week_key(selected)
+                    )
+                }
+                Some(marked) if marked == selected => "swap cancelled".to_string(),
+                Some(marked) => {
+                    crate::swap(config_path, &iso_week_key(marked), &iso_week_key(selected))?;
+                    format!(
+                        "swapped {} and {}",
+                        iso_week_key(marked),
+                        iso_week_key(selected)
+                    )
+                }
+            };
+        }
+        Key::Char(b'v') => {
+            state.status = vacation(
+                config_path,
+                conf,
+                &crate::get_week(conf, state.selected_week()),
+            )?;
+        }
+        Key::Char(b'q') | Key::Char(3) => return Ok(false),
+        _ => {}
+    }
+    Ok(true)
+}
+
+fn event_loop(config_path: &str, state: &mut State) -> io::Result<()> {
+    let mut tty = File::open("/dev/tty")?;
+    let mut out = io::stdout();
+    loop {
+        let conf = get_config(config_path)?;
+        let weeks = get_weeks(&conf, state.first, state.rows);
+        out.write_all(render(&conf, &weeks, state).as_bytes())?;
+        out.flush()?;
+
+        let Some(key) = read_key(&mut tty)? else {
+            continue;
+        };
+        match handle(config_path, &conf, state, key) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(e) => state.status = format!("error: {e}"),
+        }
+    }
+}
+
+pub fn run(config_path: &str, today: NaiveDate) -> io::Result<()> {
+    let saved = stty(&["-g"])?;
+    let mut state = State {
+        first: start_of_week(today),
+        selected: 0,
+        rows: terminal_height().saturating_sub(HEADER_LINES + 1).max(1),
+        marked: None,
+        status: String::new(),
+    };
+    stty(&["raw", "-echo"])?;
+    print!("\x1b[?25l");
+    let result = event_loop(config_path, &mut state);
+    print!("\x1b[?25h\x1b[H\x1b[2J");
+    io::stdout().flush()?;
+    stty(&[&saved])?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn render_highlights_caretakers() {
+        let start = NaiveDate::from_str("2024-01-01").unwrap();
+        let conf = Config {
+            caretakers: vec!["A".to_string(), "B".to_string()],
+            startdate: start,
+            ..Default::default()
+        };
+        let mut state = State {
+            first: start,
+            selected: 1,
+            rows: 2,
+            marked: Some(start),
+            status: String::new(),
+        };
+        let screen = render(&conf, &get_weeks(&conf, start, 2), &state);
+        assert!(screen.contains("* week # 1 2024-01-01 - 2024-01-07  \x1b[31mA\x1b[0m"));
+        assert!(screen.contains("\x1b[7m  week # 2 2024-01-08 - 2024-01-14  \x1b[32mB\x1b[0m"));
+
+        state.move_by(1);
+        assert_eq!(state.first, start + Days::new(7));
+        assert_eq!(state.selected_week(), start + Days::new(14));
+    }
+}
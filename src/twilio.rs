@@ -0,0 +1,96 @@
+use crate::contacts::Contacts;
+use crate::{http, CareWeek};
+use serde::Deserialize;
+use std::env;
+use std::io;
+
+const API_URL: &str = "https://api.twilio.com/2010-04-01";
+const AUTH_TOKEN_ENV: &str = "WHOCARES_TWILIO_AUTH_TOKEN";
+
+#[derive(Deserialize)]
+pub struct TwilioConfig {
+    account_sid: String,
+    #[serde(default)]
+    auth_token: Option<String>,
+    // the Twilio number messages are sent from
+    from: String,
+}
+
+impl TwilioConfig {
+    fn auth_token(&self) -> String {
+        env::var(AUTH_TOKEN_ENV)
+            .ok()
+            .or_else(|| self.auth_token.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn recipient<'a>(&self, contacts: &'a Contacts, week: &CareWeek) -> io::Result<&'a str> {
+        contacts
+            .get(&*week.caretaker)
+            .and_then(|c| c.phone.as_deref())
+            .ok_or_else(|| {
+                io::Error::other(format!(
+                    "no phone number for {} in contacts",
+                    week.caretaker
+                ))
+            })
+    }
+
+    pub fn form(&self, to: &str, text: &str) -> String {
+        format!(
+            "To={}&From={}&Body={}",
+            http::url_encode(to),
+            http::url_encode(&self.from),
+            http::url_encode(text)
+        )
+    }
+
+    pub fn send(&self, contacts: &Contacts, week: &CareWeek, text: &str) -> io::Result<()> {
+        let to = self.recipient(contacts, week)?;
+        let auth_token = self.auth_token();
+        http::Request {
+            method: "POST",
+            url: &format!("{API_URL}/Accounts/{}/Messages.json", self.account_sid),
+            headers: vec![(
+                "Content-Type",
+                "application/x-www-form-urlencoded".to_string(),
+            )],
+            basic_auth: Some((&self.account_sid, &auth_token)),
+            body: Some(&self.form(to, text)),
+            ..Default::default()
+        }
+        .send()?
+        .error_for_status()
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contacts::Contact;
+
+    #[test]
+    fn sends_to_the_caretaker() {
+        let conf: TwilioConfig =
+            serde_json::from_str(r#"{"account_sid": "AC1", "from": "+15005550006"}"#).unwrap();
+        let contacts = Contacts::from([(
+            "Edgar".to_string(),
+            Contact {
+                phone: Some("+4915112345678".to_string()),
+                ..Default::default()
+            },
+        )]);
+        let mut week = CareWeek {
+            caretaker: "Edgar".into(),
+            ..Default::default()
+        };
+        let to = conf.recipient(&contacts, &week).unwrap();
+        assert_eq!(
+            conf.form(to, "Take care"),
+            "To=%2B4915112345678&From=%2B15005550006&Body=Take%20care"
+        );
+        week.caretaker = "Jimmy".into();
+        assert!(conf.recipient(&contacts, &week).is_err());
+    }
+}
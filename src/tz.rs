@@ -0,0 +1,458 @@
+#[cfg(feature = "notifications")]
+use chrono::{DateTime, TimeZone};
+use chrono::{Datelike, Days, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+// A transition rule in POSIX "Mm.w.d/time" form: day d (0 = Sunday) of
+// week w (5 = last) of month m, at local time.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Rule {
+    pub month: u32,
+    pub week: u32,
+    pub weekday: u32,
+    pub time_secs: i32,
+}
+
+impl Rule {
+    pub fn date_in(&self, year: i32) -> Option<NaiveDate> {
+        let weekday = Weekday::try_from(((self.weekday + 6) % 7) as u8).ok()?;
+        if self.week < 5 {
+            return NaiveDate::from_weekday_of_month_opt(
+                year,
+                self.month,
+                weekday,
+                self.week as u8,
+            );
+        }
+        let first_of_next = match self.month {
+            12 => NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+            month => NaiveDate::from_ymd_opt(year, month + 1, 1)?,
+        };
+        (1..=7)
+            .map(|days| first_of_next - Days::new(days))
+            .find(|d| d.weekday() == weekday)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dst {
+    pub name: String,
+    pub offset_secs: i32,
+    pub start: Rule,
+    pub end: Rule,
+}
+
+// Time zone rules as found in the footer of TZif files, offsets east of UTC.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PosixTz {
+    pub std_name: String,
+    pub std_offset_secs: i32,
+    pub dst: Option<Dst>,
+}
+
+// Only names like "Europe/Berlin" below ZONEINFO, not "/etc/passwd" or
+// "../".
+pub fn load(name: &str) -> io::Result<PosixTz> {
+    let path = Path::new(name);
+    let relative = path.components().all(|c| matches!(c, Component::Normal(_)));
+    if name.is_empty() || !relative {
+        return Err(io::Error::other(format!("invalid timezone {name}")));
+    }
+    let data = fs::read(Path::new(ZONEINFO).join(name))?;
+    let footer = data
+        .strip_suffix(b"\n")
+        .and_then(|d| d.rsplit(|b| *b == b'\n').next())
+        .and_then(|f| std::str::from_utf8(f).ok())
+        .unwrap_or_default();
+    parse(footer).ok_or_else(|| io::Error::other(format!("unsupported timezone {name}")))
+}
+
+fn parse_name(s: &str) -> Option<(String, &str)> {
+    if let Some(rest) = s.strip_prefix('<') {
+        let (name, rest) = rest.split_once('>')?;
+        return Some((name.to_string(), rest));
+    }
+    let end = s
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(s.len());
+    (end >= 3).then(|| (s[..end].to_string(), &s[end..]))
+}
+
+// Parses "[+-]hh[:mm[:ss]]" into seconds.
+fn parse_time(s: &str) -> Option<(i32, &str)> {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '+' || c == '-'))
+        .unwrap_or(s.len());
+    let (time, rest) = s.split_at(end);
+    let (sign, time) = match time.strip_prefix('-') {
+        Some(t) => (-1, t),
+        None => (1, time.strip_prefix('+').unwrap_or(time)),
+    };
+    let mut secs = 0;
+    for (i, part) in time.split(':').enumerate() {
+        secs += part.parse::<i32>().ok()? * [3600, 60, 1].get(i)?;
+    }
+    Some((sign * secs, rest))
+}
+
+fn parse_rule(s: &str) -> Option<Rule> {
+    let (date, time) = s.split_once('/').unwrap_or((s, "2"));
+    let mut fields = date.strip_prefix('M')?.split('.');
+    let rule = Rule {
+        month: fields.next()?.parse().ok()?,
+        week: fields.next()?.parse().ok()?,
+        weekday: fields.next()?.parse().ok()?,
+        time_secs: parse_time(time)?.0,
+    };
+    ((1..=12).contains(&rule.month) && (1..=5).contains(&rule.week) && rule.weekday < 7)
+        .then_some(rule)
+}
+
+pub fn parse(s: &str) -> Option<PosixTz> {
+    let (std_name, rest) = parse_name(s)?;
+    let (std_offset, rest) = parse_time(rest)?;
+    let std_offset_secs = -std_offset;
+    if rest.is_empty() {
+        return Some(PosixTz {
+            std_name,
+            std_offset_secs,
+            dst: None,
+        });
+    }
+
+    let (dst_name, rest) = parse_name(rest)?;
+    let (offset_secs, rest) = match rest.strip_prefix(',') {
+        Some(_) => (std_offset_secs + 3600, rest),
+        None => {
+            let (offset, rest) = parse_time(rest)?;
+            (-offset, rest)
+        }
+    };
+    let mut rules = rest.strip_prefix(',')?.split(',');
+    Some(PosixTz {
+        std_name,
+        std_offset_secs,
+        dst: Some(Dst {
+            name: dst_name,
+            offset_secs,
+            start: parse_rule(rules.next()?)?,
+            end: parse_rule(rules.next()?)?,
+        }),
+    })
+}
+
+fn format_offset(secs: i32) -> String {
+    let sign = if secs < 0 { '-' } else { '+' };
+    let secs = secs.abs();
+    format!("{sign}{:02}{:02}", secs / 3600, secs % 3600 / 60)
+}
+
+fn rrule(rule: &Rule) -> String {
+    const DAYS: [&str; 7] = ["SU", "MO", "TU", "WE", "TH", "FR", "SA"];
+    let week = if rule.week == 5 {
+        "-1".to_string()
+    } else {
+        rule.week.to_string()
+    };
+    format!(
+        "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={week}{}",
+        rule.month, DAYS[rule.weekday as usize]
+    )
+}
+
+fn component(kind: &str, name: &str, from: i32, to: i32, rule: Option<&Rule>) -> Vec<String> {
+    let date = rule
+        .and_then(|r| r.date_in(1970))
+        .unwrap_or(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+    let time = rule.map_or(0, |r| r.time_secs);
+    let mut lines = vec![
+        format!("BEGIN:{kind}"),
+        format!(
+            "DTSTART:{}T{:02}{:02}{:02}",
+            date.format("%Y%m%d"),
+            time / 3600,
+            time % 3600 / 60,
+            time % 60
+        ),
+        format!("TZOFFSETFROM:{}", format_offset(from)),
+        format!("TZOFFSETTO:{}", format_offset(to)),
+        format!("TZNAME:{name}"),
+    ];
+    lines.extend(rule.map(rrule));
+    lines.push(format!("END:{kind}"));
+    lines
+}
+
+impl PosixTz {
+    pub fn vtimezone(&self, tzid: &str) -> Vec<String> {
+        let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{tzid}")];
+        match &self.dst {
+            Some(dst) => {
+                lines.extend(component(
+                    "DAYLIGHT",
+                    &dst.name,
+                    self.std_offset_secs,
+                    dst.offset_secs,
+                    Some(&dst.start),
+                ));
+                lines.extend(component(
+                    "STANDARD",
+                    &self.std_name,
+                    dst.offset_secs,
+                    self.std_offset_secs,
+                    Some(&dst.end),
+                ));
+            }
+            None => lines.extend(component(
+                "STANDARD",
+                &self.std_name,
+                self.std_offset_secs,
+                self.std_offset_secs,
+                None,
+            )),
+        }
+        lines.push("END:VTIMEZONE".to_string());
+        lines
+    }
+}
+
+impl PosixTz {
+    // Whether DST is in effect at a UTC time. Southern zones start DST late
+    // in the year and end it early in the next.
+    fn is_dst(&self, utc: NaiveDateTime) -> bool {
+        let Some(dst) = &self.dst else {
+            return false;
+        };
+        let change = |rule: &Rule, offset: i32| {
+            Some(
+                rule.date_in(utc.year())?.and_time(NaiveTime::MIN)
+                    + Duration::seconds((rule.time_secs - offset).into()),
+            )
+        };
+        let (Some(start), Some(end)) = (
+            change(&dst.start, self.std_offset_secs),
+            change(&dst.end, dst.offset_secs),
+        ) else {
+            return false;
+        };
+        if start < end {
+            start <= utc && utc < end
+        } else {
+            utc >= start || utc < end
+        }
+    }
+
+    pub fn offset_at(&self, utc: NaiveDateTime) -> i32 {
+        match &self.dst {
+            Some(dst) if self.is_dst(utc) => dst.offset_secs,
+            _ => self.std_offset_secs,
+        }
+    }
+
+    // Local times skipped when the clocks go forward are moved forward by
+    // the gap, the ones repeated when they go back mean the first time.
+    #[cfg(feature = "notifications")]
+    pub fn to_utc(&self, local: NaiveDateTime) -> NaiveDateTime {
+        // Larger offsets first, they give the smaller time.
+        let (larger, smaller) = match &self.dst {
+            Some(dst) => (
+                dst.offset_secs.max(self.std_offset_secs),
+                dst.offset_secs.min(self.std_offset_secs),
+            ),
+            None => (self.std_offset_secs, self.std_offset_secs),
+        };
+        let candidate = |offset: i32| local - Duration::seconds(offset.into());
+        [larger, smaller]
+            .into_iter()
+            .map(candidate)
+            .zip([larger, smaller])
+            .find(|(utc, offset)| self.offset_at(*utc) == *offset)
+            .map_or_else(|| candidate(smaller), |(utc, _)| utc)
+    }
+}
+
+// The timezone the schedule runs in, the system's unless one is configured.
+#[derive(Default)]
+pub enum Zone {
+    #[default]
+    System,
+    Posix(PosixTz),
+}
+
+impl Zone {
+    pub fn load(name: Option<&str>) -> io::Result<Zone> {
+        match name {
+            Some(name) => load(name).map(Zone::Posix),
+            None => Ok(Zone::System),
+        }
+    }
+
+    pub fn now(&self) -> NaiveDateTime {
+        match self {
+            Zone::System => chrono::Local::now().naive_local(),
+            Zone::Posix(tz) => {
+                let utc = Utc::now().naive_utc();
+                utc + Duration::seconds(tz.offset_at(utc).into())
+            }
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date()
+    }
+
+    #[cfg(feature = "notifications")]
+    pub fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            Zone::System => {
+                // Gaps are at most a few hours, step over them.
+                let time = (0..24 * 4)
+                    .map(|quarters| local + Duration::minutes(15 * quarters))
+                    .find_map(|local| chrono::Local.from_local_datetime(&local).earliest())
+                    .map(|time| time.with_timezone(&Utc));
+                time.unwrap_or_else(|| Utc.from_utc_datetime(&local))
+            }
+            Zone::Posix(tz) => Utc.from_utc_datetime(&tz.to_utc(local)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        let berlin = parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert_eq!(berlin.std_name, "CET");
+        assert_eq!(berlin.std_offset_secs, 3600);
+        let dst = berlin.dst.unwrap();
+        assert_eq!(dst.name, "CEST");
+        assert_eq!(dst.offset_secs, 7200);
+        assert_eq!(
+            dst.end,
+            Rule {
+                month: 10,
+                week: 5,
+                weekday: 0,
+                time_secs: 3 * 3600
+            }
+        );
+
+        let kolkata = parse("IST-5:30").unwrap();
+        assert_eq!(kolkata.std_offset_secs, 5 * 3600 + 1800);
+        assert!(kolkata.dst.is_none());
+
+        let quoted = parse("<-03>3").unwrap();
+        assert_eq!(quoted.std_name, "-03");
+        assert_eq!(quoted.std_offset_secs, -3 * 3600);
+
+        assert!(parse("").is_none());
+    }
+
+    #[test]
+    fn rule_date_in_works() {
+        let rule = |month, week| Rule {
+            month,
+            week,
+            weekday: 0,
+            time_secs: 0,
+        };
+        assert_eq!(
+            rule(3, 5).date_in(2024),
+            NaiveDate::from_ymd_opt(2024, 3, 31)
+        );
+        assert_eq!(
+            rule(3, 2).date_in(2024),
+            NaiveDate::from_ymd_opt(2024, 3, 10)
+        );
+        assert_eq!(
+            rule(12, 5).date_in(2024),
+            NaiveDate::from_ymd_opt(2024, 12, 29)
+        );
+    }
+
+    #[cfg(feature = "notifications")]
+    #[test]
+    fn to_utc_works() {
+        let berlin = parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(
+            berlin.to_utc(at("2024-03-30 12:00")),
+            at("2024-03-30 11:00")
+        );
+        assert_eq!(
+            berlin.to_utc(at("2024-04-01 00:00")),
+            at("2024-03-31 22:00")
+        );
+        // skipped, 02:30 CET is 03:30 CEST
+        assert_eq!(
+            berlin.to_utc(at("2024-03-31 02:30")),
+            at("2024-03-31 01:30")
+        );
+        // repeated, the first 02:30 is still CEST
+        assert_eq!(
+            berlin.to_utc(at("2024-10-27 02:30")),
+            at("2024-10-27 00:30")
+        );
+        assert_eq!(
+            berlin.to_utc(at("2024-10-28 00:00")),
+            at("2024-10-27 23:00")
+        );
+
+        // Chile changes at midnight, on Sunday morning at 00:00 local time
+        let santiago = parse("<-04>4<-03>,M9.1.6/24,M4.1.6/24").unwrap();
+        assert_eq!(
+            santiago.to_utc(at("2024-09-08 00:30")),
+            at("2024-09-08 04:30")
+        );
+        assert_eq!(
+            santiago.to_utc(at("2024-09-09 00:00")),
+            at("2024-09-09 03:00")
+        );
+        assert_eq!(
+            santiago.to_utc(at("2024-04-06 23:30")),
+            at("2024-04-07 02:30")
+        );
+        assert_eq!(
+            santiago.to_utc(at("2024-04-08 00:00")),
+            at("2024-04-08 04:00")
+        );
+    }
+
+    #[test]
+    fn load_rejects_paths() {
+        for name in [
+            "/etc/passwd",
+            "../../etc/passwd",
+            "Europe/../../etc/passwd",
+            "./UTC",
+            "",
+        ] {
+            let e = load(name).unwrap_err();
+            assert_eq!(e.to_string(), format!("invalid timezone {name}"));
+        }
+    }
+
+    #[test]
+    fn zone_today_works() {
+        let zone = |s| Zone::Posix(parse(s).unwrap());
+        let days = (zone("<+14>-14").today() - zone("<-12>12").today()).num_days();
+        assert!((1..=2).contains(&days));
+    }
+
+    #[test]
+    fn vtimezone_works() {
+        let berlin = parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let lines = berlin.vtimezone("Europe/Berlin");
+        assert!(lines.contains(&"TZID:Europe/Berlin".to_string()));
+        assert!(lines.contains(&"RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU".to_string()));
+        assert!(lines.contains(&"DTSTART:19701025T030000".to_string()));
+        assert!(lines.contains(&"DTSTART:19700329T020000".to_string()));
+        assert!(lines.contains(&"TZOFFSETTO:+0200".to_string()));
+    }
+}
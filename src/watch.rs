@@ -0,0 +1,47 @@
+use crate::{get_config, start_of_week, tz};
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn config_modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Commands that only display the schedule and can be kept on screen.
+pub fn supported(command: Option<&str>) -> bool {
+    match command {
+        None => true,
+        Some(c) if c.parse::<u32>().is_ok() => true,
+        Some(c) => matches!(
+            c,
+            "forecast" | "next-for" | "until-my-turn" | "stats" | "credits" | "fairness"
+        ),
+    }
+}
+
+// Redraws the output of `show` whenever the config changes or a new week
+// starts. Errors are shown instead of ending the loop, the config may just
+// be in the middle of an edit.
+pub fn run<F>(config_path: &str, show: F) -> io::Result<()>
+where
+    F: Fn() -> io::Result<()>,
+{
+    loop {
+        print!("\x1b[H\x1b[2J");
+        if let Err(e) = show() {
+            println!("whocares: {e}");
+        }
+        io::stdout().flush()?;
+
+        // Weeks start at midnight in the config's timezone.
+        let zone = get_config(config_path).map_or_else(|_| tz::Zone::default(), |conf| conf.zone);
+        let modified = config_modified(config_path);
+        let week = start_of_week(zone.today());
+        while config_modified(config_path) == modified && start_of_week(zone.today()) == week {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
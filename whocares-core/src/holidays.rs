@@ -0,0 +1,186 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::io;
+
+const SUPPORTED_COUNTRIES: &[&str] = &["DE"];
+
+#[derive(Deserialize, Default)]
+pub struct HolidayConfig {
+    country: Option<String>,
+    region: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: &'static str,
+}
+
+impl HolidayConfig {
+    pub fn validate(&self) -> io::Result<()> {
+        match self.country.as_deref() {
+            Some(country) if !SUPPORTED_COUNTRIES.contains(&country.to_uppercase().as_str()) => {
+                Err(io::Error::other(format!(
+                    "unsupported holiday country {country}, supported: {}",
+                    SUPPORTED_COUNTRIES.join(", ")
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn in_week(&self, start_of_week: NaiveDate) -> Vec<Holiday> {
+        let end_of_week = start_of_week + Days::new(6);
+        let mut years = vec![start_of_week.year()];
+        if end_of_week.year() != start_of_week.year() {
+            years.push(end_of_week.year());
+        }
+
+        years
+            .into_iter()
+            .flat_map(|year| self.for_year(year))
+            .filter(|h| start_of_week <= h.date && h.date <= end_of_week)
+            .collect()
+    }
+
+    fn for_year(&self, year: i32) -> Vec<Holiday> {
+        let region = self.region.as_deref().unwrap_or_default().to_uppercase();
+        match self.country.as_deref().map(str::to_uppercase).as_deref() {
+            Some("DE") => germany(year, &region),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn germany(year: i32, region: &str) -> Vec<Holiday> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let easter = easter_sunday(year);
+    let from_easter = |days: i64| easter + chrono::Duration::days(days);
+    let in_regions = |regions: &[&str]| regions.contains(&region);
+
+    let mut holidays = vec![
+        (date(1, 1), "Neujahr"),
+        (from_easter(-2), "Karfreitag"),
+        (from_easter(1), "Ostermontag"),
+        (date(5, 1), "Tag der Arbeit"),
+        (from_easter(39), "Christi Himmelfahrt"),
+        (from_easter(50), "Pfingstmontag"),
+        (date(10, 3), "Tag der Deutschen Einheit"),
+        (date(12, 25), "1. Weihnachtstag"),
+        (date(12, 26), "2. Weihnachtstag"),
+    ];
+
+    if in_regions(&["BW", "BY", "ST"]) {
+        holidays.push((date(1, 6), "Heilige Drei Könige"));
+    }
+    if (region == "BE" && year >= 2019) || (region == "MV" && year >= 2023) {
+        holidays.push((date(3, 8), "Internationaler Frauentag"));
+    }
+    if region == "BB" {
+        holidays.push((easter, "Ostersonntag"));
+        holidays.push((from_easter(49), "Pfingstsonntag"));
+    }
+    if in_regions(&["BW", "BY", "HE", "NW", "RP", "SL"]) {
+        holidays.push((from_easter(60), "Fronleichnam"));
+    }
+    if region == "SL" {
+        holidays.push((date(8, 15), "Mariä Himmelfahrt"));
+    }
+    if region == "TH" && year >= 2019 {
+        holidays.push((date(9, 20), "Weltkindertag"));
+    }
+    if in_regions(&["BB", "MV", "SN", "ST", "TH"])
+        || (in_regions(&["HB", "HH", "NI", "SH"]) && year >= 2018)
+    {
+        holidays.push((date(10, 31), "Reformationstag"));
+    }
+    if in_regions(&["BW", "BY", "NW", "RP", "SL"]) {
+        holidays.push((date(11, 1), "Allerheiligen"));
+    }
+    if region == "SN" {
+        // Wednesday before November 23rd
+        let repentance = (16..=22)
+            .map(|day| date(11, day))
+            .find(|d| d.weekday() == Weekday::Wed)
+            .unwrap();
+        holidays.push((repentance, "Buß- und Bettag"));
+    }
+
+    holidays.sort();
+    holidays
+        .into_iter()
+        .map(|(date, name)| Holiday { date, name })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn config(region: &str) -> HolidayConfig {
+        HolidayConfig {
+            country: Some("DE".to_string()),
+            region: Some(region.to_string()),
+        }
+    }
+
+    #[test]
+    fn easter_sunday_works() {
+        assert_eq!(
+            easter_sunday(2024),
+            NaiveDate::from_str("2024-03-31").unwrap()
+        );
+        assert_eq!(
+            easter_sunday(2025),
+            NaiveDate::from_str("2025-04-20").unwrap()
+        );
+    }
+
+    #[test]
+    fn in_week_works() {
+        let week = NaiveDate::from_str("2024-05-27").unwrap();
+        let holidays = config("NW").in_week(week);
+        assert_eq!(
+            holidays,
+            vec![Holiday {
+                date: NaiveDate::from_str("2024-05-30").unwrap(),
+                name: "Fronleichnam"
+            }]
+        );
+        assert!(config("BE").in_week(week).is_empty());
+
+        let new_year = NaiveDate::from_str("2024-12-30").unwrap();
+        assert_eq!(config("BE").in_week(new_year)[0].name, "Neujahr");
+    }
+
+    #[test]
+    fn validate_rejects_unknown_countries() {
+        assert!(config("NW").validate().is_ok());
+        assert!(HolidayConfig::default().validate().is_ok());
+        let unknown = HolidayConfig {
+            country: Some("XX".to_string()),
+            region: None,
+        };
+        assert!(unknown.validate().is_err());
+    }
+}
@@ -0,0 +1,22 @@
+//! The scheduling types and logic of whocares: who takes care of which
+//! week, given a start date, caretakers, reschedules, holidays and absences.
+//! Nothing here touches files, the network, the clock or stdout, loading
+//! and showing schedules and knowing what today is is up to the caller.
+//!
+//! It builds for wasm32 (`cargo build -p whocares-core --target
+//! wasm32-unknown-unknown --release`), exporting a small C ABI around
+//! `schedule::weeks_json` for web pages.
+
+pub mod holidays;
+pub mod rotation;
+pub mod schedule;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+pub mod week;
+
+pub use rotation::{CareWeek, Rotation};
+pub use schedule::Schedule;
+pub use week::Week;
+
+// Longer ranges are refused instead of allocating them, a century.
+pub const MAX_WEEKS: usize = 5218;
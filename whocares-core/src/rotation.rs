@@ -0,0 +1,1330 @@
+use crate::holidays::{Holiday, HolidayConfig};
+use crate::week::{legacy_reschedule_key, parse_week, reschedule_key, start_of_week, Week};
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, Weekday};
+use serde::{de::Error, Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Busy periods per caretaker as [start, end) date ranges.
+pub type Unavailability = HashMap<String, Vec<(NaiveDate, NaiveDate)>>;
+
+// Credits per caretaker, positive for those who covered for others.
+pub type Balances = HashMap<String, f64>;
+
+// Ad-hoc splits hand over on Thursday unless a split rule says otherwise.
+const DEFAULT_HANDOVER: Weekday = Weekday::Thu;
+
+// The second part of a split week, from the handover day to its end.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Split {
+    pub from: NaiveDate,
+    pub caretaker: Arc<str>,
+}
+
+// The outgoing caretaker staying on for the first days of a week, until
+// and including the given day.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Overlap {
+    pub caretaker: Arc<str>,
+    pub until: NaiveDate,
+}
+
+// The days of the week a schedule covers, like ["sat", "sun"] for
+// weekends only. All of them by default.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(try_from = "Vec<String>")]
+pub struct Coverage(u8);
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Coverage(0x7f)
+    }
+}
+
+impl TryFrom<Vec<String>> for Coverage {
+    type Error = String;
+
+    fn try_from(days: Vec<String>) -> Result<Self, String> {
+        let mut mask = 0;
+        for day in &days {
+            let day: Weekday = day
+                .parse()
+                .map_err(|_| format!("invalid weekday \"{day}\", expected e.g. sat"))?;
+            mask |= 1 << day.num_days_from_monday();
+        }
+        match mask {
+            0 => Err("days can't be empty, leave it out to cover every day".to_string()),
+            mask => Ok(Coverage(mask)),
+        }
+    }
+}
+
+impl Coverage {
+    pub fn contains(self, date: NaiveDate) -> bool {
+        self.0 & 1 << date.weekday().num_days_from_monday() != 0
+    }
+
+    // The stretches of consecutive covered days from start to end.
+    pub fn runs(self, start: NaiveDate, end: NaiveDate) -> Vec<(NaiveDate, NaiveDate)> {
+        let mut runs: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+        for day in start.iter_days().take_while(|d| *d <= end) {
+            if !self.contains(day) {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((_, last)) if last.succ_opt() == Some(day) => *last = day,
+                _ => runs.push((day, day)),
+            }
+        }
+        runs
+    }
+}
+
+// A shift like "night" that starts at the same time every day and lasts
+// until the next shift starts. Its caretaker is `offset` places further in
+// the roster than the week's, so shifts rotate together.
+#[derive(Deserialize)]
+pub struct Shift {
+    pub name: Arc<str>,
+    #[serde(deserialize_with = "shift_start")]
+    pub start: NaiveTime,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+fn shift_start<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let s: String = Deserialize::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&s, "%H:%M")
+        .map_err(|_| D::Error::custom(format!("invalid time \"{s}\", expected e.g. 08:00")))
+}
+
+// Every shift needs its own name and start, or they couldn't be told apart.
+pub fn validate_shifts(shifts: &[Shift]) -> Result<(), String> {
+    for (i, shift) in shifts.iter().enumerate() {
+        for other in &shifts[..i] {
+            if other.name == shift.name {
+                return Err(format!("shift {} is defined twice", shift.name));
+            }
+            if other.start == shift.start {
+                return Err(format!(
+                    "shifts {} and {} both start at {}",
+                    other.name,
+                    shift.name,
+                    shift.start.format("%H:%M")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// A shift ends when the next one starts, the first one of the next day
+// after the last.
+pub fn shift_end(shifts: &[Shift], i: usize) -> NaiveTime {
+    let start = shifts[i].start;
+    shifts
+        .iter()
+        .map(|s| s.start)
+        .filter(|t| *t > start)
+        .min()
+        .or_else(|| shifts.iter().map(|s| s.start).min())
+        .unwrap_or(start)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct OnShift {
+    pub shift: Arc<str>,
+    pub caretaker: Arc<str>,
+}
+
+// The caretaker is shared between the weeks instead of copied into each,
+// long ranges of big teams would otherwise allocate a name per week.
+#[derive(Clone, Default)]
+pub struct CareWeek {
+    pub week: u32,
+    pub caretaker: Arc<str>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub holidays: Vec<Holiday>,
+    // The name of the block the week falls into, if any.
+    pub block: Option<Arc<str>>,
+    // Set if the caretaker above hands over during the week.
+    pub split: Option<Split>,
+    // Set if last week's caretaker helps out at the start of this one.
+    pub overlap: Option<Overlap>,
+    pub coverage: Coverage,
+    // Who takes care on each day from Monday to Sunday, if the days
+    // rotate on their own.
+    pub days: Vec<Arc<str>>,
+    // Who has which shift, if there are shifts.
+    pub shifts: Vec<OnShift>,
+    // The name of the blackout the week falls into. Nobody takes care,
+    // the caretaker above is who would have.
+    pub suspended: Option<Arc<str>>,
+    // The other week of a swap waiting for approval.
+    pub pending_swap: Option<Week>,
+}
+
+impl CareWeek {
+    pub fn holiday_note(&self) -> String {
+        if self.holidays.is_empty() {
+            return String::new();
+        }
+        let holidays: Vec<String> = self
+            .holidays
+            .iter()
+            .map(|h| format!("{} {}", h.name, h.date))
+            .collect();
+        format!(" ({})", holidays.join(", "))
+    }
+
+    // Who takes care from when to when on the covered days, one part
+    // unless the week is split or has days off, none if it's suspended.
+    pub fn spans(&self) -> Vec<(&str, NaiveDate, NaiveDate)> {
+        let parts = match &self.split {
+            _ if self.suspended.is_some() => Vec::new(),
+            _ if !self.days.is_empty() => {
+                let mut parts: Vec<(&str, NaiveDate, NaiveDate)> = Vec::new();
+                for (day, caretaker) in self.start_date.iter_days().zip(&self.days) {
+                    match parts.last_mut() {
+                        Some((last, _, end)) if *last == &**caretaker => *end = day,
+                        _ => parts.push((caretaker, day, day)),
+                    }
+                }
+                parts
+            }
+            Some(split) => vec![
+                (&*self.caretaker, self.start_date, split.from - Days::new(1)),
+                (&*split.caretaker, split.from, self.end_date),
+            ],
+            None => vec![(&*self.caretaker, self.start_date, self.end_date)],
+        };
+        parts
+            .into_iter()
+            .flat_map(|(caretaker, start, end)| {
+                let runs = self.coverage.runs(start, end);
+                runs.into_iter()
+                    .map(move |(start, end)| (caretaker, start, end))
+            })
+            .collect()
+    }
+
+    // Everyone taking care some time of the week, in the order of their
+    // spans, or of the shifts if there are some. Nobody if it's suspended.
+    pub fn everyone(&self) -> Vec<&str> {
+        let spans = self.spans();
+        let names: Vec<&str> = match self.shifts.is_empty() {
+            _ if spans.is_empty() => Vec::new(),
+            true => spans.iter().map(|s| s.0).collect(),
+            false => self.shifts.iter().map(|s| &*s.caretaker).collect(),
+        };
+        let mut everyone = Vec::new();
+        for name in names {
+            if !everyone.contains(&name) {
+                everyone.push(name);
+            }
+        }
+        everyone
+    }
+
+    // Who takes care on a day, with shifts everyone on one.
+    pub fn caretakers_on(&self, day: NaiveDate) -> Vec<&str> {
+        let spans = self.spans();
+        let Some((caretaker, _, _)) = spans.iter().find(|s| s.1 <= day && day <= s.2) else {
+            return Vec::new();
+        };
+        match self.shifts.is_empty() {
+            true => vec![*caretaker],
+            false => self.everyone(),
+        }
+    }
+
+    // The first and last covered day.
+    pub fn period(&self) -> (NaiveDate, NaiveDate) {
+        let runs = self.coverage.runs(self.start_date, self.end_date);
+        match (runs.first(), runs.last()) {
+            (Some((first, _)), Some((_, last))) => (*first, *last),
+            _ => (self.start_date, self.end_date),
+        }
+    }
+
+    // "Alice", "Alice/Bob from Thu" for split weeks or "Mon Alice, Tue
+    // Bob, ..." for the covered days if they rotate on their own.
+    pub fn caretakers(&self) -> String {
+        if let Some(blackout) = &self.suspended {
+            return format!("suspended ({blackout})");
+        }
+        if !self.shifts.is_empty() {
+            let shifts: Vec<String> = self
+                .shifts
+                .iter()
+                .map(|s| format!("{} {}", s.shift, s.caretaker))
+                .collect();
+            return shifts.join(", ");
+        }
+        if !self.days.is_empty() {
+            let days: Vec<String> = self
+                .start_date
+                .iter_days()
+                .zip(&self.days)
+                .filter(|(day, _)| self.coverage.contains(*day))
+                .map(|(day, caretaker)| format!("{} {caretaker}", day.format("%a")))
+                .collect();
+            return days.join(", ");
+        }
+        match &self.split {
+            Some(split) => format!(
+                "{}/{} from {}",
+                self.caretaker,
+                split.caretaker,
+                split.from.format("%a")
+            ),
+            None => self.caretaker.to_string(),
+        }
+    }
+
+    pub fn pending_note(&self) -> String {
+        match self.pending_swap {
+            Some(other) => format!(" (swap with {other} pending)"),
+            None => String::new(),
+        }
+    }
+
+    pub fn overlap_note(&self) -> String {
+        match &self.overlap {
+            Some(overlap) => format!(
+                " (with {} until {})",
+                overlap.caretaker,
+                overlap.until.format("%a")
+            ),
+            None => String::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "week": self.week,
+            "iso_week": Week::containing(self.start_date).to_string(),
+            "caretaker": self.caretaker,
+            "start_date": self.start_date.to_string(),
+            "end_date": self.end_date.to_string(),
+            "holidays": self.holidays_json(),
+            "block": self.block,
+            "suspended": self.suspended,
+            "pending_swap": self.pending_swap,
+            "split": self.split.as_ref().map(|s| serde_json::json!({
+                "from": s.from.to_string(),
+                "caretaker": s.caretaker,
+            })),
+            "overlap": self.overlap.as_ref().map(|o| serde_json::json!({
+                "caretaker": o.caretaker,
+                "until": o.until.to_string(),
+            })),
+            "days": (!self.days.is_empty()).then_some(&self.days),
+            "shifts": self
+                .shifts
+                .iter()
+                .map(|s| serde_json::json!({ "shift": s.shift, "caretaker": s.caretaker }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn holidays_json(&self) -> serde_json::Value {
+        self.holidays
+            .iter()
+            .map(|h| serde_json::json!({ "date": h.date.to_string(), "name": h.name }))
+            .collect()
+    }
+}
+
+pub fn is_available(
+    unavailable: &Unavailability,
+    caretaker: &str,
+    start_of_week: NaiveDate,
+) -> bool {
+    let end_of_week = start_of_week + Days::new(7);
+    unavailable.get(caretaker).is_none_or(|busy| {
+        !busy
+            .iter()
+            .any(|(start, end)| *start < end_of_week && start_of_week < *end)
+    })
+}
+
+// Names that are the same once case and spaces are ignored, like "bob "
+// and "Bob".
+pub fn near_duplicates(caretakers: &[String]) -> Vec<(&str, &str)> {
+    let normalize = |name: &str| {
+        name.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let mut pairs = Vec::new();
+    for (i, a) in caretakers.iter().enumerate() {
+        for b in &caretakers[i + 1..] {
+            if a != b && normalize(a) == normalize(b) {
+                pairs.push((a.as_str(), b.as_str()));
+            }
+        }
+    }
+    pairs
+}
+
+// "Alice/Bob" in the reschedule map splits the week between the two.
+pub fn split_reschedule(caretaker: &str) -> (&str, Option<&str>) {
+    match caretaker.split_once('/') {
+        Some((first, second)) => (first.trim(), Some(second.trim())),
+        None => (caretaker, None),
+    }
+}
+
+// "A/" or "/B" would hand the week or half of it to nobody.
+pub fn validate_reschedules(reschedule: &HashMap<String, String>) -> Result<(), String> {
+    let mut weeks: Vec<(&String, &String)> = reschedule.iter().collect();
+    weeks.sort();
+    for (week, caretaker) in weeks {
+        if let (first, Some(second)) = split_reschedule(caretaker) {
+            if first.is_empty() || second.is_empty() {
+                return Err(format!(
+                    "reschedule {week} splits the week with nobody in \"{caretaker}\", expected Alice/Bob"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Splits every week that isn't rescheduled: from the handover day the next
+// available one of the roster takes over.
+#[derive(Deserialize, Clone, Copy)]
+pub struct SplitRule {
+    #[serde(deserialize_with = "handover_day")]
+    pub day: Weekday,
+}
+
+fn handover_day<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Weekday, D::Error> {
+    let s: String = Deserialize::deserialize(deserializer)?;
+    match s.parse() {
+        Ok(Weekday::Mon) => Err(D::Error::custom(
+            "a handover on Monday doesn't split the week",
+        )),
+        Ok(day) => Ok(day),
+        Err(_) => Err(D::Error::custom(format!(
+            "invalid weekday \"{s}\", expected e.g. thu"
+        ))),
+    }
+}
+
+// Which way the rosters are walked: "reverse": true goes backwards from
+// the start, a date like "2025-03-03" turns around from that week on,
+// continuing with whoever took care before the last one.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(try_from = "RawDirection")]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    ReverseFrom(NaiveDate),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDirection {
+    Always(bool),
+    From(String),
+}
+
+impl TryFrom<RawDirection> for Direction {
+    type Error = String;
+
+    fn try_from(raw: RawDirection) -> Result<Self, Self::Error> {
+        match raw {
+            RawDirection::Always(false) => Ok(Direction::Forward),
+            RawDirection::Always(true) => Ok(Direction::Reverse),
+            RawDirection::From(date) => {
+                crate::schedule::parse_date(&date).map(Direction::ReverseFrom)
+            }
+        }
+    }
+}
+
+// "Bob on 2025-03-03" or "Bob on 2025-W10": Bob has that week, the
+// rotation is shifted around it.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "String")]
+pub struct Anchor {
+    pub caretaker: String,
+    pub week: Week,
+}
+
+impl TryFrom<String> for Anchor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let (caretaker, when) = s.rsplit_once(" on ").ok_or_else(|| {
+            format!("invalid anchor \"{s}\", expected like \"Bob on 2025-03-03\"")
+        })?;
+        let monday = match parse_week(when) {
+            Some(monday) => monday,
+            None => crate::schedule::parse_date(when)?,
+        };
+        Ok(Anchor {
+            caretaker: caretaker.trim().to_string(),
+            week: Week::containing(monday),
+        })
+    }
+}
+
+// A date-bounded roster like a school term or the summer break. Its weeks
+// rotate from its own start instead of the schedule's.
+#[derive(Deserialize)]
+pub struct Block {
+    pub name: Arc<str>,
+    #[serde(deserialize_with = "crate::schedule::date")]
+    pub start: NaiveDate,
+    // The last day, inclusive.
+    #[serde(deserialize_with = "crate::schedule::date")]
+    pub end: NaiveDate,
+    pub caretakers: Vec<String>,
+    #[serde(default)]
+    pub odd_week_caretakers: Vec<String>,
+}
+
+impl Block {
+    // From the week the block starts in to the one it ends in.
+    pub fn contains(&self, start_of_week: NaiveDate) -> bool {
+        crate::week::start_of_week(self.start) <= start_of_week && start_of_week <= self.end
+    }
+}
+
+// A range like a renovation or the facility being closed, during which
+// the rotation moves on but nobody takes care.
+#[derive(Deserialize)]
+pub struct Blackout {
+    pub name: Arc<str>,
+    #[serde(deserialize_with = "crate::schedule::date")]
+    pub start: NaiveDate,
+    // The last day, inclusive.
+    #[serde(deserialize_with = "crate::schedule::date")]
+    pub end: NaiveDate,
+}
+
+impl Blackout {
+    // Every week with a day in the blackout is suspended.
+    pub fn contains(&self, start_of_week: NaiveDate) -> bool {
+        crate::week::start_of_week(self.start) <= start_of_week && start_of_week <= self.end
+    }
+}
+
+pub fn validate_blackouts(blackouts: &[Blackout]) -> Result<(), String> {
+    match blackouts.iter().find(|b| b.end < b.start) {
+        Some(b) => Err(format!(
+            "blackout {} ends on {} before it starts on {}",
+            b.name, b.end, b.start
+        )),
+        None => Ok(()),
+    }
+}
+
+// Blocks need a roster and must not overlap, a week could be in two.
+pub fn validate_blocks(blocks: &[Block]) -> Result<(), String> {
+    for block in blocks {
+        if block.caretakers.is_empty() {
+            return Err(format!("no caretakers configured for {}", block.name));
+        }
+        if block.end < block.start {
+            return Err(format!(
+                "{} ends on {} before it starts on {}",
+                block.name, block.end, block.start
+            ));
+        }
+    }
+    let mut sorted: Vec<&Block> = blocks.iter().collect();
+    sorted.sort_by_key(|b| b.start);
+    for pair in sorted.windows(2) {
+        if crate::week::start_of_week(pair[1].start) <= pair[0].end {
+            return Err(format!("{} and {} overlap", pair[0].name, pair[1].name));
+        }
+    }
+    Ok(())
+}
+
+// Everything that decides who takes care of a week, borrowed from wherever
+// the schedule was loaded. The caretakers must not be empty.
+#[derive(Clone, Copy)]
+pub struct Rotation<'a> {
+    pub startdate: NaiveDate,
+    pub caretakers: &'a [String],
+    // If not empty, they take every other week, starting with the second,
+    // and the caretakers the rest, each roster rotating on its own.
+    pub odd_caretakers: &'a [String],
+    // Take over from the rosters above within their dates.
+    pub blocks: &'a [Block],
+    pub blackouts: &'a [Blackout],
+    // Not in the rotation, they stand in for the away before the rest of
+    // the roster does, taking turns among themselves.
+    pub substitutes: &'a [String],
+    pub split: Option<SplitRule>,
+    // Days at the start of a week the outgoing caretaker stays on, up to 6.
+    pub overlap_days: u32,
+    // The rotation still moves on weekly, the other days are nobody's.
+    pub coverage: Coverage,
+    // Each weekday rotates over the roster on its own, Monday with the
+    // regular caretaker and every following day with the next one.
+    pub per_weekday: bool,
+    pub direction: Direction,
+    // Turns every roster on by this many places, see anchor_offset.
+    pub offset: i64,
+    // The week's caretaker takes the shifts with offset 0.
+    pub shifts: &'a [Shift],
+    pub reschedule: &'a HashMap<String, String>,
+    pub holidays: &'a HolidayConfig,
+    pub unavailable: &'a Unavailability,
+    // When set, whoever owes the most credits substitutes for the away.
+    pub balances: Option<&'a Balances>,
+}
+
+impl<'a> Rotation<'a> {
+    pub fn block(&self, start_of_week: NaiveDate) -> Option<&'a Block> {
+        self.blocks.iter().find(|b| b.contains(start_of_week))
+    }
+
+    // The roster on duty that week and the position in it.
+    pub fn roster(&self, start_of_week: NaiveDate) -> (&'a [String], usize) {
+        let (startdate, caretakers, odd_caretakers) = match self.block(start_of_week) {
+            Some(b) => (b.start, &b.caretakers[..], &b.odd_week_caretakers[..]),
+            None => (self.startdate, self.caretakers, self.odd_caretakers),
+        };
+        let start = crate::week::start_of_week(startdate);
+        let weeks = (start_of_week - start).num_weeks();
+        let odd_week = !odd_caretakers.is_empty() && weeks.rem_euclid(2) == 1;
+        let roster = if odd_week { odd_caretakers } else { caretakers };
+        // The turns of the week's roster in the weeks before a week.
+        let turns_before = |weeks: i64| match odd_caretakers {
+            [] => weeks,
+            _ if odd_week => weeks.div_euclid(2),
+            _ => (weeks + 1).div_euclid(2),
+        };
+        let turn = turns_before(weeks);
+        let turn = match self.direction {
+            Direction::Forward => turn,
+            Direction::Reverse => -turn,
+            Direction::ReverseFrom(date) => {
+                let flip = crate::week::start_of_week(date);
+                match turns_before((flip - start).num_weeks()) {
+                    f if f <= 0 => -turn,
+                    f if turn >= f => 2 * (f - 1) - turn,
+                    _ => turn,
+                }
+            }
+        };
+        let turn = turn + self.offset;
+        (roster, turn.rem_euclid(roster.len() as i64) as usize)
+    }
+
+    // The offset that puts the anchor's caretaker on duty in its week.
+    pub fn anchor_offset(&self, anchor: &Anchor) -> Result<i64, String> {
+        let unshifted = Rotation { offset: 0, ..*self };
+        let (roster, idx) = unshifted.roster(anchor.week.monday());
+        let position = roster
+            .iter()
+            .position(|c| *c == anchor.caretaker)
+            .ok_or_else(|| {
+                format!(
+                    "anchor {} isn't on the roster of {}",
+                    anchor.caretaker, anchor.week
+                )
+            })?;
+        Ok(position as i64 - idx as i64)
+    }
+
+    pub fn regular_caretaker(&self, start_of_week: NaiveDate) -> &'a str {
+        let (roster, idx) = self.roster(start_of_week);
+        &roster[idx]
+    }
+
+    pub fn is_available(&self, caretaker: &str, start_of_week: NaiveDate) -> bool {
+        is_available(self.unavailable, caretaker, start_of_week)
+    }
+
+    // The available substitute whose turn it is that week, if any.
+    pub fn substitute(&self, start_of_week: NaiveDate) -> Option<&'a str> {
+        let n = self.substitutes.len();
+        let weeks = (start_of_week - crate::week::start_of_week(self.startdate)).num_weeks();
+        let turn = weeks.rem_euclid(n.max(1) as i64) as usize;
+        (turn..turn + n)
+            .map(|i| self.substitutes[i % n].as_str())
+            .find(|c| self.is_available(c, start_of_week))
+    }
+
+    // The regular caretaker, or if they are away that week a substitute,
+    // the next available one of their roster in rotation order, or the
+    // available one owing the most credits. The regular one if nobody is
+    // available.
+    pub fn available_caretaker(&self, start_of_week: NaiveDate) -> &'a str {
+        let (roster, idx) = self.roster(start_of_week);
+        let regular = roster[idx].as_str();
+        let available: Vec<&'a str> = (idx..idx + roster.len())
+            .map(|i| roster[i % roster.len()].as_str())
+            .filter(|c| self.is_available(c, start_of_week))
+            .collect();
+
+        if available.first() == Some(&regular) {
+            return regular;
+        }
+        if let Some(substitute) = self.substitute(start_of_week) {
+            return substitute;
+        }
+        let substitute = match self.balances {
+            Some(balances) => {
+                let balance = |c: &str| balances.get(c).copied().unwrap_or_default();
+                available
+                    .into_iter()
+                    .reduce(|best, c| if balance(c) < balance(best) { c } else { best })
+            }
+            None => available.first().copied(),
+        };
+        substitute.unwrap_or(regular)
+    }
+
+    pub fn rescheduled(&self, week: Week) -> Option<&'a str> {
+        let legacy = legacy_reschedule_key(week.monday());
+        self.reschedule
+            .get(&reschedule_key(week))
+            .or_else(|| {
+                (parse_week(&legacy) == Some(week.monday()))
+                    .then(|| self.reschedule.get(&legacy))
+                    .flatten()
+            })
+            .map(String::as_str)
+    }
+
+    pub fn caretaker(&self, start_of_week: NaiveDate) -> &'a str {
+        match self.rescheduled(Week::containing(start_of_week)) {
+            Some(rescheduled_caretaker) => split_reschedule(rescheduled_caretaker).0,
+            None => self.available_caretaker(start_of_week),
+        }
+    }
+
+    // The handover day and who takes over, if the week is split.
+    pub fn split(&self, start_of_week: NaiveDate) -> Option<(NaiveDate, &'a str)> {
+        let day = self.split.map_or(DEFAULT_HANDOVER, |s| s.day);
+        let second = match self.rescheduled(Week::containing(start_of_week)) {
+            Some(rescheduled) => split_reschedule(rescheduled).1?,
+            None => {
+                self.split?;
+                let first = self.available_caretaker(start_of_week);
+                let (roster, idx) = self.roster(start_of_week);
+                (idx + 1..idx + roster.len())
+                    .map(|i| roster[i % roster.len()].as_str())
+                    .find(|c| *c != first && self.is_available(c, start_of_week))?
+            }
+        };
+        let from = start_of_week + Days::new(day.num_days_from_monday().into());
+        Some((from, second))
+    }
+
+    // Whoever took care at the end of last week, if they hand over to
+    // someone else and stay on for a few days.
+    pub fn overlap(&self, start_of_week: NaiveDate) -> Option<(&'a str, NaiveDate)> {
+        if self.overlap_days == 0 {
+            return None;
+        }
+        let last_week = start_of_week.checked_sub_days(Days::new(7))?;
+        let outgoing = match self.split(last_week) {
+            Some((_, second)) => second,
+            None => self.caretaker(last_week),
+        };
+        let until = start_of_week + Days::new(self.overlap_days.min(6).into()) - Days::new(1);
+        (outgoing != self.caretaker(start_of_week)).then_some((outgoing, until))
+    }
+
+    // The caretakers from Monday to Sunday for the per-weekday mode. The
+    // away are skipped like for whole weeks, reschedules still take the
+    // whole week.
+    pub fn days(&self, start_of_week: NaiveDate) -> Option<Vec<&'a str>> {
+        if !self.per_weekday || self.rescheduled(Week::containing(start_of_week)).is_some() {
+            return None;
+        }
+        let (roster, idx) = self.roster(start_of_week);
+        let days = (idx..idx + 7).map(|day| {
+            let regular = roster[day % roster.len()].as_str();
+            (day..day + roster.len())
+                .map(|i| roster[i % roster.len()].as_str())
+                .find(|c| self.is_available(c, start_of_week))
+                .unwrap_or(regular)
+        });
+        Some(days.collect())
+    }
+
+    // Who has a shift when nobody is away and nothing is rescheduled.
+    pub fn regular_on_shift(&self, start_of_week: NaiveDate, shift: &Shift) -> &'a str {
+        let (roster, idx) = self.roster(start_of_week);
+        &roster[(idx + shift.offset % roster.len()) % roster.len()]
+    }
+
+    // Who has each shift: the week's caretaker for offset 0, the rest
+    // counted on from the regular one and skipping the away.
+    pub fn shifts(&self, start_of_week: NaiveDate) -> Vec<(&'a str, &'a str)> {
+        let caretaker = self.caretaker(start_of_week);
+        let (roster, idx) = self.roster(start_of_week);
+        self.shifts
+            .iter()
+            .map(|shift| {
+                // Offsets past the roster wrap around, however large.
+                let start = idx + shift.offset % roster.len();
+                let on_shift = match shift.offset {
+                    0 => caretaker,
+                    _ => (start..start + roster.len())
+                        .map(|i| roster[i % roster.len()].as_str())
+                        .find(|c| self.is_available(c, start_of_week))
+                        .unwrap_or(roster[start % roster.len()].as_str()),
+                };
+                (&*shift.name, on_shift)
+            })
+            .collect()
+    }
+
+    fn care_week(
+        &self,
+        start_of_week: NaiveDate,
+        caretaker: Arc<str>,
+        split: Option<Split>,
+        overlap: Option<Overlap>,
+        days: Vec<Arc<str>>,
+        shifts: Vec<OnShift>,
+    ) -> CareWeek {
+        CareWeek {
+            week: start_of_week.iso_week().week(),
+            caretaker,
+            start_date: start_of_week,
+            end_date: start_of_week
+                .checked_add_days(Days::new(6))
+                .unwrap_or(NaiveDate::MAX),
+            holidays: self.holidays.in_week(start_of_week),
+            block: self.block(start_of_week).map(|b| b.name.clone()),
+            suspended: self
+                .blackouts
+                .iter()
+                .find(|b| b.contains(start_of_week))
+                .map(|b| b.name.clone()),
+            split,
+            overlap,
+            coverage: self.coverage,
+            days,
+            shifts,
+            pending_swap: None,
+        }
+    }
+
+    pub fn week(&self, start_of_week: NaiveDate) -> CareWeek {
+        let split = self.split(start_of_week).map(|(from, caretaker)| Split {
+            from,
+            caretaker: caretaker.into(),
+        });
+        let overlap = self
+            .overlap(start_of_week)
+            .map(|(caretaker, until)| Overlap {
+                caretaker: caretaker.into(),
+                until,
+            });
+        let days = self.days(start_of_week).unwrap_or_default();
+        let shifts = self.shifts(start_of_week).into_iter();
+        self.care_week(
+            start_of_week,
+            self.caretaker(start_of_week).into(),
+            split,
+            overlap,
+            days.into_iter().map(Arc::from).collect(),
+            shifts
+                .map(|(shift, caretaker)| OnShift {
+                    shift: shift.into(),
+                    caretaker: caretaker.into(),
+                })
+                .collect(),
+        )
+    }
+
+    // Lazily, ends with the last week chrono can represent. The names are
+    // allocated once and shared by all weeks.
+    pub fn weeks_from(self, from: NaiveDate) -> impl Iterator<Item = CareWeek> + 'a {
+        let mut names: HashMap<&'a str, Arc<str>> = HashMap::new();
+        start_of_week(from).iter_weeks().map(move |d| {
+            let mut intern =
+                |name: &'a str| names.entry(name).or_insert_with(|| name.into()).clone();
+            let caretaker = intern(self.caretaker(d));
+            let split = self.split(d).map(|(from, caretaker)| Split {
+                from,
+                caretaker: intern(caretaker),
+            });
+            let overlap = self.overlap(d).map(|(caretaker, until)| Overlap {
+                caretaker: intern(caretaker),
+                until,
+            });
+            let days = self.days(d).unwrap_or_default();
+            let days = days.into_iter().map(&mut intern).collect();
+            let shifts = self.shifts(d).into_iter();
+            let shifts = shifts
+                .map(|(shift, caretaker)| OnShift {
+                    shift: intern(shift),
+                    caretaker: intern(caretaker),
+                })
+                .collect();
+            self.care_week(d, caretaker, split, overlap, days, shifts)
+        })
+    }
+
+    pub fn weeks(&self, from: NaiveDate, weeks: usize) -> Vec<CareWeek> {
+        self.weeks_from(from).take(weeks).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // From 2024-01-01 with nobody, the tests fill in what they need.
+    fn rotation() -> Rotation<'static> {
+        Rotation {
+            startdate: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            caretakers: &[],
+            odd_caretakers: &[],
+            blocks: &[],
+            blackouts: &[],
+            substitutes: &[],
+            split: None,
+            overlap_days: 0,
+            coverage: Coverage::default(),
+            per_weekday: false,
+            direction: Direction::Forward,
+            offset: 0,
+            shifts: &[],
+            reschedule: Box::leak(Box::default()),
+            holidays: Box::leak(Box::default()),
+            unavailable: Box::leak(Box::default()),
+            balances: None,
+        }
+    }
+
+    #[test]
+    fn is_available_works() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let unavailable = Unavailability::from([(
+            "A".to_string(),
+            vec![(date("2024-07-05"), date("2024-07-09"))],
+        )]);
+        assert!(!is_available(&unavailable, "A", date("2024-07-01")));
+        assert!(!is_available(&unavailable, "A", date("2024-07-08")));
+        assert!(is_available(&unavailable, "A", date("2024-07-15")));
+        assert!(is_available(&unavailable, "B", date("2024-07-01")));
+    }
+
+    #[test]
+    fn rotation_works() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B", "C"].map(String::from);
+        let reschedule = HashMap::from([("2024-W02".to_string(), "C".to_string())]);
+        let unavailable = Unavailability::from([(
+            "A".to_string(),
+            vec![(date("2024-01-22"), date("2024-01-29"))],
+        )]);
+        let rotation = Rotation {
+            startdate: date("2024-01-03"),
+            caretakers: &caretakers,
+            reschedule: &reschedule,
+            unavailable: &unavailable,
+            ..rotation()
+        };
+        let caretakers: Vec<String> = rotation
+            .weeks(date("2024-01-01"), 4)
+            .into_iter()
+            .map(|w| w.caretaker.to_string())
+            .collect();
+        assert_eq!(caretakers, ["A", "C", "C", "B"]);
+        assert_eq!(rotation.regular_caretaker(date("2024-01-22")), "A");
+
+        // names are shared, rescheduled or not
+        let weeks = rotation.weeks(date("2024-01-01"), 6);
+        assert!(Arc::ptr_eq(&weeks[1].caretaker, &weeks[5].caretaker));
+    }
+
+    #[test]
+    fn substitutes_stand_in() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B"].map(String::from);
+        let substitutes = ["S", "T"].map(String::from);
+        let away = |from, to| vec![(date(from), date(to))];
+        let unavailable = Unavailability::from([
+            ("A".to_string(), away("2024-01-01", "2024-02-05")),
+            ("S".to_string(), away("2024-01-15", "2024-01-22")),
+            ("T".to_string(), away("2024-01-29", "2024-02-05")),
+        ]);
+        let rotation = Rotation {
+            caretakers: &caretakers,
+            substitutes: &substitutes,
+            unavailable: &unavailable,
+            ..rotation()
+        };
+        let caretakers: Vec<String> = rotation
+            .weeks(date("2024-01-01"), 5)
+            .into_iter()
+            .map(|w| w.caretaker.to_string())
+            .collect();
+        assert_eq!(caretakers, ["S", "B", "T", "B", "S"]);
+    }
+
+    #[test]
+    fn odd_caretakers_alternate() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let parents = ["Mum", "Dad"].map(String::from);
+        let grandparents = ["Granny"].map(String::from);
+        let rotation = Rotation {
+            caretakers: &parents,
+            odd_caretakers: &grandparents,
+            ..rotation()
+        };
+        let caretakers: Vec<String> = rotation
+            .weeks(date("2023-12-18"), 7)
+            .into_iter()
+            .map(|w| w.caretaker.to_string())
+            .collect();
+        assert_eq!(
+            caretakers,
+            ["Dad", "Granny", "Mum", "Granny", "Dad", "Granny", "Mum"]
+        );
+    }
+
+    #[test]
+    fn blocks_take_over() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B", "C"].map(String::from);
+        let blocks = [Block {
+            name: "Summer".into(),
+            start: date("2024-01-17"),
+            end: date("2024-01-31"),
+            caretakers: vec!["X".to_string(), "Y".to_string()],
+            odd_week_caretakers: Vec::new(),
+        }];
+        let rotation = Rotation {
+            caretakers: &caretakers,
+            blocks: &blocks,
+            ..rotation()
+        };
+        let weeks = rotation.weeks(date("2024-01-01"), 6);
+        let caretakers: Vec<&str> = weeks.iter().map(|w| &*w.caretaker).collect();
+        assert_eq!(caretakers, ["A", "B", "X", "Y", "X", "C"]);
+        assert_eq!(weeks[4].block.as_deref(), Some("Summer"));
+        assert_eq!(weeks[5].block, None);
+
+        let overlapping = [
+            Block {
+                name: "Winter".into(),
+                start: date("2024-01-01"),
+                end: date("2024-01-16"),
+                caretakers: vec!["Z".to_string()],
+                odd_week_caretakers: Vec::new(),
+            },
+            blocks.into_iter().next().unwrap(),
+        ];
+        assert_eq!(
+            validate_blocks(&overlapping),
+            Err("Winter and Summer overlap".to_string())
+        );
+    }
+
+    #[test]
+    fn blackouts_suspend_weeks() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B"].map(String::from);
+        let blackouts = [Blackout {
+            name: "Renovation".into(),
+            start: date("2024-01-12"),
+            end: date("2024-01-15"),
+        }];
+        let rotation = Rotation {
+            caretakers: &caretakers,
+            blackouts: &blackouts,
+            ..rotation()
+        };
+        let weeks = rotation.weeks(date("2024-01-01"), 4);
+        let suspended: Vec<Option<&str>> = weeks.iter().map(|w| w.suspended.as_deref()).collect();
+        assert_eq!(
+            suspended,
+            [None, Some("Renovation"), Some("Renovation"), None]
+        );
+        assert_eq!(weeks[1].caretakers(), "suspended (Renovation)");
+        assert!(weeks[2].spans().is_empty());
+        assert_eq!(&*weeks[3].caretaker, "B");
+
+        let backwards = [Blackout {
+            name: "Closed".into(),
+            start: date("2024-02-01"),
+            end: date("2024-01-01"),
+        }];
+        assert!(validate_blackouts(&backwards).is_err());
+    }
+
+    #[test]
+    fn weeks_split() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B", "C"].map(String::from);
+        let reschedule = HashMap::from([("2024-W02".to_string(), "C / A".to_string())]);
+        let unavailable = Unavailability::from([(
+            "C".to_string(),
+            vec![(date("2024-01-15"), date("2024-01-22"))],
+        )]);
+        let mut rotation = Rotation {
+            caretakers: &caretakers,
+            reschedule: &reschedule,
+            unavailable: &unavailable,
+            ..rotation()
+        };
+        let week = rotation.week(date("2024-01-08"));
+        assert_eq!(&*week.caretaker, "C");
+        assert_eq!(week.caretakers(), "C/A from Thu");
+        assert_eq!(
+            week.spans(),
+            [
+                ("C", date("2024-01-08"), date("2024-01-10")),
+                ("A", date("2024-01-11"), date("2024-01-14")),
+            ]
+        );
+        assert_eq!(rotation.week(date("2024-01-01")).split, None);
+
+        rotation.split = serde_json::from_str(r#"{"day": "sat"}"#).unwrap();
+        let splits: Vec<String> = rotation
+            .weeks(date("2024-01-01"), 3)
+            .iter()
+            .map(CareWeek::caretakers)
+            .collect();
+        // C is away in the third week, A covers and B takes the weekend
+        assert_eq!(splits, ["A/B from Sat", "C/A from Sat", "A/B from Sat"]);
+        assert!(serde_json::from_str::<SplitRule>(r#"{"day": "mon"}"#).is_err());
+        for half in ["A/", "/B", " / "] {
+            let reschedule = HashMap::from([("2024-W02".to_string(), half.to_string())]);
+            assert!(validate_reschedules(&reschedule).is_err(), "{half}");
+        }
+        assert!(validate_reschedules(&reschedule).is_ok());
+    }
+
+    #[test]
+    fn outgoing_caretaker_overlaps() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B"].map(String::from);
+        let reschedule = HashMap::from([("2024-W03".to_string(), "B".to_string())]);
+        let rotation = Rotation {
+            caretakers: &caretakers,
+            overlap_days: 1,
+            reschedule: &reschedule,
+            ..rotation()
+        };
+        let weeks = rotation.weeks(date("2024-01-08"), 4);
+        assert_eq!(
+            weeks[0].overlap,
+            Some(Overlap {
+                caretaker: "A".into(),
+                until: date("2024-01-08"),
+            })
+        );
+        assert_eq!(weeks[0].overlap_note(), " (with A until Mon)");
+        // B keeps the next two weeks, so there is nobody to hand over to
+        assert_eq!(weeks[1].overlap, None);
+        assert_eq!(weeks[2].overlap, None);
+        assert_eq!(weeks[3].overlap.as_ref().unwrap().caretaker, "B".into());
+    }
+
+    #[test]
+    fn coverage_limits_the_spans() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let weekends: Coverage = serde_json::from_str(r#"["sat", "Sunday"]"#).unwrap();
+        let week = CareWeek {
+            caretaker: "A".into(),
+            start_date: date("2024-01-01"),
+            end_date: date("2024-01-07"),
+            split: Some(Split {
+                from: date("2024-01-07"),
+                caretaker: "B".into(),
+            }),
+            coverage: weekends,
+            ..Default::default()
+        };
+        assert_eq!(
+            week.spans(),
+            [
+                ("A", date("2024-01-06"), date("2024-01-06")),
+                ("B", date("2024-01-07"), date("2024-01-07")),
+            ]
+        );
+        assert_eq!(week.period(), (date("2024-01-06"), date("2024-01-07")));
+        assert_eq!(week.everyone(), ["A", "B"]);
+        assert_eq!(week.caretakers_on(date("2024-01-07")), ["B"]);
+        assert!(week.caretakers_on(date("2024-01-05")).is_empty());
+
+        let odd: Coverage = serde_json::from_str(r#"["mon", "wed", "thu"]"#).unwrap();
+        assert_eq!(
+            odd.runs(date("2024-01-01"), date("2024-01-07")),
+            [
+                (date("2024-01-01"), date("2024-01-01")),
+                (date("2024-01-03"), date("2024-01-04")),
+            ]
+        );
+        assert!(serde_json::from_str::<Coverage>("[]").is_err());
+    }
+
+    #[test]
+    fn direction_turns_around() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B", "C"].map(String::from);
+        let odd = ["X", "Y"].map(String::from);
+        let rotation = |odd_caretakers, direction| Rotation {
+            caretakers: &caretakers,
+            odd_caretakers,
+            direction,
+            ..rotation()
+        };
+        let caretakers = |rotation: Rotation, weeks| {
+            rotation
+                .weeks(date("2024-01-01"), weeks)
+                .iter()
+                .map(|w| w.caretaker.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            caretakers(rotation(&[], Direction::Reverse), 5),
+            ["A", "C", "B", "A", "C"]
+        );
+        let flip = Direction::ReverseFrom(date("2024-01-24"));
+        assert_eq!(
+            caretakers(rotation(&[], flip), 6),
+            ["A", "B", "C", "B", "A", "C"]
+        );
+        let flip = Direction::ReverseFrom(date("2024-01-29"));
+        assert_eq!(
+            caretakers(rotation(&odd, flip), 8),
+            ["A", "X", "B", "Y", "A", "X", "C", "Y"]
+        );
+    }
+
+    #[test]
+    fn anchor_pins_a_week() {
+        let schedule = |anchor: &str| {
+            crate::Schedule::from_json(&format!(
+                r#"{{"startdate": "2024-01-01", "caretakers": ["A", "B", "C"],
+                    "reverse": true, "anchor": "{anchor}"}}"#
+            ))
+        };
+        let schedule = schedule("A on 2024-W03").unwrap();
+        assert_eq!(schedule.offset, -1);
+        let caretakers: Vec<String> = schedule
+            .rotation()
+            .weeks(NaiveDate::from_str("2024-01-01").unwrap(), 4)
+            .iter()
+            .map(|w| w.caretaker.to_string())
+            .collect();
+        assert_eq!(caretakers, ["C", "B", "A", "C"]);
+        assert!(crate::Schedule::from_json(
+            r#"{"startdate": "2024-01-01", "caretakers": ["A"], "anchor": "Z on 2024-01-03"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn weekdays_rotate_on_their_own() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B", "C"].map(String::from);
+        let reschedule = HashMap::from([("2024-W03".to_string(), "A".to_string())]);
+        let unavailable = Unavailability::from([(
+            "B".to_string(),
+            vec![(date("2024-01-08"), date("2024-01-15"))],
+        )]);
+        let rotation = Rotation {
+            caretakers: &caretakers,
+            per_weekday: true,
+            reschedule: &reschedule,
+            unavailable: &unavailable,
+            ..rotation()
+        };
+        let weeks = rotation.weeks(date("2024-01-01"), 3);
+        assert_eq!(
+            weeks[0].caretakers(),
+            "Mon A, Tue B, Wed C, Thu A, Fri B, Sat C, Sun A"
+        );
+        // B is away, C takes their days
+        assert_eq!(
+            weeks[1].spans(),
+            [
+                ("C", date("2024-01-08"), date("2024-01-09")),
+                ("A", date("2024-01-10"), date("2024-01-10")),
+                ("C", date("2024-01-11"), date("2024-01-12")),
+                ("A", date("2024-01-13"), date("2024-01-13")),
+                ("C", date("2024-01-14"), date("2024-01-14")),
+            ]
+        );
+        assert!(weeks[2].days.is_empty());
+    }
+
+    #[test]
+    fn shifts_rotate_together() {
+        let date = |s| NaiveDate::from_str(s).unwrap();
+        let caretakers = ["A", "B", "C"].map(String::from);
+        let shifts: Vec<Shift> = serde_json::from_str(
+            r#"[{"name": "day", "start": "08:00"}, {"name": "night", "start": "20:00", "offset": 1}]"#,
+        )
+        .unwrap();
+        let reschedule = HashMap::from([("2024-W02".to_string(), "A".to_string())]);
+        let unavailable = Unavailability::from([(
+            "C".to_string(),
+            vec![(date("2024-01-08"), date("2024-01-15"))],
+        )]);
+        let rotation = Rotation {
+            caretakers: &caretakers,
+            shifts: &shifts,
+            reschedule: &reschedule,
+            unavailable: &unavailable,
+            ..rotation()
+        };
+        // C would have the night of the second week.
+        assert_eq!(
+            rotation.regular_on_shift(date("2024-01-08"), &shifts[1]),
+            "C"
+        );
+        assert_eq!(
+            shift_end(&shifts, 0),
+            NaiveTime::from_hms_opt(20, 0, 0).unwrap()
+        );
+        assert_eq!(
+            shift_end(&shifts, 1),
+            NaiveTime::from_hms_opt(8, 0, 0).unwrap()
+        );
+        let shifts: Vec<String> = rotation
+            .weeks(date("2024-01-01"), 3)
+            .iter()
+            .map(CareWeek::caretakers)
+            .collect();
+        // A is rescheduled into the second week and covers for C at night
+        assert_eq!(
+            shifts,
+            ["day A, night B", "day A, night A", "day C, night A"]
+        );
+        // usize::MAX - 1 places on is two on for three caretakers.
+        let far: Vec<Shift> = serde_json::from_str(
+            r#"[{"name": "night", "start": "20:00", "offset": 18446744073709551614}]"#,
+        )
+        .unwrap();
+        let rotation = Rotation {
+            shifts: &far,
+            ..rotation
+        };
+        assert_eq!(rotation.week(date("2024-01-01")).caretakers(), "night C");
+
+        let twice: Vec<Shift> = serde_json::from_str(
+            r#"[{"name": "early", "start": "06:00"}, {"name": "late", "start": "06:00"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            validate_shifts(&twice),
+            Err("shifts early and late both start at 06:00".to_string())
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::holidays::HolidayConfig;
+use crate::rotation::{
+    validate_blackouts, validate_blocks, validate_reschedules, validate_shifts, Anchor, Blackout,
+    Block, CareWeek, Coverage, Direction, Rotation, Shift, SplitRule, Unavailability,
+};
+use chrono::{DateTime, NaiveDate};
+use serde::{de::Error, Deserialize, Deserializer};
+use std::collections::HashMap;
+
+// The part of a whocares config that decides the rotation, read from a
+// string so it works where there are no files, like in a browser. Other
+// keys of the config are ignored, availability calendars aren't loaded.
+#[derive(Deserialize)]
+pub struct Schedule {
+    #[serde(deserialize_with = "date")]
+    pub startdate: NaiveDate,
+    pub caretakers: Vec<String>,
+    #[serde(default)]
+    pub odd_week_caretakers: Vec<String>,
+    #[serde(default)]
+    pub blocks: Vec<Block>,
+    #[serde(default)]
+    pub blackouts: Vec<Blackout>,
+    #[serde(default)]
+    pub substitutes: Vec<String>,
+    #[serde(default)]
+    pub split: Option<SplitRule>,
+    #[serde(default)]
+    pub overlap_days: u32,
+    #[serde(default)]
+    pub days: Coverage,
+    #[serde(default)]
+    pub per_weekday: bool,
+    #[serde(default)]
+    pub reverse: Direction,
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default)]
+    pub anchor: Option<Anchor>,
+    #[serde(default)]
+    pub shifts: Vec<Shift>,
+    #[serde(default)]
+    pub reschedule: HashMap<String, String>,
+    #[serde(default)]
+    pub holidays: HolidayConfig,
+    #[serde(skip)]
+    pub unavailable: Unavailability,
+}
+
+// Accepted when reading dates, the first is how they're written.
+pub const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y"];
+
+// Parses with the first matching format, RFC 3339 timestamps give their
+// date.
+pub fn parse_date_with(s: &str, formats: &[&str]) -> Result<NaiveDate, String> {
+    formats
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+        .or_else(|| DateTime::parse_from_rfc3339(s).ok().map(|d| d.date_naive()))
+        .ok_or_else(|| {
+            format!(
+                "invalid date \"{s}\", expected one of {} or an RFC 3339 timestamp",
+                formats.join(", ")
+            )
+        })
+}
+
+pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    parse_date_with(s, DATE_FORMATS)
+}
+
+// For deserialize_with, the binary reads its start date with it too.
+pub fn date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+    let s: String = Deserialize::deserialize(deserializer)?;
+    parse_date(&s).map_err(D::Error::custom)
+}
+
+impl Schedule {
+    pub fn from_json(config: &str) -> Result<Self, String> {
+        let mut schedule: Schedule = serde_json::from_str(config).map_err(|e| e.to_string())?;
+        if schedule.caretakers.is_empty() {
+            return Err("no caretakers configured".to_string());
+        }
+        schedule.holidays.validate().map_err(|e| e.to_string())?;
+        validate_blocks(&schedule.blocks)?;
+        validate_blackouts(&schedule.blackouts)?;
+        validate_shifts(&schedule.shifts)?;
+        validate_reschedules(&schedule.reschedule)?;
+        if let Some(anchor) = &schedule.anchor {
+            if schedule.offset != 0 {
+                return Err("offset and anchor both set".to_string());
+            }
+            schedule.offset = schedule.rotation().anchor_offset(anchor)?;
+        }
+        Ok(schedule)
+    }
+
+    pub fn rotation(&self) -> Rotation<'_> {
+        Rotation {
+            startdate: self.startdate,
+            caretakers: &self.caretakers,
+            odd_caretakers: &self.odd_week_caretakers,
+            blocks: &self.blocks,
+            blackouts: &self.blackouts,
+            substitutes: &self.substitutes,
+            split: self.split,
+            overlap_days: self.overlap_days,
+            coverage: self.days,
+            per_weekday: self.per_weekday,
+            direction: self.reverse,
+            offset: self.offset,
+            shifts: &self.shifts,
+            reschedule: &self.reschedule,
+            holidays: &self.holidays,
+            unavailable: &self.unavailable,
+            balances: None,
+        }
+    }
+
+    // The weeks from today as JSON, in the shape of the server's
+    // /api/weeks. Today is passed in, there is no clock here.
+    pub fn weeks_json(&self, today: NaiveDate, weeks: usize) -> serde_json::Value {
+        self.rotation()
+            .weeks(today.max(self.startdate), weeks)
+            .iter()
+            .map(CareWeek::to_json)
+            .collect()
+    }
+}
+
+// Config and today in, {"weeks": [...]} or {"error": "..."} out, for
+// callers that only pass strings around.
+pub fn weeks_json(config: &str, today: &str, weeks: usize) -> String {
+    let result = Schedule::from_json(config)
+        .and_then(|schedule| Ok(schedule.weeks_json(parse_date(today)?, weeks)));
+    match result {
+        Ok(weeks) => serde_json::json!({ "weeks": weeks }),
+        Err(e) => serde_json::json!({ "error": e }),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weeks_json_works() {
+        let config = r#"{
+            "startdate": "2024-07-01",
+            "caretakers": ["Edgar", "Jimmy"],
+            "reschedule": { "2024-W28": "Edgar" },
+            "slack": { "channel": "ignored" }
+        }"#;
+        let result: serde_json::Value =
+            serde_json::from_str(&weeks_json(config, "2024-07-03", 2)).unwrap();
+        let caretakers: Vec<&str> = result["weeks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|w| w["caretaker"].as_str().unwrap())
+            .collect();
+        assert_eq!(caretakers, ["Edgar", "Edgar"]);
+        assert_eq!(result["weeks"][1]["iso_week"], "2024-W28");
+
+        let result = weeks_json(r#"{"startdate": "x", "caretakers": []}"#, "2024-07-03", 2);
+        assert!(result.contains("\"error\""));
+    }
+
+    #[test]
+    fn dates_parse_like_the_serde_helpers() {
+        let date = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+        for s in ["2024-07-01", "01.07.2024", "2024-07-01T08:00:00+02:00"] {
+            assert_eq!(parse_date(s), Ok(date), "{s}");
+        }
+        assert!(parse_date("07/01/2024").is_err());
+        let schedule = Schedule::from_json(
+            r#"{
+                "startdate": "2024-07-01T08:00:00Z",
+                "caretakers": ["Edgar"],
+                "blackouts": [{"name": "closed", "start": "2024-07-08T00:00:00Z", "end": "15.07.2024"}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(schedule.startdate, date);
+        assert_eq!(schedule.blackouts[0].start, date + chrono::Days::new(7));
+    }
+}
@@ -0,0 +1,54 @@
+// A C ABI for wasm32, so a web page can run the rotation without
+// bindings: write the inputs into memory from alloc, call schedule, read
+// result_len bytes of JSON at the returned pointer, then dealloc the
+// inputs. The result stays valid until the next call.
+use std::sync::Mutex;
+
+static RESULT: Mutex<String> = Mutex::new(String::new());
+
+#[no_mangle]
+pub extern "C" fn alloc(len: usize) -> *mut u8 {
+    let mut buffer = Vec::<u8>::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    ptr
+}
+
+/// # Safety
+///
+/// `ptr` and `len` have to come from a single call of `alloc`.
+#[no_mangle]
+pub unsafe extern "C" fn dealloc(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+unsafe fn input(ptr: *const u8, len: usize) -> String {
+    String::from_utf8_lossy(std::slice::from_raw_parts(ptr, len)).into_owned()
+}
+
+/// # Safety
+///
+/// Both inputs have to be `len` readable bytes, the config JSON and today
+/// as YYYY-MM-DD.
+#[no_mangle]
+pub unsafe extern "C" fn schedule(
+    config: *const u8,
+    config_len: usize,
+    today: *const u8,
+    today_len: usize,
+    weeks: usize,
+) -> *const u8 {
+    let json = crate::schedule::weeks_json(
+        &input(config, config_len),
+        &input(today, today_len),
+        weeks.min(crate::MAX_WEEKS),
+    );
+    let mut result = RESULT.lock().unwrap_or_else(|e| e.into_inner());
+    *result = json;
+    result.as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn result_len() -> usize {
+    RESULT.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
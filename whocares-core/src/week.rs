@@ -0,0 +1,148 @@
+use chrono::{Datelike, IsoWeek, NaiveDate, Weekday};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+// An ISO week like "2025-W14", validated when parsed. Usable as a map
+// key in place of strings.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Week(NaiveDate);
+
+impl Week {
+    pub fn new(year: i32, week: u32) -> Option<Self> {
+        NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).map(Week)
+    }
+
+    pub fn containing(date: NaiveDate) -> Self {
+        Week(date.week(Weekday::Mon).first_day())
+    }
+
+    pub fn monday(self) -> NaiveDate {
+        self.0
+    }
+
+    pub fn iso_week(self) -> IsoWeek {
+        self.0.iso_week()
+    }
+}
+
+impl From<IsoWeek> for Week {
+    fn from(week: IsoWeek) -> Self {
+        Week::new(week.year(), week.week()).expect("IsoWeek is always valid")
+    }
+}
+
+impl fmt::Display for Week {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let iso = self.iso_week();
+        write!(f, "{}-W{:02}", iso.year(), iso.week())
+    }
+}
+
+impl FromStr for Week {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once("-W")
+            .and_then(|(year, week)| Week::new(year.parse().ok()?, week.parse().ok()?))
+            .ok_or_else(|| format!("invalid week \"{s}\", expected YYYY-Www like 2025-W14"))
+    }
+}
+
+impl Serialize for Week {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Week {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+pub fn start_of_week(date: NaiveDate) -> NaiveDate {
+    date.week(Weekday::Mon).first_day()
+}
+
+// Reschedules are written as ISO weeks ("2025-W01"). The old keys, the
+// calendar year of the Monday and the ISO week ("2024-1"), are still read
+// where they're unambiguous: 2025-W01 starts on 2024-12-30, so its old key
+// would also name the first week of 2024.
+pub fn reschedule_key(week: Week) -> String {
+    week.to_string()
+}
+
+pub fn legacy_reschedule_key(start_of_week: NaiveDate) -> String {
+    format!(
+        "{}-{}",
+        start_of_week.year_ce().1,
+        start_of_week.iso_week().week()
+    )
+}
+
+pub fn iso_week_key(start_of_week: NaiveDate) -> String {
+    Week::containing(start_of_week).to_string()
+}
+
+// Accepts ISO weeks ("2025-W31") as well as reschedule keys ("2025-31").
+pub fn parse_week(s: &str) -> Option<NaiveDate> {
+    let (year, week) = s.split_once('-')?;
+    let year: i32 = year.parse().ok()?;
+    match week.strip_prefix('W') {
+        Some(week) => NaiveDate::from_isoywd_opt(year, week.parse().ok()?, Weekday::Mon),
+        None => {
+            let week: u32 = week.parse().ok()?;
+            [year, year + 1]
+                .into_iter()
+                .filter_map(|y| NaiveDate::from_isoywd_opt(y, week, Weekday::Mon))
+                .find(|monday| legacy_reschedule_key(*monday) == s)
+        }
+    }
+}
+
+// Whether a reschedule key will match its week, see reschedule_key.
+pub fn is_valid_reschedule_key(key: &str) -> bool {
+    match parse_week(key) {
+        Some(monday) if key.contains('W') => key == reschedule_key(Week::containing(monday)),
+        Some(_) => key.split('-').next().is_some_and(|year| year.len() == 4),
+        None => false,
+    }
+}
+
+// Reads the year and the week from the numbers in a key, two digit years
+// are taken as 20xx.
+pub fn probable_week(key: &str) -> Option<Week> {
+    let numbers: Vec<u32> = key
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    let [year, week] = numbers[..] else {
+        return None;
+    };
+    let year = if year < 100 { year + 2000 } else { year };
+    Week::new(year.try_into().ok()?, week)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse_week_works() {
+        let monday = NaiveDate::from_str("2024-12-30").unwrap();
+        assert_eq!(parse_week("2025-W01"), Some(monday));
+        assert_eq!(parse_week("2025-W1"), Some(monday));
+        assert_eq!(
+            parse_week("2024-27"),
+            NaiveDate::from_str("2024-07-01").ok()
+        );
+        assert_eq!(parse_week("2025-W54"), None);
+        assert_eq!(parse_week("2025/07"), None);
+        assert_eq!(iso_week_key(monday), "2025-W01");
+        assert_eq!(legacy_reschedule_key(monday), "2024-1");
+    }
+}